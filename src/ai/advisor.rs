@@ -0,0 +1,225 @@
+use super::backend::{create_backend, LlmBackend, TokenSink};
+use super::context::{DevTidyContext, FolderInfo};
+use super::ollama::quiet_model_by_ram;
+use super::settings::AiSettings;
+use super::verdict::{ask_verdict, Verdict};
+use crate::core::config::{AiProvider, Config};
+use crate::core::models::CleanableItem;
+use crate::services::toolchains::format_age;
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Entries of a directory, or lines of a file, shown to the model
+const SAMPLE_LINES: usize = 15;
+
+/// Model access for the TUI. Unlike the `ai-*` commands it never prints,
+/// starts a daemon or downloads a model, since that would disturb the screen.
+pub struct Advisor {
+  backend: Box<dyn LlmBackend>,
+  model: String,
+}
+
+impl Advisor {
+  /// Connect to an already running model server with the model installed
+  pub async fn connect(model: Option<String>) -> Result<Self> {
+    let config = Config::load()?.ai;
+    let settings = AiSettings::resolve(&config, model);
+    let backend = create_backend(&config, &settings);
+
+    if !backend.health_check().await? {
+      bail!(
+        "No model server answers at {} (start it with `ollama serve`)",
+        settings.base_url.value
+      );
+    }
+
+    let installed = backend.list_models().await?;
+    let model = match (settings.model, config.provider) {
+      (Some(model), _) => model.value,
+      (None, AiProvider::Ollama) => quiet_model_by_ram(),
+      (None, AiProvider::OpenAi) => installed
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow!("The model server does not list any models"))?,
+    };
+    if !installed.iter().any(|name| name.starts_with(&model)) {
+      bail!(
+        "Model '{}' is not installed; run `dd ai-explain` once to download it",
+        model
+      );
+    }
+
+    Ok(Self { backend, model })
+  }
+
+  pub fn model(&self) -> &str {
+    &self.model
+  }
+
+  /// Whether `item` can be deleted, in the model's opinion
+  pub async fn verdict(&self, item: &CleanableItem) -> Result<Verdict> {
+    let folder = folder_info(item);
+    let prompt =
+      DevTidyContext::new().create_suggest_prompt(&folder.name, &folder.size, &folder.details);
+    ask_verdict(self.backend.as_ref(), &self.model, &prompt).await
+  }
+
+  /// Explain `item`, passing the answer to `on_token` as it streams in
+  pub async fn explain(
+    &self,
+    item: &CleanableItem,
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String> {
+    let age = fs::symlink_metadata(&item.path)
+      .and_then(|metadata| metadata.modified())
+      .ok()
+      .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+      .map_or_else(|| "at an unknown time".to_string(), format_age);
+    let prompt = DevTidyContext::new().create_item_explain_prompt(
+      &folder_info(item),
+      &age,
+      &content_sample(&item.path),
+    );
+    self.backend.generate(&self.model, &prompt, on_token).await
+  }
+}
+
+/// The first entries of a directory, or the first lines of a file
+fn content_sample(path: &Path) -> Vec<String> {
+  if path.is_dir() {
+    let Ok(entries) = fs::read_dir(path) else {
+      return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+      .filter_map(Result::ok)
+      .map(|entry| {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+          format!("{}/", name)
+        } else {
+          name
+        }
+      })
+      .collect();
+    names.sort();
+    let more = names.len().saturating_sub(SAMPLE_LINES);
+    names.truncate(SAMPLE_LINES);
+    if more > 0 {
+      names.push(format!("... and {} more", more));
+    }
+    names
+  } else {
+    // Enough for the first lines without reading a large log or lockfile
+    let mut head = Vec::new();
+    if let Ok(file) = fs::File::open(path) {
+      let _ = file.take(4096).read_to_end(&mut head);
+    }
+    String::from_utf8_lossy(&head)
+      .lines()
+      .take(SAMPLE_LINES)
+      .map(|line| line.chars().take(120).collect())
+      .collect()
+  }
+}
+
+/// What the scanner knows about `item`, for the prompts
+pub(super) fn folder_info(item: &CleanableItem) -> FolderInfo {
+  let name = item
+    .path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| item.display_path());
+  let mut details = format!("{} at {}", item.display_info(), item.display_path());
+  if item.orphaned {
+    details.push_str("; the project manifest next to it no longer exists");
+  }
+
+  FolderInfo {
+    name,
+    size: item.display_size(),
+    details,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ai::mock_ollama::MockOllama;
+  use std::path::PathBuf;
+
+  #[tokio::test]
+  async fn explains_the_item_with_its_age_and_contents() {
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("target");
+    fs::create_dir_all(target.join("debug")).unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), "Signature").unwrap();
+    let mock = MockOllama::start().await;
+    mock.generate(&["Cargo build ", "output."]).await;
+    let advisor = Advisor {
+      backend: Box::new(mock.client()),
+      model: "phi".to_string(),
+    };
+    let item = CleanableItem::new(target, "target".to_string(), 4096, "Rust".to_string());
+
+    let mut streamed = Vec::new();
+    let answer = advisor
+      .explain(&item, &mut |token| streamed.push(token.to_string()))
+      .await
+      .unwrap();
+
+    assert_eq!(answer, "Cargo build output.");
+    assert_eq!(streamed, vec!["Cargo build ", "output."]);
+    let prompt = mock.requests("/api/generate").await[0]["prompt"]
+      .as_str()
+      .unwrap()
+      .to_string();
+    assert!(prompt.contains("Item: 'target' (size: 4 KiB, last modified today)"));
+    assert!(prompt.contains("  CACHEDIR.TAG\n  debug/"));
+  }
+
+  #[test]
+  fn samples_the_first_lines_of_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log = tmp.path().join("npm-debug.log");
+    let lines: Vec<String> = (0..40).map(|i| format!("line {}", i)).collect();
+    fs::write(&log, lines.join("\n")).unwrap();
+
+    let sample = content_sample(&log);
+
+    assert_eq!(sample.len(), SAMPLE_LINES);
+    assert_eq!(sample[0], "line 0");
+  }
+
+  #[tokio::test]
+  async fn asks_about_the_item_with_its_scan_details() {
+    let mock = MockOllama::start().await;
+    mock
+      .generate(&[r#"{"safe_to_delete": true, "confidence": 0.9, "reason": "Stale build output", "regenerate_command": "cargo build"}"#])
+      .await;
+    let advisor = Advisor {
+      backend: Box::new(mock.client()),
+      model: "phi".to_string(),
+    };
+    let mut item = CleanableItem::new(
+      PathBuf::from("/work/old-app/target"),
+      "target".to_string(),
+      4096,
+      "Rust build output".to_string(),
+    );
+    item.orphaned = true;
+
+    let verdict = advisor.verdict(&item).await.unwrap();
+
+    assert!(verdict.preselects());
+    let prompt = mock.requests("/api/generate").await[0]["prompt"]
+      .as_str()
+      .unwrap()
+      .to_string();
+    assert!(prompt.contains("Folder: 'target'"));
+    assert!(prompt.contains("Rust build output at /work/old-app/target"));
+    assert!(prompt.contains("manifest next to it no longer exists"));
+  }
+}
@@ -0,0 +1,234 @@
+use super::ollama::OllamaClient;
+use super::openai::OpenAiClient;
+use super::settings::AiSettings;
+use crate::core::config::{AiConfig, AiProvider};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// One message of a chat conversation (`system`, `user`, `assistant` or `tool`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+  pub role: String,
+  pub content: String,
+  /// Tools an assistant message asks to call
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tool_calls: Vec<ToolCall>,
+  /// The call a `tool` message answers
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tool_call: Option<ToolCall>,
+}
+
+/// A tool call requested by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+  /// Set by OpenAI-compatible servers to pair results with calls
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub id: Option<String>,
+  pub name: String,
+  pub arguments: Value,
+}
+
+/// A tool offered to the model, with a JSON schema of its arguments
+#[derive(Debug, Clone)]
+pub struct ToolSpec {
+  pub name: &'static str,
+  pub description: &'static str,
+  pub parameters: Value,
+}
+
+impl ToolSpec {
+  /// The `tools` entry both Ollama and the OpenAI API expect
+  pub fn to_json(&self) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": self.name,
+            "description": self.description,
+            "parameters": self.parameters
+        }
+    })
+  }
+}
+
+impl ChatMessage {
+  fn new(role: &str, content: impl Into<String>) -> Self {
+    Self {
+      role: role.to_string(),
+      content: content.into(),
+      tool_calls: Vec::new(),
+      tool_call: None,
+    }
+  }
+
+  pub fn system(content: impl Into<String>) -> Self {
+    Self::new("system", content)
+  }
+
+  pub fn user(content: impl Into<String>) -> Self {
+    Self::new("user", content)
+  }
+
+  pub fn assistant(content: impl Into<String>) -> Self {
+    Self::new("assistant", content)
+  }
+
+  /// The result of running `call`
+  pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+    Self {
+      tool_call: Some(call.clone()),
+      ..Self::new("tool", content)
+    }
+  }
+}
+
+/// Tool call arguments as an object; some servers send them as a JSON string
+pub fn tool_arguments(arguments: Option<&Value>) -> Value {
+  match arguments {
+    Some(Value::String(text)) => serde_json::from_str(text).unwrap_or_else(|_| json!({})),
+    Some(arguments) => arguments.clone(),
+    None => json!({}),
+  }
+}
+
+/// Receives the response text piece by piece while it streams in
+pub type TokenSink<'a> = dyn FnMut(&str) + Send + 'a;
+
+/// A model server the AI commands talk to: a local Ollama, or anything that
+/// speaks the OpenAI HTTP API (llama.cpp server, vLLM, LM Studio, ...)
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+  /// Provider and endpoint, for status messages
+  fn describe(&self) -> String;
+
+  /// Make sure the server answers, starting it when that is possible
+  async fn ensure_ready(&mut self) -> Result<()>;
+
+  async fn health_check(&self) -> Result<bool>;
+
+  async fn list_models(&self) -> Result<Vec<String>>;
+
+  /// The model to use when none is configured
+  async fn default_model(&self) -> Result<String>;
+
+  /// Make `model` usable, downloading it when the server supports that
+  async fn ensure_model(&self, model: &str) -> Result<()>;
+
+  /// Complete a single prompt, streaming the answer into `on_token`.
+  /// Returns the whole answer.
+  async fn generate(
+    &self,
+    model: &str,
+    prompt: &str,
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String>;
+
+  /// Complete a prompt with a JSON document matching `schema`
+  async fn generate_structured(&self, model: &str, prompt: &str, schema: &Value) -> Result<String>;
+
+  /// Answer the last message of a conversation, streaming like `generate`
+  async fn chat(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String>;
+
+  /// Answer the last message of a conversation, offering `tools`. Not
+  /// streamed: the reply is an assistant message with either text or tool calls.
+  async fn chat_with_tools(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    tools: &[ToolSpec],
+  ) -> Result<ChatMessage>;
+}
+
+/// The backend selected by the `[ai]` section of the config file
+pub fn create_backend(config: &AiConfig, settings: &AiSettings) -> Box<dyn LlmBackend> {
+  match config.provider {
+    AiProvider::Ollama => Box::new(OllamaClient::new(settings)),
+    AiProvider::OpenAi => Box::new(OpenAiClient::new(
+      settings,
+      config
+        .api_key_env
+        .as_deref()
+        .and_then(|name| std::env::var(name).ok()),
+    )),
+  }
+}
+
+/// The server did not start answering within the configured timeout
+#[derive(Debug)]
+pub struct TimedOut(pub Duration);
+
+impl fmt::Display for TimedOut {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "No answer within {}s", self.0.as_secs_f32())
+  }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Send `request`, waiting until `deadline` for the response headers
+pub async fn send_before(
+  request: reqwest::RequestBuilder,
+  deadline: Instant,
+  timeout: Duration,
+) -> Result<reqwest::Response> {
+  match tokio::time::timeout_at(deadline, request.send()).await {
+    Ok(response) => Ok(response?),
+    Err(_) => Err(TimedOut(timeout).into()),
+  }
+}
+
+/// Feed every non-empty line of a streaming response body to `handle`, which
+/// returns `false` to stop reading. Both Ollama (NDJSON) and OpenAI-compatible
+/// servers (server-sent events) stream one JSON document per line.
+///
+/// With a `first_chunk` deadline, the body must start before it; once text
+/// flows, a long answer is read to the end however long it takes.
+pub async fn for_each_line(
+  response: reqwest::Response,
+  first_chunk: Option<(Instant, Duration)>,
+  mut handle: impl FnMut(&str) -> Result<bool>,
+) -> Result<()> {
+  use futures_util::StreamExt;
+
+  let mut stream = response.bytes_stream();
+  let mut buffer = Vec::new();
+
+  if let Some((deadline, timeout)) = first_chunk {
+    match tokio::time::timeout_at(deadline, stream.next()).await {
+      Ok(Some(chunk)) => buffer.extend_from_slice(&chunk?),
+      Ok(None) => {}
+      Err(_) => return Err(TimedOut(timeout).into()),
+    }
+  }
+
+  loop {
+    while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
+      let line_bytes = buffer.drain(..=newline_pos).collect::<Vec<u8>>();
+      let line = String::from_utf8_lossy(&line_bytes[..line_bytes.len() - 1]);
+      if !line.trim().is_empty() && !handle(line.trim())? {
+        return Ok(());
+      }
+    }
+
+    match stream.next().await {
+      Some(chunk) => buffer.extend_from_slice(&chunk?),
+      None => break,
+    }
+  }
+
+  // The last line may come without a trailing newline
+  let rest = String::from_utf8_lossy(&buffer);
+  if !rest.trim().is_empty() {
+    handle(rest.trim())?;
+  }
+  Ok(())
+}
@@ -0,0 +1,226 @@
+use crate::core::constants::ECOSYSTEM_MANIFESTS;
+use crate::utils::hash::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long an answer is reused when the config sets no `cache_ttl_days`
+pub const DEFAULT_TTL_DAYS: u64 = 7;
+
+/// What identifies an answer: the same question about an equivalent item,
+/// asked of the same model with the same prompt template
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+  /// Which prompt, e.g. "explain"
+  pub kind: String,
+  pub model: String,
+  /// `PROMPT_VERSION` of the template the answer was given to
+  pub prompt_version: u32,
+  /// `DevTidyContext::app_context_hash` of the prompt
+  pub app_context: u64,
+  pub fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+  key: CacheKey,
+  /// Seconds since the epoch
+  created: u64,
+  answer: String,
+}
+
+/// Model answers saved on disk, one file per key
+pub struct ResponseCache {
+  dir: PathBuf,
+  ttl: Duration,
+}
+
+impl ResponseCache {
+  /// The cache under the user's cache directory, keeping answers for `ttl_days`
+  pub fn open(ttl_days: Option<u64>) -> Option<Self> {
+    let ttl_days = ttl_days.unwrap_or(DEFAULT_TTL_DAYS);
+    dirs::cache_dir().map(|dir| Self::in_dir(dir.join("devtidy").join("ai"), days(ttl_days)))
+  }
+
+  pub fn in_dir(dir: PathBuf, ttl: Duration) -> Self {
+    Self { dir, ttl }
+  }
+
+  /// The answer saved for `key`, unless it expired
+  pub fn get(&self, key: &CacheKey) -> Option<String> {
+    let contents = fs::read_to_string(self.path(key)).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&contents).ok()?;
+    let age = now().saturating_sub(entry.created);
+    (entry.key == *key && age < self.ttl.as_secs()).then_some(entry.answer)
+  }
+
+  /// Save `answer` for `key`, removing expired answers first
+  pub fn put(&self, key: &CacheKey, answer: &str) -> io::Result<()> {
+    fs::create_dir_all(&self.dir)?;
+    self.prune();
+    let entry = CacheEntry {
+      key: key.clone(),
+      created: now(),
+      answer: answer.to_string(),
+    };
+
+    // Write to a temporary file first so a crash never leaves a truncated entry
+    let path = self.path(key);
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(&entry)?)?;
+    fs::rename(tmp, path)
+  }
+
+  /// Delete entries written longer than the TTL ago. An entry is never
+  /// rewritten, so its modification time is when it was created.
+  fn prune(&self) {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+      let expired = entry
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= self.ttl);
+      if expired {
+        // Another process may have removed it already
+        let _ = fs::remove_file(entry.path());
+      }
+    }
+  }
+
+  /// File of the entry for `key`, named by a hash that is stable across
+  /// builds so answers survive an upgrade
+  fn path(&self, key: &CacheKey) -> PathBuf {
+    let key = serde_json::to_vec(key).unwrap_or_default();
+    self.dir.join(format!("{:016x}.json", fnv1a(&key)))
+  }
+}
+
+/// What an answer about the item at `path` depends on: its name, the project
+/// manifests next to it and the order of magnitude of its size. `None` for
+/// prompts that do not mention the size.
+pub fn fingerprint(path: &Path, size: Option<u64>) -> String {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let markers: Vec<&str> = match path.parent() {
+    Some(project) => ECOSYSTEM_MANIFESTS
+      .iter()
+      .map(|(manifest, _)| *manifest)
+      .filter(|manifest| project.join(manifest).exists())
+      .collect(),
+    None => Vec::new(),
+  };
+  let size_bucket = match size {
+    Some(size) => format!("1e{}", size.checked_ilog10().unwrap_or(0)),
+    None => "-".to_string(),
+  };
+  format!("{}|{}|{}", name, markers.join(","), size_bucket)
+}
+
+fn days(days: u64) -> Duration {
+  Duration::from_secs(days * 24 * 60 * 60)
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_secs())
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(model: &str, fingerprint: &str) -> CacheKey {
+    CacheKey {
+      kind: "explain".to_string(),
+      model: model.to_string(),
+      prompt_version: 1,
+      app_context: 7,
+      fingerprint: fingerprint.to_string(),
+    }
+  }
+
+  #[test]
+  fn reuses_answers_for_the_same_key_until_they_expire() {
+    let tmp = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::in_dir(tmp.path().join("ai"), days(1));
+
+    cache
+      .put(&key("phi", "node_modules"), "npm packages")
+      .unwrap();
+
+    assert_eq!(
+      cache.get(&key("phi", "node_modules")),
+      Some("npm packages".to_string())
+    );
+    assert_eq!(cache.get(&key("gemma:2b", "node_modules")), None);
+    assert_eq!(cache.get(&key("phi", "target")), None);
+
+    let expired = ResponseCache::in_dir(tmp.path().join("ai"), Duration::ZERO);
+    assert_eq!(expired.get(&key("phi", "node_modules")), None);
+  }
+
+  #[test]
+  fn prunes_expired_answers_when_saving() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("ai");
+    ResponseCache::in_dir(dir.clone(), days(1))
+      .put(&key("phi", "node_modules"), "npm packages")
+      .unwrap();
+
+    let expiring = ResponseCache::in_dir(dir.clone(), Duration::ZERO);
+    expiring.put(&key("phi", "target"), "build output").unwrap();
+
+    assert!(!expiring.path(&key("phi", "node_modules")).exists());
+    assert!(expiring.path(&key("phi", "target")).exists());
+  }
+
+  #[test]
+  fn entry_names_do_not_depend_on_the_std_hasher() {
+    let cache = ResponseCache::in_dir(PathBuf::from("/cache"), days(1));
+
+    assert_eq!(
+      cache.path(&key("phi", "node_modules")),
+      PathBuf::from("/cache/bb07ad282a36b0c6.json")
+    );
+  }
+
+  #[test]
+  fn fingerprints_name_markers_and_size_bucket() {
+    let tmp = tempfile::tempdir().unwrap();
+    let web = tmp.path().join("web");
+    let api = tmp.path().join("api");
+    fs::create_dir_all(&web).unwrap();
+    fs::create_dir_all(&api).unwrap();
+    fs::write(web.join("package.json"), "{}").unwrap();
+    fs::write(api.join("Cargo.toml"), "").unwrap();
+    fs::write(api.join("package.json"), "{}").unwrap();
+
+    let modules = web.join("node_modules");
+    assert_eq!(
+      fingerprint(&modules, Some(250_000_000)),
+      "node_modules|package.json|1e8"
+    );
+    assert_eq!(
+      fingerprint(&modules, Some(300_000_000)),
+      fingerprint(&modules, Some(250_000_000))
+    );
+    assert_eq!(
+      fingerprint(&api.join("target"), None),
+      "target|package.json,Cargo.toml|-"
+    );
+    assert_eq!(
+      fingerprint(&modules, Some(0)),
+      "node_modules|package.json|1e0"
+    );
+  }
+}
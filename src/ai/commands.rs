@@ -1,134 +1,311 @@
 use super::{
-  context::DevTidyContext,
-  ollama::{ensure_model_available, select_model_by_ram, OllamaClient},
+  advisor::folder_info,
+  backend::{create_backend, ChatMessage, LlmBackend, TimedOut, ToolCall},
+  cache::{fingerprint, CacheKey, ResponseCache},
+  context::{DevTidyContext, FolderInfo, PROMPT_VERSION},
+  ollama::{select_model_by_ram, OllamaClient},
+  report::{build_report, group_by_project, is_html_path, render_html, render_markdown},
+  session::{list_sessions, sessions_dir, ChatSession, DEFAULT_SESSION},
+  settings::AiSettings,
+  tools::{self, Tool},
   utils::*,
+  verdict::{ask_verdicts, Verdict, PRESELECT_CONFIDENCE},
 };
-use crate::core::constants::CLEANABLE_PATTERNS;
-use anyhow::Result;
+use crate::core::config::{AiProvider, Config};
+use crate::core::models::CleanableItem;
+use crate::services::cleaner::{clean_headless, CleanOptions};
+use crate::services::scanner::{outermost_items, scan_with_sizes, ScanOptions};
+use anyhow::{Context, Result};
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 /// Handle the ai-explain command
-pub async fn handle_ai_explain(path_arg: Option<String>) -> Result<()> {
-  // Check Ollama availability first
-  if !check_ollama_installation() {
-    handle_ollama_not_available().await?;
+pub async fn handle_ai_explain(
+  path_arg: Option<String>,
+  model: Option<String>,
+  no_cache: bool,
+) -> Result<()> {
+  let cache = response_cache(no_cache)?;
+  let Some((backend, model)) = connect(model).await? else {
     return Ok(());
-  }
-
-  let mut client = OllamaClient::new();
-
-  // Automatically start Ollama if not running
-  client.ensure_running().await?;
+  };
 
-  // Show status info
-  if client.has_spawned_process() {
-    println!("Started Ollama daemon for this session");
-  }
+  // The backend is dropped afterwards, cleaning up a spawned Ollama daemon
+  explain(backend.as_ref(), &model, path_arg, cache.as_ref()).await
+}
 
+async fn explain(
+  backend: &dyn LlmBackend,
+  model: &str,
+  path_arg: Option<String>,
+  cache: Option<&ResponseCache>,
+) -> Result<()> {
   // Resolve target path
   let target_path = resolve_target_path(path_arg)?;
   let folder_name = get_folder_display_name(&target_path);
 
-  // Select appropriate model
-  let model = select_model_by_ram();
-  ensure_model_available(&client, &model).await?;
-
   // Create context-aware prompt
   let context = DevTidyContext::new();
   let prompt = context.create_explain_prompt(&folder_name);
 
+  // The prompt only names the folder, so its size does not change the answer
+  let key = CacheKey {
+    kind: "explain".to_string(),
+    model: model.to_string(),
+    prompt_version: PROMPT_VERSION,
+    app_context: DevTidyContext::new().app_context_hash(),
+    fingerprint: fingerprint(&target_path, None),
+  };
+
   // Generate response with streaming
   println!("\nAI Explanation for '{}':", folder_name);
   print!("   ");
 
-  match client.generate_with_format(&model, &prompt, false).await {
-    Ok(_) => {
+  if let Some(answer) = cache.and_then(|cache| cache.get(&key)) {
+    println!(
+      "{}\n   (cached answer; --no-cache asks the model again)\n",
+      answer
+    );
+    return Ok(());
+  }
+
+  match stream_response(backend, model, Request::Prompt(&prompt)).await {
+    Ok(answer) => {
       // Response was printed via streaming
       println!("\n");
+      if let Some(cache) = cache {
+        // A failed write only costs a model call next time
+        let _ = cache.put(&key, &answer);
+      }
     }
     Err(e) => {
       println!("ERROR: {}\n", e);
     }
   }
 
-  // Client will be dropped here, automatically cleaning up spawned processes
   Ok(())
 }
 
+/// How `ai-suggest` acts on the verdicts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SuggestOptions {
+  /// Offer to remove the folders the model is confident are safe to delete
+  pub clean: bool,
+  /// Remove them without asking
+  pub yes: bool,
+  pub jobs: usize,
+  /// Ask the model about every folder instead of reusing cached verdicts
+  pub no_cache: bool,
+}
+
 /// Handle the ai-suggest command
-pub async fn handle_ai_suggest() -> Result<()> {
-  // Check Ollama availability first
-  if !check_ollama_installation() {
-    handle_ollama_not_available().await?;
+pub async fn handle_ai_suggest(
+  model: Option<String>,
+  roots: Vec<PathBuf>,
+  scan_options: ScanOptions,
+  options: SuggestOptions,
+) -> Result<()> {
+  let cache = response_cache(options.no_cache)?;
+  let Some((backend, model)) = connect(model).await? else {
     return Ok(());
+  };
+
+  let candidates = suggest(
+    backend.as_ref(),
+    &model,
+    &roots,
+    &scan_options,
+    cache.as_ref(),
+  )
+  .await?;
+  if options.clean {
+    clean_candidates(candidates, options).await?;
   }
+  Ok(())
+}
 
-  let mut client = OllamaClient::new();
+/// Ask for verdicts on everything a scan of `roots` finds, the same items the
+/// TUI shows, with one prompt per project. Verdicts on equivalent folders are
+/// reused from `cache`, and only the other folders are asked about. Returns the
+/// items the verdicts preselect.
+async fn suggest(
+  backend: &dyn LlmBackend,
+  model: &str,
+  roots: &[PathBuf],
+  scan_options: &ScanOptions,
+  cache: Option<&ResponseCache>,
+) -> Result<Vec<CleanableItem>> {
+  let spinner = create_spinner("Scanning for cleanable folders", "cyan");
+  let items = scan_with_sizes(roots, scan_options);
+  spinner.finish_and_clear();
+
+  if items.is_empty() {
+    println!("No cleanable folders found.");
+    return Ok(Vec::new());
+  }
 
-  // Automatically start Ollama if not running
-  client.ensure_running().await?;
+  let projects = group_by_project(items);
 
-  // Show status info
-  if client.has_spawned_process() {
-    println!("Started Ollama daemon for this session");
-  }
+  println!("\nAI Suggestions for {} projects:\n", projects.len());
 
-  // Get current directory
-  let current_dir = std::env::current_dir()?;
+  let context = DevTidyContext::new();
+  let mut candidates = Vec::new();
+
+  for (project, items) in projects {
+    let folders: Vec<FolderInfo> = items.iter().map(folder_info).collect();
+    let mut verdicts: HashMap<String, Verdict> = HashMap::new();
+    let mut uncached: Vec<(FolderInfo, CacheKey)> = Vec::new();
+    for (item, folder) in items.iter().zip(&folders) {
+      let key = verdict_key(model, item);
+      let cached = cache
+        .and_then(|cache| cache.get(&key))
+        .and_then(|answer| serde_json::from_str::<Verdict>(&answer).ok());
+      match cached {
+        Some(verdict) => {
+          verdicts.insert(folder.name.clone(), verdict);
+        }
+        None => uncached.push((folder_info(item), key)),
+      }
+    }
 
-  // Find known cleanable folders
-  let mut found_folders = Vec::new();
+    println!("Project: {}", project.display());
+    if !uncached.is_empty() {
+      let (asked, keys): (Vec<FolderInfo>, Vec<CacheKey>) = uncached.into_iter().unzip();
+      let prompt = context.create_project_suggest_prompt(&project.display().to_string(), &asked);
+      let spinner = create_spinner(&format!("Asking {}", model), "cyan");
+      let answer = ask_verdicts(backend, model, &prompt).await;
+      spinner.finish_and_clear();
+
+      match answer {
+        Ok(answer) => {
+          if let Some(cache) = cache {
+            for (folder, key) in asked.iter().zip(&keys) {
+              if let Some(verdict) = answer.get(&folder.name) {
+                // A failed write only costs a model call next time
+                let _ = cache.put(key, &serde_json::to_string(verdict)?);
+              }
+            }
+          }
+          verdicts.extend(answer);
+        }
+        Err(e) => {
+          println!("   Could not read verdicts: {}\n", e);
+          if verdicts.is_empty() {
+            continue;
+          }
+        }
+      }
+    }
 
-  for (pattern, description) in CLEANABLE_PATTERNS.iter() {
-    if !pattern.contains('*') {
-      // Only check exact folder names
-      let potential_path = current_dir.join(pattern);
-      if potential_path.exists() && potential_path.is_dir() {
-        let size = calculate_folder_size(&potential_path);
-        if size > 0 {
-          found_folders.push((pattern.to_string(), size, description.to_string()));
+    for (item, folder) in items.into_iter().zip(folders) {
+      println!(" {} ({}):", folder.name, folder.size);
+      match verdicts.get(&folder.name) {
+        Some(verdict) => {
+          print_verdict(verdict);
+          if verdict.preselects() && !item.protected {
+            candidates.push(item);
+          }
         }
+        None => println!("   No verdict for this folder\n"),
       }
     }
   }
 
-  if found_folders.is_empty() {
-    println!("No known cleanable folders found in the current directory.");
-    return Ok(());
-  }
+  Ok(candidates)
+}
 
-  // Select appropriate model
-  let model = select_model_by_ram();
-  ensure_model_available(&client, &model).await?;
+/// Cache key of the verdict on `item`, shared by folders with the same name,
+/// project manifests and order of magnitude of size
+fn verdict_key(model: &str, item: &CleanableItem) -> CacheKey {
+  CacheKey {
+    kind: "verdict".to_string(),
+    model: model.to_string(),
+    prompt_version: PROMPT_VERSION,
+    app_context: DevTidyContext::new().app_context_hash(),
+    fingerprint: fingerprint(&item.path, Some(item.size)),
+  }
+}
 
-  println!("\nAI Suggestions for cleanable folders:\n");
+/// The response cache, unless `--no-cache` turned it off
+fn response_cache(no_cache: bool) -> Result<Option<ResponseCache>> {
+  if no_cache {
+    return Ok(None);
+  }
+  Ok(ResponseCache::open(Config::load()?.ai.cache_ttl_days))
+}
 
-  // Process each folder with context
-  let context = DevTidyContext::new();
+fn print_verdict(verdict: &Verdict) {
+  let answer = if verdict.safe_to_delete {
+    "Safe to delete"
+  } else {
+    "Keep"
+  };
+  println!(
+    "   {} ({:.0}% confident): {}",
+    answer,
+    verdict.confidence * 100.0,
+    verdict.reason
+  );
+  if let Some(command) = &verdict.regenerate_command {
+    println!("   Regenerate with: {}", command);
+  }
+  println!();
+}
 
-  for (folder, size, _description) in found_folders {
-    let size_str = format_size(size);
-    let prompt = context.create_suggest_prompt(&folder, &size_str);
+/// Remove the folders the model is confident about, after confirmation
+async fn clean_candidates(candidates: Vec<CleanableItem>, options: SuggestOptions) -> Result<()> {
+  if candidates.is_empty() {
+    println!(
+      "Nothing to clean: no folder is safe to delete with at least {:.0}% confidence.",
+      PRESELECT_CONFIDENCE * 100.0
+    );
+    return Ok(());
+  }
 
-    println!("Folder: {} ({}):", folder, size_str);
-    print!("   ");
+  // Removing a folder removes everything inside it
+  let candidates: Vec<CleanableItem> = outermost_items(&candidates).into_iter().cloned().collect();
+  let total: u64 = candidates.iter().map(|item| item.size).sum();
+  for item in &candidates {
+    println!("{:>10}  {}", item.display_size(), item.display_path());
+  }
 
-    match client.generate_with_format(&model, &prompt, false).await {
-      Ok(_) => {
-        // Response was printed via streaming
-        println!("\n");
-      }
-      Err(e) => {
-        println!("ERROR: {}\n", e);
-      }
+  if !options.yes {
+    print!(
+      "Remove {} folders ({})? [y/N] ",
+      candidates.len(),
+      format_size(total)
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+      println!("Aborted.");
+      return Ok(());
     }
   }
 
+  let clean_options = CleanOptions {
+    concurrency: options.jobs,
+    native: false,
+  };
+  let (freed, failed) = clean_headless(&candidates, clean_options).await;
+
+  println!("\nFreed {}", format_size(freed));
+  if failed > 0 {
+    anyhow::bail!("{} folders could not be removed", failed);
+  }
   Ok(())
 }
 
+/// Items of a `/scan` that are listed to the model, largest first
+const SCAN_LISTING_ITEMS: usize = 40;
+/// Tool calls the agent may make for one question before it has to answer
+const MAX_TOOL_CALLS: usize = 12;
+
 /// Test context-aware AI functionality
 pub async fn handle_ai_test_context() -> Result<()> {
   println!("Testing DevTidy context-aware AI...\n");
@@ -166,19 +343,30 @@ pub async fn handle_ai_test_context() -> Result<()> {
 }
 
 /// Handle AI diagnostics command
-pub async fn handle_ai_diagnose() -> Result<()> {
+pub async fn handle_ai_diagnose(model: Option<String>) -> Result<()> {
   println!("Running AI system diagnostics...\n");
 
-  // Check Ollama installation
-  if !check_ollama_installation() {
-    println!("ISSUE: Ollama is not installed");
-    println!("SOLUTION: Install Ollama from https://ollama.com/download");
-    return Ok(());
+  let config = Config::load()?.ai;
+  let settings = AiSettings::resolve(&config, model);
+  print_settings(&settings);
+
+  if config.provider != AiProvider::Ollama {
+    return diagnose_backend(create_backend(&config, &settings)).await;
   }
-  println!("Ollama is installed");
 
   // Check Ollama service
-  let mut client = OllamaClient::new();
+  let mut client = OllamaClient::new(&settings);
+
+  // Check Ollama installation (a remote daemon does not need one)
+  if client.is_local() {
+    if !check_ollama_installation() {
+      println!("ISSUE: Ollama is not installed");
+      println!("SOLUTION: Install Ollama from https://ollama.com/download");
+      return Ok(());
+    }
+    println!("Ollama is installed");
+  }
+
   match client.detailed_health_check().await {
     Ok(status) => println!("{}", status),
     Err(e) => {
@@ -188,16 +376,16 @@ pub async fn handle_ai_diagnose() -> Result<()> {
     }
   }
 
-  // Check system resources
-  let selected_model = select_model_by_ram();
-  println!("Selected model: {}", selected_model);
-
   // Test basic connectivity
   match client.ensure_running().await {
     Ok(_) => println!("Ollama daemon is responsive"),
     Err(e) => {
       println!("ISSUE: Failed to start/connect to Ollama: {}", e);
-      println!("SOLUTION: Check if port 11434 is available and Ollama has permissions");
+      if client.is_local() {
+        println!("SOLUTION: Check if the port is available and Ollama has permissions");
+      } else {
+        println!("SOLUTION: Check that the daemon at OLLAMA_HOST or base_url is reachable");
+      }
       return Ok(());
     }
   }
@@ -206,73 +394,691 @@ pub async fn handle_ai_diagnose() -> Result<()> {
   Ok(())
 }
 
-/// Handle the ai-chat command (interactive REPL)
-pub async fn handle_ai_chat() -> Result<()> {
-  // Check Ollama availability first
-  if !check_ollama_installation() {
-    handle_ollama_not_available().await?;
+/// Print the effective AI settings and where each value came from
+fn print_settings(settings: &AiSettings) {
+  println!("Effective AI configuration:");
+  let row = |name: &str, value: String, source: String| {
+    println!("  {:<16}{:<36}({})", name, value, source);
+  };
+
+  let provider = match settings.provider.value {
+    AiProvider::Ollama => "ollama",
+    AiProvider::OpenAi => "openai",
+  };
+  row(
+    "provider",
+    provider.to_string(),
+    settings.provider.source.to_string(),
+  );
+  row(
+    "base_url",
+    settings.base_url.value.clone(),
+    settings.base_url.source.to_string(),
+  );
+  match (&settings.model, settings.provider.value) {
+    (Some(model), _) => row("model", model.value.clone(), model.source.to_string()),
+    (None, AiProvider::Ollama) => row(
+      "model",
+      select_model_by_ram(),
+      "picked for this hardware".to_string(),
+    ),
+    (None, AiProvider::OpenAi) => row(
+      "model",
+      "first model listed".to_string(),
+      "default".to_string(),
+    ),
+  }
+  row(
+    "temperature",
+    settings.temperature.value.to_string(),
+    settings.temperature.source.to_string(),
+  );
+  match &settings.context_window {
+    Some(size) => row(
+      "context_window",
+      format!("{} tokens", size.value),
+      size.source.to_string(),
+    ),
+    None => row(
+      "context_window",
+      "server default".to_string(),
+      "default".to_string(),
+    ),
+  }
+  row(
+    "max_tokens",
+    format!("{} tokens", settings.max_tokens.value),
+    settings.max_tokens.source.to_string(),
+  );
+  row(
+    "timeout",
+    format!("{}s", settings.timeout.value.as_secs()),
+    settings.timeout.source.to_string(),
+  );
+  println!();
+}
+
+/// Diagnostics for a configured non-Ollama backend
+async fn diagnose_backend(backend: Box<dyn LlmBackend>) -> Result<()> {
+  println!("Backend: {}", backend.describe());
+
+  if !backend.health_check().await? {
+    println!("ISSUE: The server is not responding");
+    println!("SOLUTION: Start the server or fix base_url in the [ai] section of the config file");
     return Ok(());
   }
 
-  let mut client = OllamaClient::new();
+  match backend.list_models().await {
+    Ok(models) if models.is_empty() => {
+      println!("ISSUE: The server does not list any models");
+      return Ok(());
+    }
+    Ok(models) => println!("Available models: {}", models.join(", ")),
+    Err(e) => {
+      println!("ISSUE: {}", e);
+      println!("SOLUTION: Check api_key_env in the [ai] section of the config file");
+      return Ok(());
+    }
+  }
+
+  println!("\nAll diagnostics passed! AI features should work properly.");
+  Ok(())
+}
 
-  // Automatically start Ollama if not running
-  client.ensure_running().await?;
+/// Where `ai-report` writes the report
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+  /// File to write; standard output if not given
+  pub output: Option<PathBuf>,
+  /// Write HTML instead of Markdown; implied by an `.html` output file
+  pub html: bool,
+}
+
+/// Prompt characters per token of context window. Models average about four
+/// characters per token; half of that leaves room for the answer.
+const PROMPT_CHARS_PER_TOKEN: usize = 2;
+/// Ollama's context window when none is configured
+const DEFAULT_CONTEXT_WINDOW: u32 = 2048;
+
+/// Most characters a prompt may have to fit the configured context window
+fn prompt_budget() -> Result<usize> {
+  let context_window = Config::load()?
+    .ai
+    .context_window
+    .unwrap_or(DEFAULT_CONTEXT_WINDOW);
+  Ok(context_window as usize * PROMPT_CHARS_PER_TOKEN)
+}
+
+/// Handle the ai-report command
+pub async fn handle_ai_report(
+  model: Option<String>,
+  roots: Vec<PathBuf>,
+  scan_options: ScanOptions,
+  options: ReportOptions,
+) -> Result<()> {
+  let budget = prompt_budget()?;
+  let Some((backend, model)) = connect(model).await? else {
+    return Ok(());
+  };
+
+  let spinner = create_spinner("Scanning for cleanable folders", "cyan");
+  let items = scan_with_sizes(&roots, &scan_options);
+  spinner.finish_and_clear();
 
-  // Show status info
-  if client.has_spawned_process() {
-    println!("Started Ollama daemon for this session");
+  if items.is_empty() {
+    println!("No cleanable folders found.");
+    return Ok(());
   }
 
-  // Select appropriate model
-  let model = select_model_by_ram();
-  ensure_model_available(&client, &model).await?;
+  let report = build_report(backend.as_ref(), &model, &roots, items, budget).await?;
 
-  println!("\nAI Chat Mode - Ask questions about folders and cleaning!");
-  println!("Model: {}", model);
-  println!("Type 'exit' or 'quit' to end the session\n");
+  let html = options.html || options.output.as_deref().is_some_and(is_html_path);
+  let document = if html {
+    render_html(&report)
+  } else {
+    render_markdown(&report)
+  };
+  match options.output {
+    Some(path) => {
+      fs::write(&path, document).with_context(|| format!("Cannot write {}", path.display()))?;
+      println!("Report written to {}", path.display());
+    }
+    None => print!("{}", document),
+  }
+  Ok(())
+}
 
-  // Initialize conversation context
-  let mut context = DevTidyContext::new();
+/// Handle the ai-chat command (interactive REPL)
+pub async fn handle_ai_chat(
+  model: Option<String>,
+  session: Option<String>,
+  tools: bool,
+  roots: Vec<PathBuf>,
+  scan_options: ScanOptions,
+) -> Result<()> {
+  let prompt_budget = prompt_budget()?;
+  let Some((backend, model)) = connect(model).await? else {
+    return Ok(());
+  };
 
-  loop {
-    print!("Dev > ");
-    io::stdout().flush()?;
+  let store = sessions_dir();
+  if store.is_none() {
+    println!("WARNING: No data directory on this system; the chat will not be saved");
+  }
+  let chat = Chat {
+    backend: backend.as_ref(),
+    model,
+    store: store.as_deref(),
+    tools,
+    roots,
+    scan_options,
+    prompt_budget,
+  };
+  chat
+    .run(
+      session.as_deref().unwrap_or(DEFAULT_SESSION),
+      io::stdin().lock(),
+    )
+    .await
+}
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-    let input = input.trim();
+/// An `ai-chat` session: the conversation is sent with its roles through the
+/// chat endpoint and saved after every answer
+struct Chat<'a> {
+  backend: &'a dyn LlmBackend,
+  model: String,
+  /// Where sessions are saved; `None` keeps them in memory
+  store: Option<&'a Path>,
+  /// Let the model inspect the filesystem with the read-only tools
+  tools: bool,
+  /// Directories the tools may measure and scan without asking
+  roots: Vec<PathBuf>,
+  /// For `/scan` and the scan tool
+  scan_options: ScanOptions,
+  /// Most characters sent with a question; older messages beyond it are left
+  /// out so the conversation fits the context window
+  prompt_budget: usize,
+}
+
+impl Chat<'_> {
+  /// Resume the session `name` and read questions from `reader` until `exit`
+  /// or end of input
+  async fn run(mut self, name: &str, mut reader: impl BufRead) -> Result<()> {
+    let mut session = match self.store {
+      Some(dir) => ChatSession::load_from(dir, name)?.unwrap_or_else(|| ChatSession::new(name)),
+      None => ChatSession::new(name),
+    };
 
-    if input.is_empty() {
-      continue;
+    println!("\nAI Chat Mode - Ask questions about folders and cleaning!");
+    println!("Model: {}", self.model);
+    if self.tools {
+      println!(
+        "Tools: the model can look at the filesystem; reading files or running git needs your OK"
+      );
     }
+    if !session.messages.is_empty() {
+      println!(
+        "Resumed session '{}' ({} messages); /clear starts over",
+        session.name,
+        session.messages.len()
+      );
+    }
+    println!("Type /help for commands, 'exit' or 'quit' to end the session\n");
+
+    loop {
+      print!("Dev > ");
+      io::stdout().flush()?;
 
-    if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
-      println!("Goodbye!");
-      if client.has_spawned_process() {
-        println!("Cleaning up Ollama daemon...");
+      let mut line = String::new();
+      if reader.read_line(&mut line)? == 0 {
+        println!();
+        break;
+      }
+      let input = line.trim();
+
+      if input.is_empty() {
+        continue;
+      }
+
+      if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
+        println!("Goodbye!");
+        break;
+      }
+
+      match input.strip_prefix('/') {
+        Some(command) => {
+          if let Err(e) = self.command(&mut session, command, &mut reader).await {
+            println!("ERROR: {}\n", e);
+          }
+        }
+        None => self.ask(&mut session, input.to_string(), &mut reader).await,
       }
-      break;
     }
 
-    // Create context-aware prompt
-    let prompt = context.create_chat_prompt(input);
+    Ok(())
+  }
 
-    // Generate response with streaming
-    print!("AI > ");
-    match client.generate_with_format(&model, &prompt, false).await {
+  /// Send `question` with the conversation so far and stream the answer.
+  /// With tools, the model may first inspect the filesystem; `reader` answers
+  /// the confirmations.
+  async fn ask(&self, session: &mut ChatSession, question: String, reader: &mut dyn BufRead) {
+    session.messages.push(ChatMessage::user(question));
+    let context = DevTidyContext::new();
+    let system_prompt = if self.tools {
+      context.create_agent_system_prompt()
+    } else {
+      context.create_chat_system_prompt()
+    };
+    let messages = recent_history(
+      ChatMessage::system(system_prompt),
+      &session.messages,
+      self.prompt_budget,
+    );
+
+    let answer = if self.tools {
+      let mut confirm = |call: &ToolCall| confirm_tool_call(call, &mut *reader);
+      run_agent(
+        self.backend,
+        &self.model,
+        messages,
+        &self.roots,
+        &self.scan_options,
+        &mut confirm,
+      )
+      .await
+      .inspect(|answer| print!("AI > {}", answer))
+    } else {
+      print!("AI > ");
+      stream_response(self.backend, &self.model, Request::Chat(&messages)).await
+    };
+
+    // Only the question and the answer are kept; tool calls are not replayed
+    match answer {
       Ok(response) => {
-        // Add to conversation history
-        context.add_exchange(input.to_string(), response.clone());
+        session.messages.push(ChatMessage::assistant(response));
         println!("\n");
+        self.autosave(session);
       }
       Err(e) => {
+        // Drop the question so the conversation keeps alternating
+        session.messages.pop();
         println!("\nERROR: Error generating response: {}\n", e);
       }
     }
   }
 
-  Ok(())
+  async fn command(
+    &mut self,
+    session: &mut ChatSession,
+    command: &str,
+    reader: &mut dyn BufRead,
+  ) -> Result<()> {
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+      Some((name, argument)) => (name, Some(argument.trim()).filter(|a| !a.is_empty())),
+      None => (command, None),
+    };
+
+    match (name, argument) {
+      ("help", _) => print_chat_help(),
+      ("save", name) => {
+        let store = self.store_dir()?;
+        let previous = session.name.clone();
+        if let Some(name) = name {
+          session.name = name.to_string();
+        }
+        session.model = Some(self.model.clone());
+        // A name that cannot be saved must not redirect later autosaves
+        let path = session
+          .save_to(store)
+          .inspect_err(|_| session.name = previous)?;
+        println!("Saved session '{}' to {}\n", session.name, path.display());
+      }
+      ("load", None) => {
+        let sessions = list_sessions(self.store_dir()?);
+        if sessions.is_empty() {
+          println!("No saved sessions\n");
+        } else {
+          println!("Saved sessions: {}\n", sessions.join(", "));
+        }
+      }
+      ("load", Some(name)) => {
+        let loaded = ChatSession::load_from(self.store_dir()?, name)?
+          .ok_or_else(|| anyhow::anyhow!("No saved session '{}'; /load lists them", name))?;
+        *session = loaded;
+        println!(
+          "Loaded session '{}' ({} messages)",
+          session.name,
+          session.messages.len()
+        );
+        match &session.model {
+          Some(model) if *model != self.model => {
+            println!("It was held with {}; /model {} switches back", model, model)
+          }
+          _ => {}
+        }
+        println!();
+      }
+      ("clear", _) => {
+        session.messages.clear();
+        self.autosave(session);
+        println!("Cleared session '{}'\n", session.name);
+      }
+      ("model", None) => {
+        println!("Model: {}", self.model);
+        if let Ok(models) = self.backend.list_models().await {
+          println!("Available: {}", models.join(", "));
+        }
+        println!();
+      }
+      ("model", Some(model)) => {
+        self.backend.ensure_model(model).await?;
+        self.model = model.to_string();
+        println!("Now chatting with {}\n", self.model);
+      }
+      ("scan", path) => {
+        let path = resolve_target_path(path.map(str::to_string))?;
+        let spinner = create_spinner(&format!("Scanning {}", path.display()), "cyan");
+        let items = scan_with_sizes(std::slice::from_ref(&path), &self.scan_options);
+        spinner.finish_and_clear();
+        if items.is_empty() {
+          println!("No cleanable items in {}\n", path.display());
+          return Ok(());
+        }
+
+        let total: u64 = items.iter().map(|item| item.size).sum();
+        println!(
+          "Found {} cleanable items ({}) in {}",
+          items.len(),
+          format_size(total),
+          path.display()
+        );
+        let listing = scan_listing(&items);
+        let message =
+          DevTidyContext::new().create_scan_message(&path.display().to_string(), &listing);
+        self.ask(session, message, reader).await;
+      }
+      ("history", _) => {
+        if session.messages.is_empty() {
+          println!("No messages yet\n");
+        }
+        for message in &session.messages {
+          let speaker = match message.role.as_str() {
+            "user" => "Dev",
+            "assistant" => "AI",
+            other => other,
+          };
+          println!("{} > {}\n", speaker, message.content.trim());
+        }
+      }
+      (name, _) => anyhow::bail!("Unknown command /{}; /help lists the commands", name),
+    }
+    Ok(())
+  }
+
+  fn store_dir(&self) -> Result<&Path> {
+    self
+      .store
+      .ok_or_else(|| anyhow::anyhow!("No data directory to keep chat sessions in"))
+  }
+
+  /// Save after every change, so a session survives closing the terminal
+  fn autosave(&self, session: &mut ChatSession) {
+    let Some(store) = self.store else {
+      return;
+    };
+    session.model = Some(self.model.clone());
+    if let Err(e) = session.save_to(store) {
+      println!("WARNING: Could not save the session: {}", e);
+    }
+  }
+}
+
+/// `system` followed by the newest messages of `history` that fit in `budget`
+/// characters with it. The latest question is always sent, and the kept part
+/// starts with a question so the roles keep alternating.
+fn recent_history(system: ChatMessage, history: &[ChatMessage], budget: usize) -> Vec<ChatMessage> {
+  let mut remaining = budget.saturating_sub(system.content.len());
+  let mut start = history.len();
+  while start > 0 {
+    let length = history[start - 1].content.len();
+    if length > remaining && start < history.len() {
+      break;
+    }
+    remaining = remaining.saturating_sub(length);
+    start -= 1;
+  }
+  while start + 1 < history.len() && history[start].role != "user" {
+    start += 1;
+  }
+
+  std::iter::once(system)
+    .chain(history[start..].iter().cloned())
+    .collect()
+}
+
+/// Answer the conversation in `messages`, running the tools the model calls
+/// until it replies with text. Calls that read contents or walk outside `roots`
+/// run only if `confirm` allows them.
+async fn run_agent(
+  backend: &dyn LlmBackend,
+  model: &str,
+  mut messages: Vec<ChatMessage>,
+  roots: &[PathBuf],
+  scan_options: &ScanOptions,
+  confirm: &mut dyn FnMut(&ToolCall) -> bool,
+) -> Result<String> {
+  let specs = tools::specs();
+  let mut calls = 0;
+
+  loop {
+    let spinner = create_spinner(&format!("Asking {}", model), "cyan");
+    let reply = backend.chat_with_tools(model, &messages, &specs).await;
+    spinner.finish_and_clear();
+    let reply = reply?;
+
+    if reply.tool_calls.is_empty() {
+      let answer = reply.content.trim().to_string();
+      if answer.is_empty() {
+        anyhow::bail!("No response received from AI");
+      }
+      return Ok(answer);
+    }
+    if calls >= MAX_TOOL_CALLS {
+      anyhow::bail!(
+        "The model kept calling tools without answering ({} calls)",
+        calls
+      );
+    }
+
+    // Every call gets a result, but only up to the limit are run
+    let mut results = Vec::new();
+    for call in &reply.tool_calls {
+      let result = if calls < MAX_TOOL_CALLS {
+        calls += 1;
+        run_tool(call, roots, scan_options, confirm)
+      } else {
+        "Not run: no more tool calls are allowed; answer with what you found".to_string()
+      };
+      results.push(ChatMessage::tool_result(call, result));
+    }
+    messages.push(reply);
+    messages.extend(results);
+  }
+}
+
+/// Run one tool call and describe the outcome for the model
+fn run_tool(
+  call: &ToolCall,
+  roots: &[PathBuf],
+  scan_options: &ScanOptions,
+  confirm: &mut dyn FnMut(&ToolCall) -> bool,
+) -> String {
+  let Some(tool) = Tool::from_name(&call.name) else {
+    return format!("There is no tool named {}", call.name);
+  };
+  println!("   [{}] {}", call.name, call.arguments);
+
+  if tool.needs_confirmation(&call.arguments, roots) && !confirm(call) {
+    return "The user did not allow this call".to_string();
+  }
+  match tool.run(&call.arguments, scan_options) {
+    Ok(output) => output,
+    Err(e) => format!("Error: {:#}", e),
+  }
+}
+
+/// Ask before a tool reads file contents, runs git or walks outside the roots
+fn confirm_tool_call(call: &ToolCall, reader: &mut dyn BufRead) -> bool {
+  let target = call
+    .arguments
+    .get("path")
+    .and_then(|path| path.as_str())
+    .unwrap_or("?");
+  print!("   Allow {} on {}? [y/N] ", call.name, target);
+  io::stdout().flush().ok();
+
+  let mut answer = String::new();
+  if reader.read_line(&mut answer).is_err() {
+    return false;
+  }
+  matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// The largest scanned items, one per line, for `/scan`
+fn scan_listing(items: &[CleanableItem]) -> String {
+  let mut lines: Vec<String> = items
+    .iter()
+    .take(SCAN_LISTING_ITEMS)
+    .map(|item| {
+      format!(
+        "- {} {} ({})",
+        item.display_size(),
+        item.display_path(),
+        item.display_info()
+      )
+    })
+    .collect();
+  if items.len() > SCAN_LISTING_ITEMS {
+    lines.push(format!(
+      "- ... and {} smaller items",
+      items.len() - SCAN_LISTING_ITEMS
+    ));
+  }
+  lines.join("\n")
+}
+
+fn print_chat_help() {
+  println!("Commands:");
+  println!("  /save [NAME]   Save the session; with NAME, continue under that name");
+  println!("  /load [NAME]   Load a saved session, or list them");
+  println!("  /clear         Forget the conversation");
+  println!("  /model [NAME]  Show the model, or switch to another one");
+  println!("  /scan [PATH]   Scan PATH (default: current directory) and discuss the results");
+  println!("  /history       Show the conversation so far");
+  println!("  exit, quit     End the chat; the session is kept for next time\n");
+}
+
+/// Connect to the backend configured in the `[ai]` section of the config file,
+/// overridden by `--model` and `OLLAMA_HOST`. Returns `None` when the user was
+/// pointed to the Ollama installer instead.
+async fn connect(model: Option<String>) -> Result<Option<(Box<dyn LlmBackend>, String)>> {
+  let config = Config::load()?.ai;
+  let settings = AiSettings::resolve(&config, model);
+
+  // Check Ollama availability first
+  if config.provider == AiProvider::Ollama
+    && OllamaClient::new(&settings).is_local()
+    && !check_ollama_installation()
+  {
+    handle_ollama_not_available().await?;
+    return Ok(None);
+  }
+
+  let backend = create_backend(&config, &settings);
+  prepare(backend, settings.model.map(|model| model.value))
+    .await
+    .map(Some)
+}
+
+/// Start the backend if needed and make the model available, pulling it into
+/// Ollama on first use
+async fn prepare(
+  mut backend: Box<dyn LlmBackend>,
+  model: Option<String>,
+) -> Result<(Box<dyn LlmBackend>, String)> {
+  // Automatically start Ollama if not running
+  backend.ensure_ready().await?;
+
+  // The configured model wins over the one picked for the hardware
+  let model = match model {
+    Some(model) => model,
+    None => backend.default_model().await?,
+  };
+  backend.ensure_model(&model).await?;
+
+  Ok((backend, model))
+}
+
+/// What to ask the model
+enum Request<'a> {
+  /// A single self-contained prompt
+  Prompt(&'a str),
+  /// A conversation to continue
+  Chat(&'a [ChatMessage]),
+}
+
+/// Print the answer to `request` as it streams in, retrying once when it does
+/// not start in time. Once text has been printed it is never asked for again.
+async fn stream_response(
+  backend: &dyn LlmBackend,
+  model: &str,
+  request: Request<'_>,
+) -> Result<String> {
+  for attempt in 1..=2 {
+    // Initial spinner with attempt info
+    let spinner_msg = if attempt == 1 {
+      format!("Connecting to AI ({})", model)
+    } else {
+      format!("Retrying AI connection ({}) - attempt {}", model, attempt)
+    };
+    let spinner = create_spinner(&spinner_msg, "cyan");
+    let mut printed = false;
+
+    let mut on_token = |token: &str| {
+      printed = true;
+      // Stop spinner once the first words arrive
+      if !spinner.is_finished() {
+        spinner.finish_and_clear();
+      }
+      print!("{}", token);
+      io::stdout().flush().ok();
+    };
+
+    let result = match request {
+      Request::Prompt(prompt) => backend.generate(model, prompt, &mut on_token).await,
+      Request::Chat(messages) => backend.chat(model, messages, &mut on_token).await,
+    };
+    spinner.finish_and_clear();
+
+    match result {
+      Err(e) if attempt == 1 && !printed && is_timeout(&e) => {
+        println!("   Retrying... (attempt {}/2)", attempt + 1);
+      }
+      result => return result,
+    }
+  }
+
+  unreachable!()
+}
+
+/// Whether `error` comes from an answer that did not start in time
+fn is_timeout(error: &anyhow::Error) -> bool {
+  error.chain().any(|cause| {
+    cause.is::<TimedOut>()
+      || cause
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout())
+  })
 }
 
 /// Handle case where Ollama is not installed
@@ -363,3 +1169,466 @@ fn get_single_key_input() -> Option<String> {
 
   result
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ai::mock_ollama::MockOllama;
+  use serde_json::{json, Value};
+
+  #[tokio::test]
+  async fn prepare_pulls_the_requested_model() {
+    let mock = MockOllama::start().await;
+    mock.with_models(&["phi:latest"]).await;
+    mock.pull(&["downloading", "success"]).await;
+
+    let (_, model) = prepare(Box::new(mock.client()), Some("gemma:7b".to_string()))
+      .await
+      .unwrap();
+
+    assert_eq!(model, "gemma:7b");
+    assert_eq!(mock.requests("/api/pull").await[0]["name"], "gemma:7b");
+  }
+
+  #[tokio::test]
+  async fn retries_once_after_a_timeout() {
+    let mock = MockOllama::start().await;
+    mock.stall_once("/api/generate").await;
+    mock.generate(&["Build output, safe to delete."]).await;
+
+    let answer = stream_response(&mock.client(), "phi", Request::Prompt("target?"))
+      .await
+      .unwrap();
+
+    assert_eq!(answer, "Build output, safe to delete.");
+    assert_eq!(mock.requests("/api/generate").await.len(), 2);
+  }
+
+  #[tokio::test]
+  async fn gives_up_after_the_retry() {
+    let mock = MockOllama::start().await;
+    mock.stall_once("/api/generate").await;
+    mock.stall_once("/api/generate").await;
+    mock.generate(&["too late"]).await;
+
+    let error = stream_response(&mock.client(), "phi", Request::Prompt("target?"))
+      .await
+      .unwrap_err();
+
+    assert!(is_timeout(&error));
+    assert_eq!(mock.requests("/api/generate").await.len(), 2);
+  }
+
+  #[tokio::test]
+  async fn does_not_retry_other_errors() {
+    let mock = MockOllama::start().await;
+    mock.fail("/api/generate", 404, "model not found").await;
+
+    let result = stream_response(&mock.client(), "phi", Request::Prompt("target?")).await;
+
+    assert!(result.is_err());
+    assert_eq!(mock.requests("/api/generate").await.len(), 1);
+  }
+
+  #[tokio::test]
+  async fn explain_asks_about_the_folder() {
+    let tmp = tempfile::tempdir().unwrap();
+    let folder = tmp.path().join("node_modules");
+    fs::create_dir(&folder).unwrap();
+    let mock = MockOllama::start().await;
+    mock.generate(&["npm dependencies."]).await;
+
+    explain(
+      &mock.client(),
+      "phi",
+      Some(folder.display().to_string()),
+      None,
+    )
+    .await
+    .unwrap();
+
+    let prompt = mock.requests("/api/generate").await[0]["prompt"]
+      .as_str()
+      .unwrap()
+      .to_string();
+    assert!(prompt.contains("Folder/file to explain: 'node_modules'"));
+  }
+
+  #[tokio::test]
+  async fn suggest_asks_once_per_project() {
+    let tmp = tempfile::tempdir().unwrap();
+    for folder in ["node_modules", "target", "src", "web/node_modules"] {
+      fs::create_dir_all(tmp.path().join(folder)).unwrap();
+      fs::write(tmp.path().join(folder).join("file"), "data").unwrap();
+    }
+    let mock = MockOllama::start().await;
+    mock
+      .generate(&[r#"{"verdicts": [
+        {"name": "node_modules", "safe_to_delete": true, "confidence": 0.9, "reason": "npm packages", "regenerate_command": "npm install"},
+        {"name": "target", "safe_to_delete": false, "confidence": 0.6, "reason": "Might be in use", "regenerate_command": null}
+      ]}"#])
+      .await;
+    let scan_options = ScanOptions {
+      max_depth: 6,
+      ..Default::default()
+    };
+
+    let candidates = suggest(
+      &mock.client(),
+      "phi",
+      &[tmp.path().to_path_buf()],
+      &scan_options,
+      None,
+    )
+    .await
+    .unwrap();
+
+    let root = tmp.path().canonicalize().unwrap();
+    let mut paths: Vec<_> = candidates.iter().map(|item| item.path.clone()).collect();
+    paths.sort();
+    assert_eq!(
+      paths,
+      vec![root.join("node_modules"), root.join("web/node_modules")]
+    );
+
+    let requests = mock.requests("/api/generate").await;
+    assert_eq!(requests.len(), 2);
+    let prompts: Vec<&str> = requests
+      .iter()
+      .map(|request| request["prompt"].as_str().unwrap())
+      .collect();
+    assert!(prompts
+      .iter()
+      .any(|prompt| prompt.contains("- 'node_modules'") && prompt.contains("- 'target'")));
+    assert!(prompts.iter().all(|prompt| !prompt.contains("- 'src'")));
+  }
+
+  #[tokio::test]
+  async fn explain_reuses_cached_answers() {
+    let tmp = tempfile::tempdir().unwrap();
+    let folder = tmp.path().join("node_modules");
+    fs::create_dir(&folder).unwrap();
+    let cache = ResponseCache::in_dir(tmp.path().join("cache"), Duration::from_secs(3600));
+    let mock = MockOllama::start().await;
+    mock.generate(&["npm dependencies."]).await;
+
+    for _ in 0..2 {
+      explain(
+        &mock.client(),
+        "phi",
+        Some(folder.display().to_string()),
+        Some(&cache),
+      )
+      .await
+      .unwrap();
+    }
+    explain(
+      &mock.client(),
+      "gemma:2b",
+      Some(folder.display().to_string()),
+      Some(&cache),
+    )
+    .await
+    .unwrap();
+
+    let requests = mock.requests("/api/generate").await;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["model"], "gemma:2b");
+  }
+
+  #[tokio::test]
+  async fn suggest_only_asks_about_uncached_folders() {
+    let tmp = tempfile::tempdir().unwrap();
+    for folder in ["web/node_modules", "api/node_modules"] {
+      fs::create_dir_all(tmp.path().join(folder)).unwrap();
+      fs::write(tmp.path().join(folder).join("file"), "data").unwrap();
+    }
+    fs::write(tmp.path().join("web/package.json"), "{}").unwrap();
+    fs::write(tmp.path().join("api/package.json"), "{}").unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = ResponseCache::in_dir(cache_dir.path().to_path_buf(), Duration::from_secs(3600));
+    let mock = MockOllama::start().await;
+    mock
+      .generate(&[r#"{"verdicts": [
+        {"name": "node_modules", "safe_to_delete": true, "confidence": 0.9, "reason": "npm packages", "regenerate_command": "npm install"}
+      ]}"#])
+      .await;
+    let scan_options = ScanOptions {
+      max_depth: 6,
+      ..Default::default()
+    };
+
+    let candidates = suggest(
+      &mock.client(),
+      "phi",
+      &[tmp.path().to_path_buf()],
+      &scan_options,
+      Some(&cache),
+    )
+    .await
+    .unwrap();
+
+    // Both projects hold an equivalent node_modules, so the second is cached
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(mock.requests("/api/generate").await.len(), 1);
+  }
+
+  fn test_chat<'a>(backend: &'a dyn LlmBackend, store: &'a Path) -> Chat<'a> {
+    Chat {
+      backend,
+      model: "phi".to_string(),
+      store: Some(store),
+      tools: false,
+      roots: vec![store.to_path_buf()],
+      scan_options: ScanOptions {
+        max_depth: 6,
+        ..Default::default()
+      },
+      prompt_budget: 100_000,
+    }
+  }
+
+  #[test]
+  fn chat_history_keeps_the_newest_messages_that_fit() {
+    let history = vec![
+      ChatMessage::user("a".repeat(50)),
+      ChatMessage::assistant("b".repeat(50)),
+      ChatMessage::user("c".repeat(30)),
+      ChatMessage::assistant("d".repeat(30)),
+      ChatMessage::user("e".repeat(30)),
+    ];
+    let system = ChatMessage::system("s".repeat(10));
+
+    let contents = |messages: Vec<ChatMessage>| -> Vec<String> {
+      messages
+        .into_iter()
+        .map(|message| message.content)
+        .collect()
+    };
+
+    // Room for the last three messages
+    assert_eq!(
+      contents(recent_history(system.clone(), &history, 140)),
+      vec![
+        "s".repeat(10),
+        "c".repeat(30),
+        "d".repeat(30),
+        "e".repeat(30)
+      ]
+    );
+    // Room for one more, but an answer is not sent without its question
+    assert_eq!(
+      contents(recent_history(system.clone(), &history, 150)),
+      vec![
+        "s".repeat(10),
+        "c".repeat(30),
+        "d".repeat(30),
+        "e".repeat(30)
+      ]
+    );
+    // The question is sent even when it alone is over the budget
+    assert_eq!(
+      contents(recent_history(system, &history, 5)),
+      vec!["s".repeat(10), "e".repeat(30)]
+    );
+  }
+
+  #[tokio::test]
+  async fn failed_saves_keep_the_session_name() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    mock.chat(&["Build output."]).await;
+
+    let client = mock.client();
+    test_chat(&client, tmp.path())
+      .run(
+        "default",
+        "/save ../elsewhere
+What is target?
+"
+        .as_bytes(),
+      )
+      .await
+      .unwrap();
+
+    let session = ChatSession::load_from(tmp.path(), "default")
+      .unwrap()
+      .unwrap();
+    assert_eq!(session.messages.len(), 2);
+  }
+
+  #[tokio::test]
+  async fn chat_sends_the_conversation_with_roles() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    mock.chat(&["It caches npm packages."]).await;
+    let input = "What is node_modules?\n\nCan I delete it?\n";
+
+    let client = mock.client();
+    test_chat(&client, tmp.path())
+      .run("default", input.as_bytes())
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/chat").await;
+    assert_eq!(requests.len(), 2);
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let roles: Vec<&str> = messages
+      .iter()
+      .map(|message| message["role"].as_str().unwrap())
+      .collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+    assert_eq!(messages[2]["content"], "It caches npm packages.");
+    assert_eq!(messages[3]["content"], "Can I delete it?");
+  }
+
+  #[tokio::test]
+  async fn chat_sessions_persist_across_runs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    mock.chat(&["Build output."]).await;
+    let client = mock.client();
+
+    test_chat(&client, tmp.path())
+      .run("default", "What is target?\n/save cleanup\n".as_bytes())
+      .await
+      .unwrap();
+    test_chat(&client, tmp.path())
+      .run("default", "/clear\n/load cleanup\nAnd dist?\n".as_bytes())
+      .await
+      .unwrap();
+
+    assert!(ChatSession::load_from(tmp.path(), "default")
+      .unwrap()
+      .unwrap()
+      .messages
+      .is_empty());
+    let session = ChatSession::load_from(tmp.path(), "cleanup")
+      .unwrap()
+      .unwrap();
+    let questions: Vec<&str> = session
+      .messages
+      .iter()
+      .filter(|message| message.role == "user")
+      .map(|message| message.content.as_str())
+      .collect();
+    assert_eq!(questions, vec!["What is target?", "And dist?"]);
+    assert_eq!(session.model.as_deref(), Some("phi"));
+  }
+
+  #[tokio::test]
+  async fn chat_scan_shares_the_scan_results() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path().join("app");
+    fs::create_dir_all(project.join("node_modules")).unwrap();
+    fs::write(project.join("node_modules").join("file"), "data").unwrap();
+    let mock = MockOllama::start().await;
+    mock.chat(&["node_modules is the only artifact."]).await;
+
+    let client = mock.client();
+    let input = format!("/scan {}\n", project.display());
+    test_chat(&client, &tmp.path().join("chats"))
+      .run("default", input.as_bytes())
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/chat").await;
+    let question = requests[0]["messages"][1]["content"].as_str().unwrap();
+    assert!(question.contains("node_modules"));
+    assert!(question.contains("found these cleanable items"));
+  }
+
+  #[tokio::test]
+  async fn agent_runs_at_most_the_tool_call_limit() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    let calls: Vec<(&str, Value)> = (0..MAX_TOOL_CALLS + 3)
+      .map(|_| ("list_dir", json!({ "path": tmp.path() })))
+      .collect();
+    mock.tool_calls_once(&calls).await;
+    mock
+      .chat_reply(json!({ "role": "assistant", "content": "Nothing to clean." }))
+      .await;
+
+    let client = mock.client();
+    let store = tmp.path().join("chats");
+    let chat = Chat {
+      tools: true,
+      ..test_chat(&client, &store)
+    };
+    chat
+      .run("default", "What is here?\n".as_bytes())
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/chat").await;
+    let skipped = requests[1]["messages"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .filter(|message| message["role"] == "tool")
+      .filter(|message| message["content"].as_str().unwrap().starts_with("Not run"))
+      .count();
+    assert_eq!(skipped, 3);
+  }
+
+  #[tokio::test]
+  async fn agent_runs_tools_and_asks_before_reading_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let app = tmp.path().join("app");
+    fs::create_dir_all(app.join("target")).unwrap();
+    fs::write(app.join("Cargo.toml"), "[package]\nname = \"app\"").unwrap();
+    let mock = MockOllama::start().await;
+    mock
+      .tool_calls_once(&[
+        ("list_dir", json!({ "path": app })),
+        ("read_manifest", json!({ "path": app.join("Cargo.toml") })),
+        ("git_status", json!({ "path": app })),
+        ("delete", json!({ "path": app })),
+      ])
+      .await;
+    mock
+      .chat_reply(json!({ "role": "assistant", "content": "target holds Cargo build output." }))
+      .await;
+
+    let client = mock.client();
+    let store = tmp.path().join("chats");
+    let chat = Chat {
+      tools: true,
+      ..test_chat(&client, &store)
+    };
+    chat
+      .run("default", "What is in app?\ny\nn\n".as_bytes())
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/chat").await;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["tools"].as_array().unwrap().len(), 5);
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let results: Vec<&str> = messages
+      .iter()
+      .filter(|message| message["role"] == "tool")
+      .map(|message| message["content"].as_str().unwrap())
+      .collect();
+    assert_eq!(
+      results,
+      vec![
+        "Cargo.toml (file, 22 B)\ntarget (directory)",
+        "[package]\nname = \"app\"",
+        "The user did not allow this call",
+        "There is no tool named delete",
+      ]
+    );
+    assert_eq!(messages[2]["tool_calls"][0]["function"]["name"], "list_dir");
+
+    let session = ChatSession::load_from(&store, "default").unwrap().unwrap();
+    assert_eq!(
+      session.messages,
+      vec![
+        ChatMessage::user("What is in app?"),
+        ChatMessage::assistant("target holds Cargo build output."),
+      ]
+    );
+  }
+}
@@ -1,63 +1,188 @@
-use crate::core::constants::CLEANABLE_PATTERNS;
+use crate::core::constants::{CLEANABLE_PATTERNS, LOCKFILE_PATTERNS};
+use crate::utils::hash::fnv1a;
+
+/// Bumped whenever a prompt template changes, so cached answers to the old
+/// wording are not reused
+pub const PROMPT_VERSION: u32 = 2;
+
+/// How lockfiles are described to the model
+const LOCKFILE_NOTE: &str = "regenerable, but deleting it changes the resolved dependency versions";
+
+/// What the scanner found about one folder, for a prompt
+pub struct FolderInfo {
+  pub name: String,
+  pub size: String,
+  /// Empty if there is nothing beyond the name
+  pub details: String,
+}
 
 /// Context-aware AI prompts for DevTidy
-pub struct DevTidyContext {
-  conversation_history: Vec<(String, String)>, // (user_input, ai_response)
-}
+#[derive(Default)]
+pub struct DevTidyContext;
 
 impl DevTidyContext {
   pub fn new() -> Self {
-    Self {
-      conversation_history: Vec::new(),
-    }
-  }
-
-  /// Add a conversation exchange to history
-  pub fn add_exchange(&mut self, user_input: String, ai_response: String) {
-    self.conversation_history.push((user_input, ai_response));
-
-    // Keep only last 5 exchanges to avoid token limits
-    if self.conversation_history.len() > 5 {
-      self.conversation_history.remove(0);
-    }
+    Self
   }
 
   /// Create a context-aware prompt for explaining files/folders
   pub fn create_explain_prompt(&self, folder_name: &str) -> String {
     let app_context = self.get_app_context();
     let pattern_info = self.check_cleanable_pattern(folder_name);
-    let conversation_context = self.get_conversation_context();
 
     format!(
-            "{app_context}\n\n{pattern_info}\n\nFolder/file to explain: '{folder_name}'\n\n{conversation_context}\n\nExplain what this folder/file is used for and whether DevTidy can safely delete it. Be specific and reference the pattern information above if applicable. Keep response concise (1-2 sentences)."
+            "{app_context}\n\n{pattern_info}\n\nFolder/file to explain: '{folder_name}'\n\nExplain what this folder/file is used for and whether DevTidy can safely delete it. Be specific and reference the pattern information above if applicable. Keep response concise (1-2 sentences)."
         )
   }
 
-  /// Create a context-aware prompt for cleanup suggestions  
-  pub fn create_suggest_prompt(&self, folder_name: &str, size: &str) -> String {
+  /// Create a context-aware prompt asking for a JSON verdict on one folder.
+  /// `details` describes what the scanner knows about it, if anything.
+  pub fn create_suggest_prompt(&self, folder_name: &str, size: &str, details: &str) -> String {
     let app_context = self.get_app_context();
     let pattern_info = self.check_cleanable_pattern(folder_name);
+    let details = if details.is_empty() {
+      String::new()
+    } else {
+      format!("\nDetails: {details}")
+    };
+
+    format!(
+            "{app_context}\n\n{pattern_info}\n\nFolder: '{folder_name}' (size: {size}){details}\n\nBased on DevTidy's patterns above, can this folder be safely deleted? Answer with JSON only: safe_to_delete (true/false), confidence (0 to 1), reason (one sentence) and regenerate_command (the command that recreates the folder, or null)."
+        )
+  }
+
+  /// Create a context-aware prompt asking for a JSON verdict on each of the
+  /// `folders` found in one project, named as they are listed
+  pub fn create_project_suggest_prompt(&self, project: &str, folders: &[FolderInfo]) -> String {
+    let app_context = self.get_app_context();
+    let listing = folders
+      .iter()
+      .map(|folder| {
+        let details = if folder.details.is_empty() {
+          String::new()
+        } else {
+          format!("; {}", folder.details)
+        };
+        format!(
+          "- '{}' (size: {}){}\n  {}",
+          folder.name,
+          folder.size,
+          details,
+          self.check_cleanable_pattern(&folder.name)
+        )
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+
+    format!(
+            "{app_context}\n\nProject: '{project}'\nFolders found in it:\n{listing}\n\nBased on DevTidy's patterns above, can each folder be safely deleted? Answer with JSON only: a verdicts array with one entry per folder holding name (as listed), safe_to_delete (true/false), confidence (0 to 1), reason (one sentence) and regenerate_command (the command that recreates the folder, or null)."
+        )
+  }
+
+  /// Create a context-aware prompt asking about one scanned item, with its age
+  /// and a few of its entries (or lines, for a file)
+  pub fn create_item_explain_prompt(
+    &self,
+    item: &FolderInfo,
+    age: &str,
+    sample: &[String],
+  ) -> String {
+    let app_context = self.get_app_context();
+    let pattern_info = self.check_cleanable_pattern(&item.name);
+    let name = &item.name;
+    let size = &item.size;
+    let details = &item.details;
+    let sample = if sample.is_empty() {
+      "  (empty)".to_string()
+    } else {
+      sample
+        .iter()
+        .map(|entry| format!("  {}", entry))
+        .collect::<Vec<_>>()
+        .join("\n")
+    };
+
+    format!(
+            "{app_context}\n\n{pattern_info}\n\nItem: '{name}' (size: {size}, last modified {age})\nDetails: {details}\nSample of its contents:\n{sample}\n\nExplain what this item is, which tool created it and whether deleting it is safe, given its age and contents. Keep the answer to at most four sentences."
+        )
+  }
+
+  /// System prompt of a chat session; the conversation itself is sent as
+  /// user and assistant messages
+  pub fn create_chat_system_prompt(&self) -> String {
+    let app_context = self.get_app_context();
+
+    format!(
+            "{app_context}\n\nAnswer as DevTidy's AI assistant. If the question is about files/folders, check if they match any cleanable patterns and advise accordingly. Be helpful and specific."
+        )
+  }
+
+  /// System prompt of a chat where the model can call the read-only tools
+  pub fn create_agent_system_prompt(&self) -> String {
+    let chat_prompt = self.create_chat_system_prompt();
 
     format!(
-            "{app_context}\n\n{pattern_info}\n\nFolder: '{folder_name}' (size: {size})\n\nBased on DevTidy's patterns above, can this folder be safely deleted? Give a clear yes/no answer with brief reasoning."
+            "{chat_prompt}\n\nYou can call tools to look at the user's filesystem: list_dir, get_size, read_manifest, scan and git_status. Use them to check instead of guessing from names, and mention what you found. The tools are read-only and you cannot delete anything: when something should be removed, tell the user to select it in the `dd` TUI or run `dd ai-suggest --clean`, which ask for confirmation."
         )
   }
 
-  /// Create a context-aware prompt for chat interactions
-  pub fn create_chat_prompt(&self, user_input: &str) -> String {
+  /// Scan results shared in a chat with `/scan`
+  pub fn create_scan_message(&self, path: &str, listing: &str) -> String {
+    format!(
+            "DevTidy scanned '{path}' and found these cleanable items (size, path, what it is):\n{listing}\n\nSummarise what stands out and what looks safest to clean. Keep this in mind for my next questions."
+        )
+  }
+
+  /// Create a prompt condensing one part of a large report's project listing
+  /// into notes, for `create_report_plan_prompt`
+  pub fn create_report_chunk_prompt(&self, part: usize, parts: usize, listing: &str) -> String {
     let app_context = self.get_app_context();
-    let conversation_context = self.get_conversation_context();
 
     format!(
-            "{app_context}\n\n{conversation_context}\n\nUser question: {user_input}\n\nAnswer as DevTidy's AI assistant. If the question is about files/folders, check if they match any cleanable patterns and advise accordingly. Be helpful and specific."
+            "{app_context}\n\nThis is part {part} of {parts} of a disk scan, listing projects (path, ecosystems, total) and the cleanable items found in each:\n{listing}\n\nSummarise this part as short notes for a cleanup plan: the biggest items, what recreates them, anything orphaned, and anything risky to delete. Use at most ten bullet points."
         )
   }
 
+  /// Create a prompt merging notes made from parts of a large report, for when
+  /// they are still too long for `create_report_plan_prompt`
+  pub fn create_report_notes_prompt(&self, part: usize, parts: usize, notes: &str) -> String {
+    let app_context = self.get_app_context();
+
+    format!(
+            "{app_context}\n\nThese are notes on a disk scan, group {part} of {parts}:\n{notes}\n\nMerge them into shorter notes for a cleanup plan, keeping the biggest items, what recreates them, anything orphaned and anything risky to delete. Use at most ten bullet points."
+        )
+  }
+
+  /// Create a prompt asking for a prioritised cleanup plan. `overview` holds
+  /// the totals; `details` is the project listing, or notes made from it.
+  pub fn create_report_plan_prompt(&self, overview: &str, details: &str) -> String {
+    let app_context = self.get_app_context();
+
+    format!(
+            "{app_context}\n\nDevTidy scanned a developer machine.\n{overview}\n\nDetails:\n{details}\n\nWrite a prioritised cleanup plan in Markdown for a disk-space ticket read by managers. Start with a two-sentence summary. Then list the steps, biggest savings first, each with the space it frees, what has to be run to recreate the files and the risks of deleting them. End with a short Risks section. Do not invent paths or sizes that are not listed above."
+        )
+  }
+
+  /// Hash of the application context every prompt starts with. It lists the
+  /// patterns DevTidy knows, which can change without a new `PROMPT_VERSION`.
+  pub fn app_context_hash(&self) -> u64 {
+    fnv1a(self.get_app_context().as_bytes())
+  }
+
   /// Get DevTidy application context
   fn get_app_context(&self) -> String {
-    let patterns_list = CLEANABLE_PATTERNS
-      .iter()
-      .map(|(pattern, desc)| format!("  - {}: {}", pattern, desc))
+    // Sorted, so the same patterns always give the same prompt
+    let mut patterns: Vec<(&&str, &&str)> = CLEANABLE_PATTERNS.iter().collect();
+    patterns.sort();
+    let patterns_list = patterns
+      .into_iter()
+      .map(|(pattern, desc)| {
+        if LOCKFILE_PATTERNS.contains(pattern) {
+          format!("  - {}: {} ({})", pattern, desc, LOCKFILE_NOTE)
+        } else {
+          format!("  - {}: {}", pattern, desc)
+        }
+      })
       .collect::<Vec<_>>()
       .join("\n");
 
@@ -68,6 +193,15 @@ impl DevTidyContext {
 
   /// Check if a folder/file matches cleanable patterns
   fn check_cleanable_pattern(&self, name: &str) -> String {
+    // Lockfiles are regenerable but not disposable like build output
+    if LOCKFILE_PATTERNS.contains(&name) {
+      let description = CLEANABLE_PATTERNS.get(name).copied().unwrap_or("lock file");
+      return format!(
+        "PATTERN MATCH: '{}' is a lockfile - {}. It is {}; only delete it when the project it belongs to is gone.",
+        name, description, LOCKFILE_NOTE
+      );
+    }
+
     // Direct pattern match
     if let Some(description) = CLEANABLE_PATTERNS.get(name) {
       return format!(
@@ -88,7 +222,7 @@ impl DevTidyContext {
 
     // Extension matching for files
     if name.contains('.') {
-      let extension = format!("*.{}", name.split('.').last().unwrap_or(""));
+      let extension = format!("*.{}", name.split('.').next_back().unwrap_or(""));
       if let Some(description) = CLEANABLE_PATTERNS.get(extension.as_str()) {
         return format!("PATTERN MATCH: '{}' matches DevTidy extension pattern '{}' - {}. This CAN be safely deleted.", name, extension, description);
       }
@@ -96,27 +230,4 @@ impl DevTidyContext {
 
     format!("NO PATTERN MATCH: '{}' does not match any DevTidy cleanable patterns. This should NOT be deleted as it's likely important project files.", name)
   }
-
-  /// Get recent conversation context
-  fn get_conversation_context(&self) -> String {
-    if self.conversation_history.is_empty() {
-      return "No previous conversation.".to_string();
-    }
-
-    let context = self
-      .conversation_history
-      .iter()
-      .enumerate()
-      .map(|(i, (user, ai))| format!("Previous exchange {}:\nUser: {}\nAI: {}", i + 1, user, ai))
-      .collect::<Vec<_>>()
-      .join("\n\n");
-
-    format!("Recent conversation:\n{}", context)
-  }
-}
-
-impl Default for DevTidyContext {
-  fn default() -> Self {
-    Self::new()
-  }
 }
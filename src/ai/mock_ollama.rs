@@ -0,0 +1,164 @@
+//! A scripted stand-in for the Ollama HTTP API, for tests
+//!
+//! Responses are matched in the order they were scripted, so a response limited
+//! with `once` can be followed by a different one for the retry.
+
+use super::ollama::OllamaClient;
+use super::settings::AiSettings;
+use crate::core::config::AiConfig;
+use serde_json::{json, Value};
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub struct MockOllama {
+  server: MockServer,
+}
+
+impl MockOllama {
+  pub async fn start() -> Self {
+    Self {
+      server: MockServer::start().await,
+    }
+  }
+
+  /// Settings pointing at this server, with a short timeout
+  pub fn settings(&self) -> AiSettings {
+    let config = AiConfig {
+      base_url: Some(self.server.uri()),
+      ..Default::default()
+    };
+    let mut settings = AiSettings::resolve_with(&config, None, |_| None);
+    settings.timeout.value = Duration::from_millis(500);
+    settings
+  }
+
+  pub fn client(&self) -> OllamaClient {
+    OllamaClient::new(&self.settings())
+  }
+
+  /// Serve `/api/tags` listing `models`
+  pub async fn with_models(&self, models: &[&str]) {
+    let models: Vec<Value> = models
+      .iter()
+      .map(|name| json!({ "name": name, "size": 1_000_000 }))
+      .collect();
+    Mock::given(method("GET"))
+      .and(path("/api/tags"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": models })))
+      .mount(&self.server)
+      .await;
+  }
+
+  /// Stream `chunks` from `/api/generate` as NDJSON, then a final `done` line
+  pub async fn generate(&self, chunks: &[&str]) {
+    let lines = chunks
+      .iter()
+      .map(|chunk| json!({ "response": chunk, "done": false }).to_string())
+      .chain([json!({ "response": "", "done": true }).to_string()]);
+    self.stream("/api/generate", lines.collect()).await;
+  }
+
+  /// Stream `chunks` from `/api/chat` as assistant messages, then `done`
+  pub async fn chat(&self, chunks: &[&str]) {
+    let lines = chunks
+      .iter()
+      .map(|chunk| {
+        json!({ "message": { "role": "assistant", "content": chunk }, "done": false }).to_string()
+      })
+      .chain([json!({ "done": true }).to_string()]);
+    self.stream("/api/chat", lines.collect()).await;
+  }
+
+  /// Answer non-streamed `/api/chat` requests with this assistant message
+  pub async fn chat_reply(&self, message: Value) {
+    self
+      .reply(
+        "/api/chat",
+        json!({ "message": message, "done": true }),
+        None,
+      )
+      .await;
+  }
+
+  /// Answer the next non-streamed `/api/chat` request with these tool calls
+  pub async fn tool_calls_once(&self, calls: &[(&str, Value)]) {
+    let calls: Vec<Value> = calls
+      .iter()
+      .map(|(name, arguments)| json!({ "function": { "name": name, "arguments": arguments } }))
+      .collect();
+    let message = json!({ "role": "assistant", "content": "", "tool_calls": calls });
+    self
+      .reply(
+        "/api/chat",
+        json!({ "message": message, "done": true }),
+        Some(1),
+      )
+      .await;
+  }
+
+  /// Answer POST `endpoint` with a JSON body, at most `times` times if given
+  pub async fn reply(&self, endpoint: &str, body: Value, times: Option<u64>) {
+    let mock = Mock::given(method("POST"))
+      .and(path(endpoint))
+      .respond_with(ResponseTemplate::new(200).set_body_json(body));
+    match times {
+      Some(times) => mock.up_to_n_times(times),
+      None => mock,
+    }
+    .mount(&self.server)
+    .await;
+  }
+
+  /// Stream `/api/pull` progress through `statuses`
+  pub async fn pull(&self, statuses: &[&str]) {
+    let lines = statuses
+      .iter()
+      .map(|status| json!({ "status": status }).to_string())
+      .collect();
+    self.stream("/api/pull", lines).await;
+  }
+
+  /// Answer POST `endpoint` with these raw lines, joined by newlines
+  pub async fn stream(&self, endpoint: &str, lines: Vec<String>) {
+    Mock::given(method("POST"))
+      .and(path(endpoint))
+      .respond_with(
+        ResponseTemplate::new(200).set_body_raw(lines.join("\n"), "application/x-ndjson"),
+      )
+      .mount(&self.server)
+      .await;
+  }
+
+  /// Answer POST `endpoint` with an HTTP error
+  pub async fn fail(&self, endpoint: &str, status: u16, body: &str) {
+    Mock::given(method("POST"))
+      .and(path(endpoint))
+      .respond_with(ResponseTemplate::new(status).set_body_string(body))
+      .mount(&self.server)
+      .await;
+  }
+
+  /// Make the next POST to `endpoint` answer only after the client timed out
+  pub async fn stall_once(&self, endpoint: &str) {
+    Mock::given(method("POST"))
+      .and(path(endpoint))
+      .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+      .up_to_n_times(1)
+      .mount(&self.server)
+      .await;
+  }
+
+  /// JSON bodies of the requests received at `endpoint`, oldest first
+  pub async fn requests(&self, endpoint: &str) -> Vec<Value> {
+    self
+      .server
+      .received_requests()
+      .await
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|request| request.url.path() == endpoint)
+      .map(|request| serde_json::from_slice(&request.body).unwrap_or(Value::Null))
+      .collect()
+  }
+}
@@ -1,6 +1,19 @@
+mod advisor;
+mod backend;
+mod cache;
 mod commands;
 mod context;
+#[cfg(test)]
+mod mock_ollama;
 mod ollama;
+mod openai;
+mod report;
+mod session;
+mod settings;
+mod tools;
 mod utils;
+mod verdict;
 
+pub use advisor::Advisor;
 pub use commands::*;
+pub use verdict::Verdict;
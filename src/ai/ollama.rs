@@ -1,29 +1,17 @@
+use super::backend::{
+  for_each_line, send_before, tool_arguments, ChatMessage, LlmBackend, TokenSink, ToolCall,
+  ToolSpec,
+};
+use super::settings::AiSettings;
+use super::utils::create_spinner;
 use anyhow::{anyhow, Result};
-use indicatif::{ProgressBar, ProgressStyle};
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::time::Duration;
 use sysinfo::System;
-
-/// Create an animated spinner with consistent styling
-fn create_spinner(message: &str, color: &str) -> ProgressBar {
-  let spinner = ProgressBar::new_spinner();
-  spinner.set_style(
-    ProgressStyle::default_spinner()
-      .template(&format!("{{spinner:.{}}} {{msg}}", color))
-      .unwrap(),
-  );
-  spinner.set_message(message.to_string());
-  spinner.enable_steady_tick(Duration::from_millis(100));
-  spinner
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OllamaResponse {
-  pub response: String,
-  pub done: bool,
-}
+use tokio::time::Instant;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaModel {
@@ -40,30 +28,31 @@ pub struct OllamaClient {
   client: Client,
   base_url: String,
   spawned_process: Option<std::process::Child>,
+  temperature: f32,
+  context_window: Option<u32>,
+  max_tokens: u32,
+  timeout: Duration,
 }
 
 impl OllamaClient {
-  pub fn new() -> Self {
+  pub fn new(settings: &AiSettings) -> Self {
     Self {
       client: Client::new(),
-      base_url: "http://localhost:11434".to_string(),
+      base_url: settings.base_url.value.trim_end_matches('/').to_string(),
       spawned_process: None,
+      temperature: settings.temperature.value,
+      context_window: settings.context_window.as_ref().map(|s| s.value),
+      max_tokens: settings.max_tokens.value,
+      timeout: settings.timeout.value,
     }
   }
 
-  /// Check if Ollama is running and accessible
-  pub async fn health_check(&self) -> Result<bool> {
-    let response = self
-      .client
-      .get(&format!("{}/api/tags", self.base_url))
-      .timeout(Duration::from_secs(5))
-      .send()
-      .await;
-
-    match response {
-      Ok(resp) => Ok(resp.status().is_success()),
-      Err(_) => Ok(false),
-    }
+  /// Whether the daemon would run on this machine, so it can be started here
+  pub fn is_local(&self) -> bool {
+    reqwest::Url::parse(&self.base_url)
+      .ok()
+      .and_then(|url| url.host_str().map(str::to_string))
+      .is_some_and(|host| matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]"))
   }
 
   /// Extended health check with detailed diagnostics
@@ -111,6 +100,11 @@ impl OllamaClient {
       return Ok(());
     }
 
+    // A remote daemon cannot be started from here
+    if !self.is_local() {
+      return Err(anyhow!("Ollama is not responding at {}", self.base_url));
+    }
+
     // Check if Ollama is installed
     if !crate::ai::utils::check_ollama_installation() {
       return Err(anyhow!("Ollama is not installed"));
@@ -166,33 +160,15 @@ impl OllamaClient {
     self.spawned_process.is_some()
   }
 
-  /// List available models
-  pub async fn list_models(&self) -> Result<Vec<String>> {
-    let response = self
-      .client
-      .get(&format!("{}/api/tags", self.base_url))
-      .send()
-      .await?;
-
-    if !response.status().is_success() {
-      return Err(anyhow!("Failed to list models"));
-    }
-
-    let models_response: OllamaModelsResponse = response.json().await?;
-    Ok(models_response.models.into_iter().map(|m| m.name).collect())
-  }
-
   /// Pull a model if not available locally
   pub async fn pull_model(&self, model: &str) -> Result<()> {
-    use futures_util::StreamExt;
-
     println!("Pulling model '{}'...\nThis may take a few minutes.", model);
 
     let spinner = create_spinner(&format!("Downloading {}", model), "green");
 
     let response = self
       .client
-      .post(&format!("{}/api/pull", self.base_url))
+      .post(format!("{}/api/pull", self.base_url))
       .json(&json!({ "name": model }))
       .send()
       .await?;
@@ -203,122 +179,46 @@ impl OllamaClient {
     }
 
     // Handle streaming response for real-time progress
-    let mut stream = response.bytes_stream();
-    let mut buffer = Vec::new();
-
-    while let Some(chunk) = stream.next().await {
-      let chunk = chunk?;
-      buffer.extend_from_slice(&chunk);
-
-      // Process complete lines
-      while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
-        let line_bytes = buffer.drain(..=newline_pos).collect::<Vec<u8>>();
-        let line = String::from_utf8_lossy(&line_bytes[..line_bytes.len() - 1]); // Remove newline
-
-        if let Ok(status) = serde_json::from_str::<serde_json::Value>(&line) {
-          if let Some(status_msg) = status.get("status").and_then(|s| s.as_str()) {
-            spinner.set_message(format!("{}: {}", model, status_msg));
-          }
+    for_each_line(response, None, |line| {
+      let Ok(status) = serde_json::from_str::<Value>(line) else {
+        return Ok(true);
+      };
+      if let Some(error) = status.get("error").and_then(|e| e.as_str()) {
+        return Err(anyhow!("Failed to pull model {}: {}", model, error));
+      }
 
-          // Check if download is complete
-          if status.get("status").and_then(|s| s.as_str()) == Some("success") {
-            break;
-          }
-        }
+      let status_msg = status.get("status").and_then(|s| s.as_str());
+      if let Some(status_msg) = status_msg {
+        spinner.set_message(format!("{}: {}", model, status_msg));
       }
-    }
+      // Check if download is complete
+      Ok(status_msg != Some("success"))
+    })
+    .await
+    .inspect_err(|_| spinner.finish_with_message("ERROR: Failed to pull model"))?;
 
     spinner.finish_with_message(format!("Model '{}' ready", model));
     Ok(())
   }
 
-  /// Generate text with optional formatting
-  pub async fn generate_with_format(
-    &self,
-    model: &str,
-    prompt: &str,
-    show_header: bool,
-  ) -> Result<String> {
-    // Retry logic for timeout errors
-    for attempt in 1..=2 {
-      match self
-        .try_generate_with_format(model, prompt, show_header, attempt)
-        .await
-      {
-        Ok(result) => return Ok(result),
-        Err(e) => {
-          if attempt == 2 || !e.to_string().contains("timeout") {
-            return Err(e);
-          }
-          println!("   Retrying... (attempt {}/2)", attempt + 1);
-        }
-      }
-    }
-
-    unreachable!()
-  }
-
-  /// Internal implementation with retry support
-  async fn try_generate_with_format(
+  /// POST a streaming request and pass the text of every chunk to `on_token`.
+  /// `text` extracts the new text from one NDJSON chunk.
+  async fn stream(
     &self,
-    model: &str,
-    prompt: &str,
-    show_header: bool,
-    attempt: u32,
+    endpoint: &str,
+    payload: Value,
+    text: fn(&Value) -> Option<&str>,
+    on_token: &mut TokenSink<'_>,
   ) -> Result<String> {
-    use futures_util::StreamExt;
-    use std::io::{self, Write};
-
-    // Initial spinner with attempt info
-    let spinner_msg = if attempt == 1 {
-      format!("Connecting to AI ({})", model)
-    } else {
-      format!("Retrying AI connection ({}) - attempt {}", model, attempt)
-    };
-    let spinner = create_spinner(&spinner_msg, "cyan");
-
-    // Model-specific token limits for complete responses
-    let max_tokens = match model {
-      "tinyllama" => 1000,        // Small model, moderate limit
-      "phi" => 1500,              // Balanced
-      "gemma:2b" => 2000,         // Good capacity
-      "gemma:7b" => 2500,         // Larger capacity
-      "mistral:instruct" => 3000, // High capacity
-      _ => 1500,                  // Safe default
-    };
-
-    let payload = json!({
-        "model": model,
-        "prompt": prompt,
-        "stream": true,  // Enable streaming
-        "options": {
-            "temperature": 0.1,
-            "top_p": 0.9,
-            "num_predict": max_tokens
-        }
-    });
-
-    // Model-specific timeouts for initial loading
-    let timeout_secs = match model {
-      "tinyllama" => 30,
-      "phi" => 45,
-      "gemma:2b" => 60,
-      "mistral:instruct" => 120,
-      _ => 60,
-    };
-
-    // Start the streaming request with model-appropriate timeout
-    let response = self
+    // Loading the model counts towards the timeout, streaming the answer does not
+    let deadline = Instant::now() + self.timeout;
+    let request = self
       .client
-      .post(&format!("{}/api/generate", self.base_url))
-      .json(&payload)
-      .timeout(Duration::from_secs(timeout_secs))
-      .send()
-      .await?;
+      .post(format!("{}{}", self.base_url, endpoint))
+      .json(&payload);
+    let response = send_before(request, deadline, self.timeout).await?;
 
     if !response.status().is_success() {
-      spinner.finish_and_clear();
-      println!("Failed to connect to AI");
       let status = response.status();
       let error_text = response.text().await.unwrap_or_default();
       return Err(anyhow!(
@@ -332,112 +232,245 @@ impl OllamaClient {
       ));
     }
 
-    // Stop spinner and start streaming output
-    spinner.finish_and_clear();
+    let mut full_response = String::new();
+    for_each_line(response, Some((deadline, self.timeout)), |line| {
+      // Skip malformed JSON lines (common in streaming responses)
+      let Ok(chunk) = serde_json::from_str::<Value>(line) else {
+        return Ok(true);
+      };
+      if let Some(error) = chunk.get("error") {
+        return Err(anyhow!("AI model error: {}", error));
+      }
+
+      if let Some(response_text) = text(&chunk).filter(|t| !t.is_empty()) {
+        on_token(response_text);
+        full_response.push_str(response_text);
+      }
+
+      // Check if streaming is done
+      Ok(!chunk.get("done").and_then(|d| d.as_bool()).unwrap_or(false))
+    })
+    .await?;
 
-    if show_header {
-      print!("AI > ");
+    if full_response.trim().is_empty() {
+      return Err(anyhow!("No response received from AI"));
     }
-    io::stdout().flush().ok();
 
-    // Handle streaming response
-    let mut stream = response.bytes_stream();
-    let mut buffer = Vec::new();
-    let mut full_response = String::new();
-    let mut words_on_line = 0;
-    const MAX_WORDS_PER_LINE: usize = 12;
+    Ok(full_response.trim().to_string())
+  }
 
-    while let Some(chunk_result) = stream.next().await {
-      let chunk = match chunk_result {
-        Ok(chunk) => chunk,
-        Err(e) => {
-          eprintln!("\nERROR: Streaming interrupted: {}", e);
-          break;
-        }
-      };
+  /// Sampling options sent with every request
+  fn options(&self) -> Value {
+    let mut options = json!({
+        "temperature": self.temperature,
+        "top_p": 0.9,
+        "num_predict": self.max_tokens
+    });
+    if let Some(context_window) = self.context_window {
+      options["num_ctx"] = json!(context_window);
+    }
+    options
+  }
+}
 
-      buffer.extend_from_slice(&chunk);
+#[async_trait]
+impl LlmBackend for OllamaClient {
+  fn describe(&self) -> String {
+    format!("Ollama at {}", self.base_url)
+  }
 
-      // Process complete lines
-      while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
-        let line_bytes = buffer.drain(..=newline_pos).collect::<Vec<u8>>();
-        let line = String::from_utf8_lossy(&line_bytes[..line_bytes.len() - 1]); // Remove newline
+  async fn ensure_ready(&mut self) -> Result<()> {
+    self.ensure_running().await?;
+    if self.has_spawned_process() {
+      println!("Started Ollama daemon for this session");
+    }
+    Ok(())
+  }
 
-        // Skip empty lines
-        if line.trim().is_empty() {
-          continue;
-        }
+  /// Check if Ollama is running and accessible
+  async fn health_check(&self) -> Result<bool> {
+    let response = self
+      .client
+      .get(format!("{}/api/tags", self.base_url))
+      .timeout(Duration::from_secs(5))
+      .send()
+      .await;
 
-        match serde_json::from_str::<serde_json::Value>(&line) {
-          Ok(chunk_data) => {
-            // Check for error in response
-            if let Some(error) = chunk_data.get("error") {
-              eprintln!("\nERROR: AI model error: {}", error);
-              break;
-            }
+    match response {
+      Ok(resp) => Ok(resp.status().is_success()),
+      Err(_) => Ok(false),
+    }
+  }
 
-            if let Some(response_text) = chunk_data.get("response").and_then(|r| r.as_str()) {
-              if !response_text.is_empty() {
-                // Print the streaming text
-                print!("{}", response_text);
-                io::stdout().flush().ok();
-
-                full_response.push_str(response_text);
-
-                // Add line breaks for readability (only if showing header)
-                if show_header && response_text.contains(' ') {
-                  words_on_line += response_text.split_whitespace().count();
-                  if words_on_line >= MAX_WORDS_PER_LINE {
-                    println!();
-                    print!("> ");
-                    io::stdout().flush().ok();
-                    words_on_line = 0;
-                  }
-                }
-              }
-            }
+  /// List available models
+  async fn list_models(&self) -> Result<Vec<String>> {
+    let response = self
+      .client
+      .get(format!("{}/api/tags", self.base_url))
+      .send()
+      .await?;
 
-            // Check if streaming is done
-            if chunk_data
-              .get("done")
-              .and_then(|d| d.as_bool())
-              .unwrap_or(false)
-            {
-              break;
-            }
-          }
-          Err(e) => {
-            // Skip malformed JSON lines (common in streaming responses)
-            eprintln!("\nDEBUG: Skipping malformed JSON: {} (line: {})", e, line);
-            continue;
-          }
-        }
-      }
+    if !response.status().is_success() {
+      return Err(anyhow!("Failed to list models"));
     }
 
-    // Process any remaining buffer data
-    if !buffer.is_empty() {
-      let remaining = String::from_utf8_lossy(&buffer);
-      if !remaining.trim().is_empty() {
-        if let Ok(chunk_data) = serde_json::from_str::<serde_json::Value>(&remaining) {
-          if let Some(response_text) = chunk_data.get("response").and_then(|r| r.as_str()) {
-            if !response_text.is_empty() {
-              print!("{}", response_text);
-              full_response.push_str(response_text);
-            }
-          }
-        }
-      }
+    let models_response: OllamaModelsResponse = response.json().await?;
+    Ok(models_response.models.into_iter().map(|m| m.name).collect())
+  }
+
+  async fn default_model(&self) -> Result<String> {
+    Ok(select_model_by_ram())
+  }
+
+  /// Pull the model if it is not available locally
+  async fn ensure_model(&self, model: &str) -> Result<()> {
+    let available_models = self.list_models().await?;
+
+    if !available_models.iter().any(|m| m.starts_with(model)) {
+      self.pull_model(model).await?;
     }
 
-    if full_response.trim().is_empty() {
-      return Err(anyhow!("No response received from AI"));
+    Ok(())
+  }
+
+  async fn generate(
+    &self,
+    model: &str,
+    prompt: &str,
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String> {
+    let payload = json!({
+        "model": model,
+        "prompt": prompt,
+        "stream": true,
+        "options": self.options()
+    });
+    self
+      .stream(
+        "/api/generate",
+        payload,
+        |chunk| chunk.get("response")?.as_str(),
+        on_token,
+      )
+      .await
+  }
+
+  /// Ollama constrains the output to the schema given as `format`
+  async fn generate_structured(&self, model: &str, prompt: &str, schema: &Value) -> Result<String> {
+    let payload = json!({
+        "model": model,
+        "prompt": prompt,
+        "format": schema,
+        "stream": true,
+        "options": self.options()
+    });
+    self
+      .stream(
+        "/api/generate",
+        payload,
+        |chunk| chunk.get("response")?.as_str(),
+        &mut |_: &str| {},
+      )
+      .await
+  }
+
+  async fn chat(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String> {
+    let payload = json!({
+        "model": model,
+        "messages": wire_messages(messages),
+        "stream": true,
+        "options": self.options()
+    });
+    self
+      .stream(
+        "/api/chat",
+        payload,
+        |chunk| chunk.get("message")?.get("content")?.as_str(),
+        on_token,
+      )
+      .await
+  }
+
+  async fn chat_with_tools(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    tools: &[ToolSpec],
+  ) -> Result<ChatMessage> {
+    let payload = json!({
+        "model": model,
+        "messages": wire_messages(messages),
+        "tools": tools.iter().map(ToolSpec::to_json).collect::<Vec<_>>(),
+        "stream": false,
+        "options": self.options()
+    });
+    // Without streaming the reply only starts once it is complete
+    let request = self
+      .client
+      .post(format!("{}/api/chat", self.base_url))
+      .json(&payload);
+    let response = send_before(request, Instant::now() + self.timeout, self.timeout).await?;
+
+    if !response.status().is_success() {
+      let status = response.status();
+      let error_text = response.text().await.unwrap_or_default();
+      return Err(anyhow!("Ollama API error ({}): {}", status, error_text));
     }
 
-    Ok(full_response.trim().to_string())
+    let body: Value = response.json().await?;
+    if let Some(error) = body.get("error") {
+      return Err(anyhow!("AI model error: {}", error));
+    }
+    Ok(parse_reply(&body["message"]))
   }
 }
 
+/// Messages in Ollama's format: tool calls carry no ids, so results name the
+/// tool they answer instead
+fn wire_messages(messages: &[ChatMessage]) -> Vec<Value> {
+  messages
+    .iter()
+    .map(|message| {
+      let mut wire = json!({ "role": message.role, "content": message.content });
+      if !message.tool_calls.is_empty() {
+        wire["tool_calls"] = message
+          .tool_calls
+          .iter()
+          .map(|call| json!({ "function": { "name": call.name, "arguments": call.arguments } }))
+          .collect();
+      }
+      if let Some(call) = &message.tool_call {
+        wire["tool_name"] = json!(call.name);
+      }
+      wire
+    })
+    .collect()
+}
+
+/// The assistant message of a non-streamed `/api/chat` answer
+fn parse_reply(message: &Value) -> ChatMessage {
+  let mut reply = ChatMessage::assistant(message["content"].as_str().unwrap_or_default());
+  reply.tool_calls = message["tool_calls"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|call| {
+      let function = call.get("function")?;
+      Some(ToolCall {
+        id: None,
+        name: function.get("name")?.as_str()?.to_string(),
+        arguments: tool_arguments(function.get("arguments")),
+      })
+    })
+    .collect();
+  reply
+}
+
 impl Drop for OllamaClient {
   fn drop(&mut self) {
     let _ = self.stop_if_spawned();
@@ -467,9 +500,9 @@ enum GpuType {
 /// Detect GPU capabilities using system commands
 fn detect_gpu() -> GpuType {
   // Check for NVIDIA GPU
-  if let Ok(_) = which::which("nvidia-smi") {
+  if which::which("nvidia-smi").is_ok() {
     if let Ok(output) = std::process::Command::new("nvidia-smi")
-      .args(&["--query-gpu=name", "--format=csv,noheader,nounits"])
+      .args(["--query-gpu=name", "--format=csv,noheader,nounits"])
       .output()
     {
       if output.status.success() {
@@ -482,9 +515,9 @@ fn detect_gpu() -> GpuType {
   }
 
   // Check for AMD GPU (ROCm)
-  if let Ok(_) = which::which("rocm-smi") {
+  if which::which("rocm-smi").is_ok() {
     if let Ok(output) = std::process::Command::new("rocm-smi")
-      .args(&["--showproductname"])
+      .args(["--showproductname"])
       .output()
     {
       if output.status.success() {
@@ -585,8 +618,33 @@ fn detect_hardware() -> HardwareInfo {
 /// Determine the best model based on comprehensive hardware analysis
 pub fn select_model_by_ram() -> String {
   let hw = detect_hardware();
+  let model = model_for_hardware(&hw);
+
+  // Print comprehensive hardware info
+  let gpu_info = match &hw.gpu_type {
+    GpuType::NvidiaGpu(name) => format!("NVIDIA {} ({:.1}GB VRAM)", name, hw.gpu_memory_gb),
+    GpuType::AmdGpu(name) => format!("AMD {} ({:.1}GB VRAM)", name, hw.gpu_memory_gb),
+    GpuType::AppleSilicon => "Apple Silicon (Unified Memory)".to_string(),
+    GpuType::IntelGpu => "Intel Integrated Graphics".to_string(),
+    GpuType::None => "CPU Only".to_string(),
+  };
+
+  println!(
+    "Hardware detected - GPU: {}, CPU: {} cores, RAM: {:.1}GB total, {:.1}GB available",
+    gpu_info, hw.cpu_cores, hw.total_memory_gb, hw.available_memory_gb
+  );
+  println!("Selected model '{}' for optimal performance", model);
 
-  let model = match &hw.gpu_type {
+  model.to_string()
+}
+
+/// The model `select_model_by_ram` picks, without printing the hardware
+pub fn quiet_model_by_ram() -> String {
+  model_for_hardware(&detect_hardware()).to_string()
+}
+
+fn model_for_hardware(hw: &HardwareInfo) -> &'static str {
+  match &hw.gpu_type {
     GpuType::NvidiaGpu(_name) => {
       // NVIDIA GPUs can handle larger models efficiently
       if hw.gpu_memory_gb >= 16.0 {
@@ -629,33 +687,215 @@ pub fn select_model_by_ram() -> String {
         "tinyllama"
       }
     }
-  };
+  }
+}
 
-  // Print comprehensive hardware info
-  let gpu_info = match &hw.gpu_type {
-    GpuType::NvidiaGpu(name) => format!("NVIDIA {} ({:.1}GB VRAM)", name, hw.gpu_memory_gb),
-    GpuType::AmdGpu(name) => format!("AMD {} ({:.1}GB VRAM)", name, hw.gpu_memory_gb),
-    GpuType::AppleSilicon => "Apple Silicon (Unified Memory)".to_string(),
-    GpuType::IntelGpu => "Intel Integrated Graphics".to_string(),
-    GpuType::None => "CPU Only".to_string(),
-  };
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ai::mock_ollama::MockOllama;
+
+  async fn collect(client: &OllamaClient, prompt: &str) -> (Result<String>, Vec<String>) {
+    let mut tokens = Vec::new();
+    let result = client
+      .generate("gemma:2b", prompt, &mut |token: &str| {
+        tokens.push(token.to_string())
+      })
+      .await;
+    (result, tokens)
+  }
 
-  println!(
-    "Hardware detected - GPU: {}, CPU: {} cores, RAM: {:.1}GB total, {:.1}GB available",
-    gpu_info, hw.cpu_cores, hw.total_memory_gb, hw.available_memory_gb
-  );
-  println!("Selected model '{}' for optimal performance", model);
+  #[tokio::test]
+  async fn long_answers_outlast_the_timeout() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Starts answering at once, then pauses longer than the 500 ms timeout
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      let (mut socket, _) = listener.accept().await.unwrap();
+      let mut request = [0; 4096];
+      let _ = socket.read(&mut request).await.unwrap();
+      let chunk = |line: &str| format!("{:x}\r\n{}\n\r\n", line.len() + 1, line);
+      let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+      let first = chunk(r#"{"response":"Safe to ","done":false}"#);
+      socket
+        .write_all(format!("{}{}", head, first).as_bytes())
+        .await
+        .unwrap();
+      tokio::time::sleep(Duration::from_millis(800)).await;
+      let rest = chunk(r#"{"response":"delete.","done":true}"#);
+      socket
+        .write_all(format!("{}0\r\n\r\n", rest).as_bytes())
+        .await
+        .unwrap();
+    });
 
-  model.to_string()
-}
+    let mock = MockOllama::start().await;
+    let mut settings = mock.settings();
+    settings.base_url.value = format!("http://{}", address);
+    let (result, tokens) = collect(&OllamaClient::new(&settings), "target?").await;
+
+    assert_eq!(result.unwrap(), "Safe to delete.");
+    assert_eq!(tokens, vec!["Safe to ", "delete."]);
+  }
+
+  #[tokio::test]
+  async fn streams_generated_text() {
+    let mock = MockOllama::start().await;
+    mock
+      .stream(
+        "/api/generate",
+        vec![
+          r#"{"response":"Safe to ","done":false}"#.to_string(),
+          "not json".to_string(),
+          r#"{"response":"delete.","done":false}"#.to_string(),
+          r#"{"response":"","done":true}"#.to_string(),
+          r#"{"response":" ignored after done","done":false}"#.to_string(),
+        ],
+      )
+      .await;
+
+    let (result, tokens) = collect(&mock.client(), "What is node_modules?").await;
+
+    assert_eq!(result.unwrap(), "Safe to delete.");
+    assert_eq!(tokens, vec!["Safe to ", "delete."]);
+
+    let requests = mock.requests("/api/generate").await;
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["model"], "gemma:2b");
+    assert_eq!(requests[0]["prompt"], "What is node_modules?");
+    assert_eq!(requests[0]["stream"], true);
+  }
+
+  #[tokio::test]
+  async fn sends_configured_options() {
+    let mock = MockOllama::start().await;
+    mock.generate(&["ok"]).await;
+    let mut settings = mock.settings();
+    settings.temperature.value = 0.5;
+    settings.context_window = Some(crate::ai::settings::Setting {
+      value: 8192,
+      source: crate::ai::settings::Source::ConfigFile,
+    });
 
-/// Ensure model is available, pull if necessary
-pub async fn ensure_model_available(client: &OllamaClient, model: &str) -> Result<()> {
-  let available_models = client.list_models().await?;
+    let client = OllamaClient::new(&settings);
+    client
+      .generate("phi", "hi", &mut |_: &str| {})
+      .await
+      .unwrap();
+
+    let options = &mock.requests("/api/generate").await[0]["options"];
+    assert_eq!(options["temperature"], 0.5);
+    assert_eq!(options["num_ctx"], 8192);
+    assert_eq!(
+      options["num_predict"],
+      crate::ai::settings::DEFAULT_MAX_TOKENS
+    );
+  }
+
+  #[tokio::test]
+  async fn chats_with_message_roles() {
+    let mock = MockOllama::start().await;
+    mock.chat(&["It holds ", "dependencies."]).await;
+    let messages = vec![
+      ChatMessage::system("You are DevTidy's assistant"),
+      ChatMessage::user("What is node_modules?"),
+    ];
+
+    let answer = mock
+      .client()
+      .chat("gemma:2b", &messages, &mut |_: &str| {})
+      .await
+      .unwrap();
+
+    assert_eq!(answer, "It holds dependencies.");
+    let request = &mock.requests("/api/chat").await[0];
+    assert_eq!(request["messages"][0]["role"], "system");
+    assert_eq!(request["messages"][1]["content"], "What is node_modules?");
+  }
 
-  if !available_models.iter().any(|m| m.starts_with(model)) {
-    client.pull_model(model).await?;
+  #[tokio::test]
+  async fn reports_model_and_http_errors() {
+    let mock = MockOllama::start().await;
+    mock
+      .stream(
+        "/api/generate",
+        vec![r#"{"error":"model 'gemma:2b' not found"}"#.to_string()],
+      )
+      .await;
+    let (result, _) = collect(&mock.client(), "hi").await;
+    assert!(result.unwrap_err().to_string().contains("not found"));
+
+    let mock = MockOllama::start().await;
+    mock.fail("/api/generate", 500, "out of memory").await;
+    let (result, _) = collect(&mock.client(), "hi").await;
+    let error = result.unwrap_err().to_string();
+    assert!(
+      error.contains("500") && error.contains("out of memory"),
+      "{}",
+      error
+    );
+
+    let mock = MockOllama::start().await;
+    mock.generate(&[]).await;
+    let (result, _) = collect(&mock.client(), "hi").await;
+    assert!(result.is_err());
   }
 
-  Ok(())
+  #[tokio::test]
+  async fn pulls_only_missing_models() {
+    let mock = MockOllama::start().await;
+    mock.with_models(&["phi:latest"]).await;
+    mock
+      .pull(&["pulling manifest", "downloading", "success"])
+      .await;
+    let client = mock.client();
+
+    client.ensure_model("phi").await.unwrap();
+    assert!(mock.requests("/api/pull").await.is_empty());
+
+    client.ensure_model("gemma:2b").await.unwrap();
+    let pulls = mock.requests("/api/pull").await;
+    assert_eq!(pulls.len(), 1);
+    assert_eq!(pulls[0]["name"], "gemma:2b");
+  }
+
+  #[tokio::test]
+  async fn failed_pulls_are_errors() {
+    let mock = MockOllama::start().await;
+    mock
+      .stream(
+        "/api/pull",
+        vec![
+          r#"{"status":"pulling manifest"}"#.to_string(),
+          r#"{"error":"pull model manifest: file does not exist"}"#.to_string(),
+        ],
+      )
+      .await;
+
+    let error = mock.client().pull_model("nope").await.unwrap_err();
+
+    assert!(error.to_string().contains("file does not exist"));
+  }
+
+  #[tokio::test]
+  async fn health_check_follows_the_server() {
+    let mock = MockOllama::start().await;
+    mock.with_models(&["phi"]).await;
+    let client = mock.client();
+
+    assert!(client.health_check().await.unwrap());
+    assert_eq!(client.list_models().await.unwrap(), vec!["phi"]);
+
+    // Nothing listens on a port that was just released
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+      .unwrap()
+      .local_addr()
+      .unwrap()
+      .port();
+    let mut settings = mock.settings();
+    settings.base_url.value = format!("http://127.0.0.1:{}", port);
+    assert!(!OllamaClient::new(&settings).health_check().await.unwrap());
+  }
 }
@@ -0,0 +1,380 @@
+use super::backend::{
+  for_each_line, send_before, tool_arguments, ChatMessage, LlmBackend, TokenSink, ToolCall,
+  ToolSpec,
+};
+use super::settings::AiSettings;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Client for servers implementing the OpenAI HTTP API, such as llama.cpp
+/// server, vLLM or LM Studio
+pub struct OpenAiClient {
+  client: Client,
+  base_url: String,
+  api_key: Option<String>,
+  temperature: f32,
+  max_tokens: u32,
+  timeout: Duration,
+}
+
+impl OpenAiClient {
+  pub fn new(settings: &AiSettings, api_key: Option<String>) -> Self {
+    Self {
+      client: Client::new(),
+      base_url: settings.base_url.value.trim_end_matches('/').to_string(),
+      api_key,
+      temperature: settings.temperature.value,
+      max_tokens: settings.max_tokens.value,
+      timeout: settings.timeout.value,
+    }
+  }
+
+  fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+    match &self.api_key {
+      Some(key) => request.bearer_auth(key),
+      None => request,
+    }
+  }
+
+  /// POST a streaming chat completion and pass its text to `on_token`
+  async fn complete(&self, payload: Value, on_token: &mut TokenSink<'_>) -> Result<String> {
+    // Loading the model counts towards the timeout, streaming the answer does not
+    let deadline = Instant::now() + self.timeout;
+    let request = self
+      .authorize(
+        self
+          .client
+          .post(format!("{}/chat/completions", self.base_url)),
+      )
+      .json(&payload);
+    let response = send_before(request, deadline, self.timeout).await?;
+
+    if !response.status().is_success() {
+      let status = response.status();
+      let error_text = response.text().await.unwrap_or_default();
+      return Err(anyhow!("API error ({}): {}", status, error_text));
+    }
+
+    let mut full_response = String::new();
+    for_each_line(
+      response,
+      Some((deadline, self.timeout)),
+      |line| match parse_event(line)? {
+        Event::Done => Ok(false),
+        Event::Text(text) => {
+          on_token(&text);
+          full_response.push_str(&text);
+          Ok(true)
+        }
+        Event::Skip => Ok(true),
+      },
+    )
+    .await?;
+
+    if full_response.trim().is_empty() {
+      return Err(anyhow!("No response received from AI"));
+    }
+
+    Ok(full_response.trim().to_string())
+  }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiClient {
+  fn describe(&self) -> String {
+    format!("OpenAI-compatible server at {}", self.base_url)
+  }
+
+  async fn ensure_ready(&mut self) -> Result<()> {
+    if self.health_check().await? {
+      Ok(())
+    } else {
+      Err(anyhow!(
+        "No OpenAI-compatible server answers at {}",
+        self.base_url
+      ))
+    }
+  }
+
+  async fn health_check(&self) -> Result<bool> {
+    let response = self
+      .authorize(self.client.get(format!("{}/models", self.base_url)))
+      .timeout(Duration::from_secs(5))
+      .send()
+      .await;
+
+    Ok(response.is_ok_and(|resp| resp.status().is_success()))
+  }
+
+  async fn list_models(&self) -> Result<Vec<String>> {
+    let response = self
+      .authorize(self.client.get(format!("{}/models", self.base_url)))
+      .send()
+      .await?;
+
+    if !response.status().is_success() {
+      return Err(anyhow!("Failed to list models ({})", response.status()));
+    }
+
+    let body: Value = response.json().await?;
+    Ok(model_ids(&body))
+  }
+
+  /// Servers like llama.cpp serve a single model, so use the first one listed
+  async fn default_model(&self) -> Result<String> {
+    self
+      .list_models()
+      .await?
+      .into_iter()
+      .next()
+      .ok_or_else(|| anyhow!("{} does not list any models", self.base_url))
+  }
+
+  /// Models cannot be downloaded through this API, only checked
+  async fn ensure_model(&self, model: &str) -> Result<()> {
+    let models = self.list_models().await?;
+    if models.iter().any(|m| m == model) {
+      Ok(())
+    } else {
+      Err(anyhow!(
+        "Model '{}' is not served by {} (available: {})",
+        model,
+        self.base_url,
+        models.join(", ")
+      ))
+    }
+  }
+
+  async fn generate(
+    &self,
+    model: &str,
+    prompt: &str,
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String> {
+    self
+      .chat(model, &[ChatMessage::user(prompt)], on_token)
+      .await
+  }
+
+  async fn generate_structured(&self, model: &str, prompt: &str, schema: &Value) -> Result<String> {
+    let payload = json!({
+        "model": model,
+        "messages": [ChatMessage::user(prompt)],
+        "stream": true,
+        "temperature": self.temperature,
+        "max_tokens": self.max_tokens,
+        "response_format": {
+            "type": "json_schema",
+            "json_schema": { "name": "response", "schema": schema }
+        }
+    });
+    self.complete(payload, &mut |_: &str| {}).await
+  }
+
+  async fn chat(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    on_token: &mut TokenSink<'_>,
+  ) -> Result<String> {
+    let payload = json!({
+        "model": model,
+        "messages": wire_messages(messages),
+        "stream": true,
+        "temperature": self.temperature,
+        "max_tokens": self.max_tokens,
+        "top_p": 0.9
+    });
+    self.complete(payload, on_token).await
+  }
+
+  async fn chat_with_tools(
+    &self,
+    model: &str,
+    messages: &[ChatMessage],
+    tools: &[ToolSpec],
+  ) -> Result<ChatMessage> {
+    let payload = json!({
+        "model": model,
+        "messages": wire_messages(messages),
+        "tools": tools.iter().map(ToolSpec::to_json).collect::<Vec<_>>(),
+        "stream": false,
+        "temperature": self.temperature,
+        "max_tokens": self.max_tokens
+    });
+    // Without streaming the reply only starts once it is complete
+    let request = self
+      .authorize(
+        self
+          .client
+          .post(format!("{}/chat/completions", self.base_url)),
+      )
+      .json(&payload);
+    let response = send_before(request, Instant::now() + self.timeout, self.timeout).await?;
+
+    if !response.status().is_success() {
+      let status = response.status();
+      let error_text = response.text().await.unwrap_or_default();
+      return Err(anyhow!("API error ({}): {}", status, error_text));
+    }
+
+    let body: Value = response.json().await?;
+    parse_reply(&body)
+  }
+}
+
+/// Messages in the OpenAI format: call arguments are JSON strings and results
+/// refer to their call by id
+fn wire_messages(messages: &[ChatMessage]) -> Vec<Value> {
+  messages
+    .iter()
+    .map(|message| {
+      let mut wire = json!({ "role": message.role, "content": message.content });
+      if !message.tool_calls.is_empty() {
+        wire["tool_calls"] = message
+          .tool_calls
+          .iter()
+          .map(|call| {
+            json!({
+                "id": call.id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments.to_string() }
+            })
+          })
+          .collect();
+      }
+      if let Some(call) = &message.tool_call {
+        wire["tool_call_id"] = json!(call.id);
+      }
+      wire
+    })
+    .collect()
+}
+
+/// The assistant message of a non-streamed chat completion
+fn parse_reply(body: &Value) -> Result<ChatMessage> {
+  if let Some(error) = body.get("error") {
+    return Err(anyhow!("AI model error: {}", error));
+  }
+  let message = body
+    .pointer("/choices/0/message")
+    .ok_or_else(|| anyhow!("No response received from AI"))?;
+
+  let mut reply = ChatMessage::assistant(message["content"].as_str().unwrap_or_default());
+  reply.tool_calls = message["tool_calls"]
+    .as_array()
+    .into_iter()
+    .flatten()
+    .filter_map(|call| {
+      let function = call.get("function")?;
+      Some(ToolCall {
+        id: call.get("id").and_then(Value::as_str).map(str::to_string),
+        name: function.get("name")?.as_str()?.to_string(),
+        arguments: tool_arguments(function.get("arguments")),
+      })
+    })
+    .collect();
+  Ok(reply)
+}
+
+/// One server-sent event line of a streamed chat completion
+#[derive(Debug, PartialEq)]
+enum Event {
+  Text(String),
+  Done,
+  Skip,
+}
+
+fn parse_event(line: &str) -> Result<Event> {
+  let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+    // Comments and `event:` lines carry no text
+    return Ok(Event::Skip);
+  };
+  if data == "[DONE]" {
+    return Ok(Event::Done);
+  }
+
+  let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+    return Ok(Event::Skip);
+  };
+  if let Some(error) = chunk.get("error") {
+    return Err(anyhow!("AI model error: {}", error));
+  }
+
+  let text = chunk
+    .pointer("/choices/0/delta/content")
+    .and_then(|c| c.as_str())
+    .filter(|c| !c.is_empty());
+  Ok(match text {
+    Some(text) => Event::Text(text.to_string()),
+    None => Event::Skip,
+  })
+}
+
+fn model_ids(body: &Value) -> Vec<String> {
+  body
+    .get("data")
+    .and_then(|data| data.as_array())
+    .into_iter()
+    .flatten()
+    .filter_map(|model| model.get("id")?.as_str().map(str::to_string))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_streamed_chat_events() {
+    assert_eq!(
+      parse_event(r#"data: {"choices":[{"delta":{"content":"Safe"}}]}"#).unwrap(),
+      Event::Text("Safe".to_string())
+    );
+    assert_eq!(
+      parse_event(r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#).unwrap(),
+      Event::Skip
+    );
+    assert_eq!(parse_event(": keep-alive").unwrap(), Event::Skip);
+    assert_eq!(parse_event("data: [DONE]").unwrap(), Event::Done);
+    assert!(parse_event(r#"data: {"error":{"message":"overloaded"}}"#).is_err());
+  }
+
+  #[test]
+  fn round_trips_tool_calls() {
+    let body = json!({ "choices": [{ "message": {
+        "role": "assistant",
+        "content": null,
+        "tool_calls": [{
+            "id": "call_1",
+            "type": "function",
+            "function": { "name": "get_size", "arguments": "{\"path\": \"/work/app/target\"}" }
+        }]
+    } }] });
+
+    let reply = parse_reply(&body).unwrap();
+    let call = &reply.tool_calls[0];
+    assert_eq!(call.id.as_deref(), Some("call_1"));
+    assert_eq!(call.arguments, json!({ "path": "/work/app/target" }));
+
+    let wire = wire_messages(&[reply.clone(), ChatMessage::tool_result(call, "4 KiB")]);
+    assert_eq!(
+      wire[0]["tool_calls"][0]["function"]["arguments"],
+      r#"{"path":"/work/app/target"}"#
+    );
+    assert_eq!(wire[1]["tool_call_id"], "call_1");
+    assert_eq!(wire[1]["role"], "tool");
+  }
+
+  #[test]
+  fn lists_model_ids() {
+    let body = json!({ "object": "list", "data": [{ "id": "qwen2.5-coder" }, { "id": "llama3" }] });
+
+    assert_eq!(model_ids(&body), vec!["qwen2.5-coder", "llama3"]);
+    assert!(model_ids(&json!({})).is_empty());
+  }
+}
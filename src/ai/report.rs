@@ -0,0 +1,748 @@
+use super::backend::LlmBackend;
+use super::context::DevTidyContext;
+use super::utils::{create_spinner, format_size};
+use crate::core::constants::{CLEANABLE_PATTERNS, ECOSYSTEM_MANIFESTS, ORPHAN_MANIFESTS};
+use crate::core::models::CleanableItem;
+use crate::services::scanner::outermost_items;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Ecosystem of projects and items nothing identifies
+const OTHER_ECOSYSTEM: &str = "Other";
+/// Room left for the scan details when the instructions alone fill the budget
+const MIN_DETAILS_CHARS: usize = 1000;
+
+/// Group scanned items by project, the nearest directory above them that is not
+/// itself an artifact. Items inside another listed item are dropped: removing
+/// the outer one removes them too.
+pub(super) fn group_by_project(items: Vec<CleanableItem>) -> BTreeMap<PathBuf, Vec<CleanableItem>> {
+  let outermost: HashSet<PathBuf> = outermost_items(&items)
+    .into_iter()
+    .map(|item| item.path.clone())
+    .collect();
+
+  let mut projects: BTreeMap<PathBuf, Vec<CleanableItem>> = BTreeMap::new();
+  for item in items
+    .into_iter()
+    .filter(|item| outermost.contains(&item.path))
+  {
+    projects
+      .entry(project_dir(&item.path).to_path_buf())
+      .or_default()
+      .push(item);
+  }
+  projects
+}
+
+/// The project holding the artifact at `path`: `app` for `app/target/debug`
+fn project_dir(path: &Path) -> &Path {
+  path
+    .ancestors()
+    .skip(1)
+    .find(|dir| {
+      !dir
+        .file_name()
+        .is_some_and(|name| CLEANABLE_PATTERNS.contains_key(name.to_string_lossy().as_ref()))
+    })
+    .unwrap_or(path)
+}
+
+/// One project of the report with the cleanable items found in it
+pub(super) struct ProjectSummary {
+  pub path: PathBuf,
+  /// From the manifests in the project, or else from the items' names
+  pub ecosystems: Vec<&'static str>,
+  pub items: Vec<CleanableItem>,
+  pub size: u64,
+}
+
+impl ProjectSummary {
+  fn new(path: PathBuf, items: Vec<CleanableItem>) -> Self {
+    let mut ecosystems: Vec<&'static str> = Vec::new();
+    for (manifest, ecosystem) in ECOSYSTEM_MANIFESTS {
+      if path.join(manifest).exists() && !ecosystems.contains(ecosystem) {
+        ecosystems.push(ecosystem);
+      }
+    }
+    if ecosystems.is_empty() {
+      for ecosystem in items
+        .iter()
+        .filter_map(|item| item_ecosystems(item).first().copied())
+      {
+        if !ecosystems.contains(&ecosystem) {
+          ecosystems.push(ecosystem);
+        }
+      }
+    }
+    if ecosystems.is_empty() {
+      ecosystems.push(OTHER_ECOSYSTEM);
+    }
+
+    let size = items.iter().map(|item| item.size).sum();
+    Self {
+      path,
+      ecosystems,
+      items,
+      size,
+    }
+  }
+
+  /// The ecosystem an item counts towards: the first one its name points to
+  /// that the project uses, e.g. `target` in a Maven project is JVM output
+  fn item_ecosystem(&self, item: &CleanableItem) -> &'static str {
+    let candidates = item_ecosystems(item);
+    candidates
+      .iter()
+      .find(|ecosystem| self.ecosystems.contains(ecosystem))
+      .or(candidates.first())
+      .copied()
+      .unwrap_or(self.ecosystems[0])
+  }
+
+  /// The project and its items, one per line, for a prompt. A project whose
+  /// listing is longer than `budget` characters is split into parts that each
+  /// repeat the project line.
+  fn listings(&self, budget: usize) -> Vec<String> {
+    let header = format!(
+      "Project: {} [{}], {}",
+      self.path.display(),
+      self.ecosystems.join(", "),
+      format_size(self.size)
+    );
+    let mut listings = vec![header.clone()];
+    for item in &self.items {
+      let name = item
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| item.display_path());
+      let orphaned = if item.orphaned { " [orphaned]" } else { "" };
+      let protected = if item.protected { " [protected]" } else { "" };
+      let line = format!(
+        "- {} ({}): {}{}{}",
+        name,
+        item.display_size(),
+        item.display_info(),
+        orphaned,
+        protected
+      );
+      let last = listings.last_mut().expect("starts with the header");
+      if last.len() > header.len() && last.len() + 1 + line.len() > budget {
+        listings.push(format!("{} (continued)\n{}", header, line));
+      } else {
+        last.push('\n');
+        last.push_str(&line);
+      }
+    }
+    listings
+  }
+}
+
+/// Ecosystems whose manifests an item belongs to, going by its name
+fn item_ecosystems(item: &CleanableItem) -> Vec<&'static str> {
+  let name = item
+    .path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let Some(manifests) = ORPHAN_MANIFESTS.get(name.as_str()) else {
+    return Vec::new();
+  };
+  let mut ecosystems = Vec::new();
+  for manifest in manifests.iter() {
+    let found = ECOSYSTEM_MANIFESTS
+      .iter()
+      .find(|(name, _)| name == manifest)
+      .map(|(_, ecosystem)| *ecosystem);
+    if let Some(ecosystem) = found.filter(|ecosystem| !ecosystems.contains(ecosystem)) {
+      ecosystems.push(ecosystem);
+    }
+  }
+  ecosystems
+}
+
+/// Totals of one ecosystem across all projects
+#[derive(Debug, PartialEq)]
+pub(super) struct EcosystemTotal {
+  pub ecosystem: &'static str,
+  pub projects: usize,
+  pub items: usize,
+  pub size: u64,
+}
+
+/// The scan summary and the model's cleanup plan
+pub(super) struct Report {
+  pub roots: Vec<PathBuf>,
+  pub model: String,
+  /// Largest first
+  pub projects: Vec<ProjectSummary>,
+  /// Markdown written by the model
+  pub plan: String,
+}
+
+impl Report {
+  /// Items of all projects; `group_by_project` already dropped nested ones,
+  /// so every byte is counted once
+  fn items(&self) -> impl Iterator<Item = &CleanableItem> {
+    self.projects.iter().flat_map(|project| &project.items)
+  }
+
+  fn size(&self) -> u64 {
+    self.projects.iter().map(|project| project.size).sum()
+  }
+
+  /// Per-ecosystem totals, largest first
+  pub fn ecosystems(&self) -> Vec<EcosystemTotal> {
+    let mut totals: Vec<EcosystemTotal> = Vec::new();
+    for project in &self.projects {
+      let mut counted: Vec<&'static str> = Vec::new();
+      for item in &project.items {
+        let ecosystem = project.item_ecosystem(item);
+        let index = match totals.iter().position(|total| total.ecosystem == ecosystem) {
+          Some(index) => index,
+          None => {
+            totals.push(EcosystemTotal {
+              ecosystem,
+              projects: 0,
+              items: 0,
+              size: 0,
+            });
+            totals.len() - 1
+          }
+        };
+        let total = &mut totals[index];
+        if !counted.contains(&ecosystem) {
+          counted.push(ecosystem);
+          total.projects += 1;
+        }
+        total.items += 1;
+        total.size += item.size;
+      }
+    }
+    totals.sort_by(|a, b| b.size.cmp(&a.size).then(a.ecosystem.cmp(b.ecosystem)));
+    totals
+  }
+
+  /// One sentence with the totals
+  fn headline(&self) -> String {
+    let items = self.items().count();
+    let orphaned: Vec<&CleanableItem> = self.items().filter(|item| item.orphaned).collect();
+    let mut headline = format!(
+      "{} of cleanable files in {} items across {} projects",
+      format_size(self.size()),
+      items,
+      self.projects.len()
+    );
+    if !orphaned.is_empty() {
+      headline.push_str(&format!(
+        "; {} items ({}) belong to projects whose manifest is gone",
+        orphaned.len(),
+        format_size(orphaned.iter().map(|item| item.size).sum())
+      ));
+    }
+    headline
+  }
+
+  /// The totals as plain text, for the plan prompt
+  fn overview(&self) -> String {
+    let mut lines = vec![
+      format!("Totals: {}.", self.headline()),
+      "By ecosystem:".to_string(),
+    ];
+    lines.extend(self.ecosystems().iter().map(|total| {
+      format!(
+        "- {}: {} in {} items across {} projects",
+        total.ecosystem,
+        format_size(total.size),
+        total.items,
+        total.projects
+      )
+    }));
+    lines.join("\n")
+  }
+}
+
+/// Scan results turned into a report, with a plan from the model. `budget` is
+/// the most characters a prompt may have; project listings that do not fit
+/// next to the instructions are condensed in parts first, and the notes again
+/// until they fit, so every prompt fits the model's context.
+pub(super) async fn build_report(
+  backend: &dyn LlmBackend,
+  model: &str,
+  roots: &[PathBuf],
+  items: Vec<CleanableItem>,
+  budget: usize,
+) -> Result<Report> {
+  let mut projects: Vec<ProjectSummary> = group_by_project(items)
+    .into_iter()
+    .map(|(path, items)| ProjectSummary::new(path, items))
+    .collect();
+  projects.sort_by(|a, b| b.size.cmp(&a.size).then(a.path.cmp(&b.path)));
+  let mut report = Report {
+    roots: roots.to_vec(),
+    model: model.to_string(),
+    projects,
+    plan: String::new(),
+  };
+
+  let context = DevTidyContext::new();
+  let overview = report.overview();
+  // The instructions and the app context take their share of every prompt
+  let instructions = [
+    context.create_report_plan_prompt(&overview, "").len(),
+    context
+      .create_report_chunk_prompt(usize::MAX, usize::MAX, "")
+      .len(),
+    context
+      .create_report_notes_prompt(usize::MAX, usize::MAX, "")
+      .len(),
+  ];
+  let budget = budget
+    .saturating_sub(instructions.into_iter().max().unwrap_or_default())
+    .max(MIN_DETAILS_CHARS);
+
+  let listings: Vec<String> = report
+    .projects
+    .iter()
+    .flat_map(|project| project.listings(budget))
+    .collect();
+  let mut parts = chunk(listings, budget);
+  let mut round = 0;
+  while parts.len() > 1 || parts[0].len() > budget {
+    let count = parts.len();
+    let length: usize = parts.iter().map(String::len).sum();
+    let mut notes = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+      let prompt = if round == 0 {
+        context.create_report_chunk_prompt(index + 1, count, part)
+      } else {
+        context.create_report_notes_prompt(index + 1, count, part)
+      };
+      let message = format!("Summarising part {} of {}", index + 1, count);
+      notes.push(ask(backend, model, &prompt, &message).await?);
+    }
+    round += 1;
+
+    // Notes that do not get shorter are cut to fit instead of asked about forever
+    if notes.iter().map(String::len).sum::<usize>() >= length {
+      notes = vec![truncate(&notes.join("\n\n"), budget)];
+    }
+    parts = chunk(notes, budget);
+  }
+  let details = parts.swap_remove(0);
+
+  let prompt = context.create_report_plan_prompt(&overview, &details);
+  report.plan = ask(backend, model, &prompt, "Writing the cleanup plan").await?;
+  Ok(report)
+}
+
+/// Cut `text` to at most `budget` bytes, at a character boundary
+fn truncate(text: &str, budget: usize) -> String {
+  let end = text
+    .char_indices()
+    .map(|(index, c)| index + c.len_utf8())
+    .take_while(|&end| end <= budget)
+    .last()
+    .unwrap_or(0);
+  text[..end].to_string()
+}
+
+/// Complete `prompt` behind a spinner
+async fn ask(backend: &dyn LlmBackend, model: &str, prompt: &str, message: &str) -> Result<String> {
+  let spinner = create_spinner(&format!("{} with {}", message, model), "cyan");
+  let answer = backend.generate(model, prompt, &mut |_: &str| {}).await;
+  spinner.finish_and_clear();
+  answer
+    .map(|answer| answer.trim().to_string())
+    .with_context(|| format!("{} failed", message))
+}
+
+/// Pack `listings` into as few chunks of at most `budget` characters as they
+/// fit in, keeping their order. A listing larger than the budget gets a chunk
+/// of its own.
+pub(super) fn chunk(listings: Vec<String>, budget: usize) -> Vec<String> {
+  let mut chunks: Vec<String> = Vec::new();
+  for listing in listings {
+    match chunks.last_mut() {
+      Some(last) if last.len() + 2 + listing.len() <= budget => {
+        last.push_str("\n\n");
+        last.push_str(&listing);
+      }
+      _ => chunks.push(listing),
+    }
+  }
+  if chunks.is_empty() {
+    chunks.push(String::new());
+  }
+  chunks
+}
+
+/// The report as Markdown
+pub(super) fn render_markdown(report: &Report) -> String {
+  let cell = |text: &str| text.replace('|', "\\|");
+  let mut out = String::from("# DevTidy cleanup report\n\n");
+  out.push_str(&format!(
+    "Scanned: {}\n\n",
+    report
+      .roots
+      .iter()
+      .map(|root| format!("`{}`", root.display()))
+      .collect::<Vec<_>>()
+      .join(", ")
+  ));
+  out.push_str(&format!("**{}.**\n\n", report.headline()));
+
+  out
+    .push_str("## By ecosystem\n\n| Ecosystem | Projects | Items | Size |\n|---|---:|---:|---:|\n");
+  for total in report.ecosystems() {
+    out.push_str(&format!(
+      "| {} | {} | {} | {} |\n",
+      total.ecosystem,
+      total.projects,
+      total.items,
+      format_size(total.size)
+    ));
+  }
+
+  out.push_str("\n## By project\n\n| Project | Ecosystems | Items | Size |\n|---|---|---:|---:|\n");
+  for project in &report.projects {
+    out.push_str(&format!(
+      "| {} | {} | {} | {} |\n",
+      cell(&project.path.display().to_string()),
+      project.ecosystems.join(", "),
+      project.items.len(),
+      format_size(project.size)
+    ));
+  }
+
+  out.push_str(&format!(
+    "\n## Cleanup plan\n\n_Suggested by {}. Review it before deleting anything._\n\n{}\n",
+    report.model, report.plan
+  ));
+  out
+}
+
+/// The report as a standalone HTML page. The plan is Markdown and is shown
+/// as preformatted text.
+pub(super) fn render_html(report: &Report) -> String {
+  let mut out = String::from(
+    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>DevTidy cleanup report</title>\n<style>\nbody { font-family: sans-serif; max-width: 60em; margin: 2em auto; }\ntable { border-collapse: collapse; }\nth, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }\ntd.size { text-align: right; }\npre { white-space: pre-wrap; background: #f6f6f6; padding: 1em; }\n</style>\n</head>\n<body>\n<h1>DevTidy cleanup report</h1>\n",
+  );
+  out.push_str(&format!(
+    "<p>Scanned: {}</p>\n<p><strong>{}.</strong></p>\n",
+    report
+      .roots
+      .iter()
+      .map(|root| format!("<code>{}</code>", escape(&root.display().to_string())))
+      .collect::<Vec<_>>()
+      .join(", "),
+    escape(&report.headline())
+  ));
+
+  out.push_str("<h2>By ecosystem</h2>\n<table>\n<tr><th>Ecosystem</th><th>Projects</th><th>Items</th><th>Size</th></tr>\n");
+  for total in report.ecosystems() {
+    out.push_str(&format!(
+      "<tr><td>{}</td><td class=\"size\">{}</td><td class=\"size\">{}</td><td class=\"size\">{}</td></tr>\n",
+      escape(total.ecosystem),
+      total.projects,
+      total.items,
+      format_size(total.size)
+    ));
+  }
+
+  out.push_str("</table>\n<h2>By project</h2>\n<table>\n<tr><th>Project</th><th>Ecosystems</th><th>Items</th><th>Size</th></tr>\n");
+  for project in &report.projects {
+    out.push_str(&format!(
+      "<tr><td>{}</td><td>{}</td><td class=\"size\">{}</td><td class=\"size\">{}</td></tr>\n",
+      escape(&project.path.display().to_string()),
+      escape(&project.ecosystems.join(", ")),
+      project.items.len(),
+      format_size(project.size)
+    ));
+  }
+
+  out.push_str(&format!(
+    "</table>\n<h2>Cleanup plan</h2>\n<p><em>Suggested by {}. Review it before deleting anything.</em></p>\n<pre>{}</pre>\n</body>\n</html>\n",
+    escape(&report.model),
+    escape(&report.plan)
+  ));
+  out
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+/// Whether `path` names an HTML file
+pub(super) fn is_html_path(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|extension| extension.to_str())
+    .is_some_and(|extension| {
+      extension.eq_ignore_ascii_case("html") || extension.eq_ignore_ascii_case("htm")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ai::mock_ollama::MockOllama;
+  use std::fs;
+
+  fn item(path: &Path, size: u64) -> CleanableItem {
+    CleanableItem::new(
+      path.to_path_buf(),
+      "directory".to_string(),
+      size,
+      String::new(),
+    )
+  }
+
+  #[test]
+  fn packs_listings_into_chunks_within_the_budget() {
+    let listings = vec![
+      "a".repeat(40),
+      "b".repeat(40),
+      "c".repeat(100),
+      "d".repeat(10),
+    ];
+
+    let chunks = chunk(listings, 90);
+
+    assert_eq!(
+      chunks,
+      vec![
+        format!("{}\n\n{}", "a".repeat(40), "b".repeat(40)),
+        "c".repeat(100),
+        "d".repeat(10),
+      ]
+    );
+  }
+
+  #[test]
+  fn groups_nested_items_under_their_project() {
+    let app = Path::new("/work/app");
+    let items = vec![
+      item(&app.join("target"), 300),
+      item(&app.join("target/debug"), 200),
+      item(&app.join("node_modules"), 100),
+      item(Path::new("/work/lib/target/release"), 50),
+    ];
+
+    let projects = group_by_project(items);
+
+    let paths: Vec<(&Path, Vec<&Path>)> = projects
+      .iter()
+      .map(|(project, items)| {
+        (
+          project.as_path(),
+          items.iter().map(|item| item.path.as_path()).collect(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      paths,
+      vec![
+        (
+          app,
+          vec![
+            app.join("target").as_path(),
+            app.join("node_modules").as_path()
+          ]
+        ),
+        (
+          Path::new("/work/lib"),
+          vec![Path::new("/work/lib/target/release")]
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn sorts_items_into_ecosystems() {
+    let tmp = tempfile::tempdir().unwrap();
+    let api = tmp.path().join("api");
+    fs::create_dir(&api).unwrap();
+    fs::write(api.join("pom.xml"), "<project/>").unwrap();
+    let gone = tmp.path().join("gone");
+
+    let report = Report {
+      roots: vec![tmp.path().to_path_buf()],
+      model: "phi".to_string(),
+      projects: vec![
+        ProjectSummary::new(api.clone(), vec![item(&api.join("target"), 300)]),
+        ProjectSummary::new(
+          gone.clone(),
+          vec![
+            item(&gone.join("node_modules"), 200),
+            item(&gone.join("target"), 50),
+          ],
+        ),
+      ],
+      plan: String::new(),
+    };
+
+    assert_eq!(report.projects[0].ecosystems, vec!["JVM"]);
+    assert_eq!(report.projects[1].ecosystems, vec!["Node.js", "Rust"]);
+    assert_eq!(
+      report.ecosystems(),
+      vec![
+        EcosystemTotal {
+          ecosystem: "JVM",
+          projects: 1,
+          items: 1,
+          size: 300
+        },
+        EcosystemTotal {
+          ecosystem: "Node.js",
+          projects: 1,
+          items: 1,
+          size: 200
+        },
+        EcosystemTotal {
+          ecosystem: "Rust",
+          projects: 1,
+          items: 1,
+          size: 50
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn renders_markdown_and_escaped_html() {
+    let report = Report {
+      roots: vec![PathBuf::from("/work")],
+      model: "phi".to_string(),
+      projects: vec![ProjectSummary::new(
+        PathBuf::from("/work/<web>"),
+        vec![item(Path::new("/work/<web>/node_modules"), 2048)],
+      )],
+      plan: "1. Delete node_modules & reinstall".to_string(),
+    };
+
+    let markdown = render_markdown(&report);
+    assert!(markdown.contains("| Node.js | 1 | 1 | 2 KiB |"));
+    assert!(markdown.contains("| /work/<web> | Node.js | 1 | 2 KiB |"));
+    assert!(markdown.ends_with("1. Delete node_modules & reinstall\n"));
+
+    let html = render_html(&report);
+    assert!(html.contains("<td>/work/&lt;web&gt;</td>"));
+    assert!(html.contains("<pre>1. Delete node_modules &amp; reinstall</pre>"));
+    assert!(!html.contains("<web>"));
+
+    assert!(is_html_path(Path::new("report.HTML")));
+    assert!(!is_html_path(Path::new("report.md")));
+  }
+
+  /// Items in three projects whose listings each take over half of the
+  /// smallest details budget
+  fn long_projects(root: &Path) -> Vec<CleanableItem> {
+    ["a", "b", "c"]
+      .iter()
+      .map(|project| {
+        let dir = root
+          .join(project)
+          .join("p".repeat(200))
+          .join("q".repeat(200))
+          .join("r".repeat(200));
+        item(&dir.join("node_modules"), 1024)
+      })
+      .collect()
+  }
+
+  #[tokio::test]
+  async fn condenses_large_scans_before_asking_for_the_plan() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    mock.generate(&["Clean node_modules first."]).await;
+
+    let items = long_projects(tmp.path());
+    let report = build_report(&mock.client(), "phi", &[tmp.path().to_path_buf()], items, 1)
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/generate").await;
+    assert_eq!(requests.len(), 4);
+    assert!(requests[0]["prompt"]
+      .as_str()
+      .unwrap()
+      .contains("This is part 1 of 3"));
+    assert!(requests[3]["prompt"]
+      .as_str()
+      .unwrap()
+      .contains("Node.js: 3 KiB in 3 items across 3 projects"));
+    assert_eq!(report.plan, "Clean node_modules first.");
+    assert_eq!(report.projects.len(), 3);
+  }
+
+  #[tokio::test]
+  async fn notes_that_do_not_shrink_are_cut_to_the_budget() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mock = MockOllama::start().await;
+    let rambling = "x".repeat(2000);
+    mock.generate(&[&rambling]).await;
+
+    let items = long_projects(tmp.path());
+    build_report(&mock.client(), "phi", &[tmp.path().to_path_buf()], items, 1)
+      .await
+      .unwrap();
+
+    let requests = mock.requests("/api/generate").await;
+    assert_eq!(requests.len(), 4);
+    let plan_prompt = requests[3]["prompt"].as_str().unwrap();
+    assert!(plan_prompt.contains(&"x".repeat(MIN_DETAILS_CHARS)));
+    assert!(!plan_prompt.contains(&"x".repeat(MIN_DETAILS_CHARS + 1)));
+  }
+
+  #[test]
+  fn splits_projects_longer_than_the_budget() {
+    let app = Path::new("/w/app");
+    let names = ["node_modules", "dist", ".next", "coverage"];
+    let project = ProjectSummary::new(
+      app.to_path_buf(),
+      names
+        .iter()
+        .map(|name| item(&app.join(name), 1024))
+        .collect(),
+    );
+
+    let listings = project.listings(80);
+
+    assert!(listings.len() > 1);
+    for listing in &listings {
+      assert!(listing.starts_with("Project: /w/app "));
+    }
+    let joined = listings.join("\n");
+    for name in names {
+      assert_eq!(joined.matches(&format!("- {} (", name)).count(), 1);
+    }
+  }
+
+  #[test]
+  fn counts_nested_items_once() {
+    let app = Path::new("/w/app");
+    let items = vec![
+      item(&app.join("target"), 300),
+      item(&app.join("target/debug"), 200),
+    ];
+    let report = Report {
+      roots: vec![PathBuf::from("/w")],
+      model: "phi".to_string(),
+      projects: group_by_project(items)
+        .into_iter()
+        .map(|(path, items)| ProjectSummary::new(path, items))
+        .collect(),
+      plan: String::new(),
+    };
+
+    assert_eq!(report.size(), 300);
+    assert_eq!(report.ecosystems()[0].items, 1);
+    assert_eq!(report.ecosystems()[0].size, 300);
+  }
+}
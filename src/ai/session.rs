@@ -0,0 +1,127 @@
+use super::backend::ChatMessage;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The session `ai-chat` resumes unless `--session` names another one
+pub const DEFAULT_SESSION: &str = "default";
+
+/// A chat conversation, saved as one JSON file per session name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatSession {
+  #[serde(skip)]
+  pub name: String,
+  /// Model the session last talked to
+  #[serde(default)]
+  pub model: Option<String>,
+  /// User and assistant messages, oldest first; the system prompt is not saved
+  #[serde(default)]
+  pub messages: Vec<ChatMessage>,
+}
+
+/// Directory holding the saved chat sessions
+pub fn sessions_dir() -> Option<PathBuf> {
+  dirs::data_local_dir().map(|dir| dir.join("devtidy").join("chats"))
+}
+
+impl ChatSession {
+  pub fn new(name: &str) -> Self {
+    Self {
+      name: name.to_string(),
+      ..Default::default()
+    }
+  }
+
+  /// Read the session `name` from `dir`; `None` if it was never saved
+  pub fn load_from(dir: &Path, name: &str) -> Result<Option<Self>> {
+    let path = session_path(dir, name)?;
+    let json = match fs::read_to_string(&path) {
+      Ok(json) => json,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+    };
+    let mut session: Self = serde_json::from_str(&json)
+      .with_context(|| format!("Malformed chat session {}", path.display()))?;
+    session.name = name.to_string();
+    Ok(Some(session))
+  }
+
+  /// Write the session to `dir`, replacing an earlier save of the same name
+  pub fn save_to(&self, dir: &Path) -> Result<PathBuf> {
+    let path = session_path(dir, &self.name)?;
+    fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+
+    // Write a temporary file first so an interrupted save keeps the old session
+    let temporary = path.with_extension("json.tmp");
+    fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+    fs::rename(&temporary, &path)?;
+    Ok(path)
+  }
+}
+
+/// Names of the sessions saved in `dir`, sorted
+pub fn list_sessions(dir: &Path) -> Vec<String> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut names: Vec<String> = entries
+    .filter_map(Result::ok)
+    .filter_map(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+      name.strip_suffix(".json").map(str::to_string)
+    })
+    .collect();
+  names.sort();
+  names
+}
+
+/// Session names become file names, so they are kept to a safe alphabet
+fn session_path(dir: &Path, name: &str) -> Result<PathBuf> {
+  let valid = !name.is_empty()
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !valid {
+    bail!(
+      "Invalid session name '{}': use letters, digits, '-' and '_'",
+      name
+    );
+  }
+  Ok(dir.join(format!("{}.json", name)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn saves_and_loads_sessions_by_name() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("chats");
+    let mut session = ChatSession::new("cleanup-2024");
+    session.model = Some("phi".to_string());
+    session.messages = vec![
+      ChatMessage::user("What is target?"),
+      ChatMessage::assistant("Cargo build output."),
+    ];
+
+    session.save_to(&dir).unwrap();
+    ChatSession::new("other").save_to(&dir).unwrap();
+
+    assert_eq!(
+      ChatSession::load_from(&dir, "cleanup-2024").unwrap(),
+      Some(session)
+    );
+    assert_eq!(ChatSession::load_from(&dir, "missing").unwrap(), None);
+    assert_eq!(list_sessions(&dir), vec!["cleanup-2024", "other"]);
+  }
+
+  #[test]
+  fn rejects_names_that_leave_the_directory() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    assert!(ChatSession::new("../escape").save_to(tmp.path()).is_err());
+    assert!(ChatSession::load_from(tmp.path(), "").is_err());
+  }
+}
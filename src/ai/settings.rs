@@ -0,0 +1,229 @@
+use crate::core::config::{AiConfig, AiProvider};
+use std::fmt;
+use std::time::Duration;
+
+/// Where Ollama listens unless `OLLAMA_HOST` or the config says otherwise
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+/// Where llama.cpp's `llama-server` listens by default
+pub const DEFAULT_OPENAI_URL: &str = "http://localhost:8080/v1";
+pub const DEFAULT_TEMPERATURE: f32 = 0.1;
+/// Room for a detailed answer without letting a rambling model run forever
+pub const DEFAULT_MAX_TOKENS: u32 = 2048;
+/// Generous enough for a large model to load on the first request
+pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// Where an effective setting came from, for `dd ai-diagnose`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+  Default,
+  ConfigFile,
+  Env(&'static str),
+  Flag(&'static str),
+}
+
+impl fmt::Display for Source {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Source::Default => write!(f, "default"),
+      Source::ConfigFile => write!(f, "config file"),
+      Source::Env(name) => write!(f, "{} environment variable", name),
+      Source::Flag(name) => write!(f, "{} flag", name),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+  pub value: T,
+  pub source: Source,
+}
+
+impl<T> Setting<T> {
+  fn new(value: T, source: Source) -> Self {
+    Self { value, source }
+  }
+
+  fn map_value<U>(self, f: impl FnOnce(T) -> U) -> Setting<U> {
+    Setting::new(f(self.value), self.source)
+  }
+}
+
+/// The settings the AI commands run with, after applying command-line flags,
+/// environment variables and the `[ai]` config section, in that order
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiSettings {
+  pub provider: Setting<AiProvider>,
+  pub base_url: Setting<String>,
+  /// `None` picks a model for the backend (by hardware for Ollama)
+  pub model: Option<Setting<String>>,
+  pub temperature: Setting<f32>,
+  /// `None` leaves the context window to the server
+  pub context_window: Option<Setting<u32>>,
+  pub max_tokens: Setting<u32>,
+  /// How long to wait for an answer to start; once it streams it is read to
+  /// the end
+  pub timeout: Setting<Duration>,
+}
+
+impl AiSettings {
+  pub fn resolve(config: &AiConfig, model_flag: Option<String>) -> Self {
+    Self::resolve_with(config, model_flag, |name| std::env::var(name).ok())
+  }
+
+  pub(super) fn resolve_with(
+    config: &AiConfig,
+    model_flag: Option<String>,
+    env: impl Fn(&str) -> Option<String>,
+  ) -> Self {
+    let provider = if config.provider == AiProvider::default() {
+      Setting::new(config.provider, Source::Default)
+    } else {
+      Setting::new(config.provider, Source::ConfigFile)
+    };
+
+    let base_url = match (config.provider, env("OLLAMA_HOST")) {
+      (AiProvider::Ollama, Some(host)) if !host.trim().is_empty() => {
+        Setting::new(ollama_host_url(&host), Source::Env("OLLAMA_HOST"))
+      }
+      _ => match &config.base_url {
+        Some(url) => Setting::new(url.clone(), Source::ConfigFile),
+        None => Setting::new(
+          match config.provider {
+            AiProvider::Ollama => DEFAULT_OLLAMA_URL,
+            AiProvider::OpenAi => DEFAULT_OPENAI_URL,
+          }
+          .to_string(),
+          Source::Default,
+        ),
+      },
+    };
+
+    let model = match model_flag {
+      Some(model) => Some(Setting::new(model, Source::Flag("--model"))),
+      None => config
+        .model
+        .clone()
+        .map(|model| Setting::new(model, Source::ConfigFile)),
+    };
+
+    Self {
+      provider,
+      base_url,
+      model,
+      temperature: configured(config.temperature, DEFAULT_TEMPERATURE),
+      context_window: config
+        .context_window
+        .map(|size| Setting::new(size, Source::ConfigFile)),
+      max_tokens: configured(config.max_tokens, DEFAULT_MAX_TOKENS),
+      timeout: configured(config.timeout_secs, DEFAULT_TIMEOUT_SECS).map_value(Duration::from_secs),
+    }
+  }
+}
+
+fn configured<T>(value: Option<T>, default: T) -> Setting<T> {
+  match value {
+    Some(value) => Setting::new(value, Source::ConfigFile),
+    None => Setting::new(default, Source::Default),
+  }
+}
+
+/// Turn an `OLLAMA_HOST` value (`host`, `host:port` or a full URL) into a base URL.
+/// Ollama's port is only assumed for a bare host; a URL without a port uses
+/// its scheme's, as behind a reverse proxy.
+fn ollama_host_url(host: &str) -> String {
+  let host = host.trim().trim_end_matches('/');
+  let (scheme, rest, explicit_scheme) = match host.split_once("://") {
+    Some((scheme, rest)) => (scheme, rest, true),
+    None => ("http", host, false),
+  };
+  // The server binds to 0.0.0.0 to listen everywhere; connect locally then
+  let rest = rest.replacen("0.0.0.0", "localhost", 1);
+
+  let authority = rest.split('/').next().unwrap_or_default();
+  let has_port = authority
+    .rsplit_once(':')
+    .is_some_and(|(_, port)| port.chars().all(|c| c.is_ascii_digit()));
+  if has_port || explicit_scheme {
+    format!("{}://{}", scheme, rest)
+  } else {
+    format!("{}://{}:11434", scheme, rest)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn no_env(_: &str) -> Option<String> {
+    None
+  }
+
+  #[test]
+  fn normalizes_ollama_host() {
+    assert_eq!(ollama_host_url("gpu-box"), "http://gpu-box:11434");
+    assert_eq!(ollama_host_url("0.0.0.0:8000"), "http://localhost:8000");
+    assert_eq!(
+      ollama_host_url("https://ollama.example.com/"),
+      "https://ollama.example.com"
+    );
+    assert_eq!(
+      ollama_host_url("http://gpu-box/ollama"),
+      "http://gpu-box/ollama"
+    );
+    assert_eq!(
+      ollama_host_url("http://10.0.0.5:11500"),
+      "http://10.0.0.5:11500"
+    );
+  }
+
+  #[test]
+  fn flag_env_and_config_take_precedence_over_defaults() {
+    let config = AiConfig {
+      base_url: Some("http://config-host:11434".to_string()),
+      model: Some("gemma:7b".to_string()),
+      timeout_secs: Some(300),
+      ..Default::default()
+    };
+
+    let settings = AiSettings::resolve_with(&config, Some("qwen2.5-coder".to_string()), |name| {
+      (name == "OLLAMA_HOST").then(|| "gpu-box".to_string())
+    });
+
+    assert_eq!(
+      settings.base_url,
+      Setting::new(
+        "http://gpu-box:11434".to_string(),
+        Source::Env("OLLAMA_HOST")
+      )
+    );
+    assert_eq!(
+      settings.model,
+      Some(Setting::new(
+        "qwen2.5-coder".to_string(),
+        Source::Flag("--model")
+      ))
+    );
+    assert_eq!(settings.timeout.value, Duration::from_secs(300));
+    assert_eq!(settings.timeout.source, Source::ConfigFile);
+    assert_eq!(settings.temperature.source, Source::Default);
+    assert_eq!(settings.context_window, None);
+    assert_eq!(settings.max_tokens.value, DEFAULT_MAX_TOKENS);
+
+    let settings = AiSettings::resolve_with(&config, None, no_env);
+    assert_eq!(settings.base_url.source, Source::ConfigFile);
+    assert_eq!(settings.model.unwrap().value, "gemma:7b");
+  }
+
+  #[test]
+  fn ollama_host_does_not_apply_to_openai_servers() {
+    let config = AiConfig {
+      provider: AiProvider::OpenAi,
+      ..Default::default()
+    };
+
+    let settings = AiSettings::resolve_with(&config, None, |_| Some("gpu-box".to_string()));
+
+    assert_eq!(settings.base_url.value, DEFAULT_OPENAI_URL);
+    assert_eq!(settings.provider.source, Source::ConfigFile);
+  }
+}
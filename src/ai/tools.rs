@@ -0,0 +1,414 @@
+use super::backend::ToolSpec;
+use super::utils::format_size;
+use crate::core::config::expand_home;
+use crate::services::scanner::{get_directory_size, scan_with_sizes, ScanOptions};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Entries listed by `list_dir` and items listed by `scan`
+const MAX_ENTRIES: usize = 100;
+/// Bytes of a manifest shown to the model
+const MAX_MANIFEST_BYTES: u64 = 16 * 1024;
+
+/// Files `read_manifest` may read: project manifests, never arbitrary files
+const MANIFEST_FILES: &[&str] = &[
+  "package.json",
+  "Cargo.toml",
+  "pyproject.toml",
+  "requirements.txt",
+  "setup.py",
+  "Pipfile",
+  "go.mod",
+  "pom.xml",
+  "build.gradle",
+  "build.gradle.kts",
+  "settings.gradle",
+  "build.sbt",
+  "composer.json",
+  "Gemfile",
+  "pubspec.yaml",
+  "mix.exs",
+  "Package.swift",
+  "CMakeLists.txt",
+];
+
+/// The read-only tools the chat agent can call. None of them changes anything;
+/// deleting stays with the cleaner and its own confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+  ListDir,
+  GetSize,
+  ReadManifest,
+  Scan,
+  GitStatus,
+}
+
+impl Tool {
+  pub const ALL: [Tool; 5] = [
+    Tool::ListDir,
+    Tool::GetSize,
+    Tool::ReadManifest,
+    Tool::Scan,
+    Tool::GitStatus,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Tool::ListDir => "list_dir",
+      Tool::GetSize => "get_size",
+      Tool::ReadManifest => "read_manifest",
+      Tool::Scan => "scan",
+      Tool::GitStatus => "git_status",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Tool> {
+    Tool::ALL.into_iter().find(|tool| tool.name() == name)
+  }
+
+  /// Whether the tool reads file contents or runs a program, rather than only
+  /// looking at names and sizes. Those calls need the user's permission.
+  pub fn reads_contents(self) -> bool {
+    matches!(self, Tool::ReadManifest | Tool::GitStatus)
+  }
+
+  /// Whether a call with `arguments` needs the user's permission: reading
+  /// contents always does, and measuring or scanning does outside `roots`,
+  /// where it could walk a whole disk
+  pub fn needs_confirmation(self, arguments: &Value, roots: &[PathBuf]) -> bool {
+    let walks_tree = matches!(self, Tool::GetSize | Tool::Scan);
+    self.reads_contents()
+      || (walks_tree && !path_argument(arguments).is_ok_and(|path| is_inside(&path, roots)))
+  }
+
+  pub fn spec(self) -> ToolSpec {
+    let (description, parameters) = match self {
+      Tool::ListDir => (
+        "List the entries of a directory with their type and file size",
+        path_parameter("Directory to list"),
+      ),
+      Tool::GetSize => (
+        "Total size of a file or directory, including everything inside it",
+        path_parameter("File or directory to measure"),
+      ),
+      Tool::ReadManifest => (
+        "Read a project manifest such as package.json, Cargo.toml or pyproject.toml",
+        path_parameter("Path of the manifest file"),
+      ),
+      Tool::Scan => (
+        "Run the DevTidy scanner and list the cleanable artifacts under a directory, largest first",
+        path_parameter("Directory to scan"),
+      ),
+      Tool::GitStatus => (
+        "Whether a path is inside a git repository, and whether git tracks or ignores it",
+        path_parameter("File or directory to check"),
+      ),
+    };
+    ToolSpec {
+      name: self.name(),
+      description,
+      parameters,
+    }
+  }
+
+  /// Run the tool with the model's `arguments`. The result is text for the model.
+  pub fn run(self, arguments: &Value, scan_options: &ScanOptions) -> Result<String> {
+    let path = path_argument(arguments)?;
+    match self {
+      Tool::ListDir => list_dir(&path),
+      Tool::GetSize => get_size(&path),
+      Tool::ReadManifest => read_manifest(&path),
+      Tool::Scan => scan(&path, scan_options),
+      Tool::GitStatus => git_status(&path),
+    }
+  }
+}
+
+/// Specs of all tools, to offer to the model
+pub fn specs() -> Vec<ToolSpec> {
+  Tool::ALL.into_iter().map(Tool::spec).collect()
+}
+
+fn path_parameter(description: &str) -> Value {
+  json!({
+      "type": "object",
+      "properties": {
+          "path": { "type": "string", "description": description }
+      },
+      "required": ["path"]
+  })
+}
+
+fn path_argument(arguments: &Value) -> Result<PathBuf> {
+  let path = arguments
+    .get("path")
+    .and_then(Value::as_str)
+    .ok_or_else(|| anyhow!("missing the path argument"))?;
+  let path = expand_home(path);
+  if !path.exists() {
+    bail!("{} does not exist", path.display());
+  }
+  Ok(path)
+}
+
+/// Whether `path` is one of `roots` or lies below one, after resolving links
+fn is_inside(path: &Path, roots: &[PathBuf]) -> bool {
+  let Ok(path) = path.canonicalize() else {
+    return false;
+  };
+  roots.iter().any(|root| {
+    let root = root.canonicalize().unwrap_or_else(|_| root.clone());
+    path.starts_with(root)
+  })
+}
+
+fn list_dir(path: &Path) -> Result<String> {
+  let mut entries: Vec<(String, String)> = fs::read_dir(path)
+    .with_context(|| format!("cannot list {}", path.display()))?
+    .filter_map(Result::ok)
+    .map(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+      let description = match entry.metadata() {
+        Ok(metadata) if metadata.is_dir() => "directory".to_string(),
+        Ok(metadata) => format!("file, {}", format_size(metadata.len())),
+        Err(_) => "unreadable".to_string(),
+      };
+      (name, description)
+    })
+    .collect();
+  entries.sort();
+
+  let total = entries.len();
+  let mut lines: Vec<String> = entries
+    .into_iter()
+    .take(MAX_ENTRIES)
+    .map(|(name, description)| format!("{} ({})", name, description))
+    .collect();
+  if total > MAX_ENTRIES {
+    lines.push(format!("... and {} more entries", total - MAX_ENTRIES));
+  }
+  if lines.is_empty() {
+    lines.push("(empty directory)".to_string());
+  }
+  Ok(lines.join("\n"))
+}
+
+fn get_size(path: &Path) -> Result<String> {
+  let size = if path.is_dir() {
+    get_directory_size(path)
+  } else {
+    fs::metadata(path)?.len()
+  };
+  Ok(format!("{}: {}", path.display(), format_size(size)))
+}
+
+fn read_manifest(path: &Path) -> Result<String> {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  if !MANIFEST_FILES.contains(&name.as_str()) && !name.ends_with(".csproj") {
+    bail!(
+      "{} is not a project manifest; only these can be read: {}, *.csproj",
+      name,
+      MANIFEST_FILES.join(", ")
+    );
+  }
+
+  // A link named like a manifest could point at any file
+  if fs::symlink_metadata(path)?.file_type().is_symlink() {
+    bail!(
+      "{} is a symbolic link; only the manifest itself can be read",
+      path.display()
+    );
+  }
+
+  // The limit can fall inside a multi-byte character
+  let mut contents = Vec::new();
+  fs::File::open(path)?
+    .take(MAX_MANIFEST_BYTES)
+    .read_to_end(&mut contents)
+    .with_context(|| format!("cannot read {}", path.display()))?;
+  Ok(String::from_utf8_lossy(&contents).into_owned())
+}
+
+fn scan(path: &Path, scan_options: &ScanOptions) -> Result<String> {
+  if !path.is_dir() {
+    bail!("{} is not a directory", path.display());
+  }
+  let items = scan_with_sizes(&[path.to_path_buf()], scan_options);
+  if items.is_empty() {
+    return Ok(format!("No cleanable items under {}", path.display()));
+  }
+
+  let total: u64 = items.iter().map(|item| item.size).sum();
+  let mut lines = vec![format!(
+    "{} cleanable items, {} in total:",
+    items.len(),
+    format_size(total)
+  )];
+  lines.extend(items.iter().take(MAX_ENTRIES).map(|item| {
+    let orphaned = if item.orphaned { " [orphaned]" } else { "" };
+    format!(
+      "{} {}{} ({})",
+      item.display_size(),
+      item.display_path(),
+      orphaned,
+      item.display_info()
+    )
+  }));
+  if items.len() > MAX_ENTRIES {
+    lines.push(format!(
+      "... and {} smaller items",
+      items.len() - MAX_ENTRIES
+    ));
+  }
+  Ok(lines.join("\n"))
+}
+
+fn git_status(path: &Path) -> Result<String> {
+  let dir = if path.is_dir() {
+    path
+  } else {
+    path.parent().unwrap_or(path)
+  };
+  let git = |args: &[&str]| {
+    Command::new("git")
+      .arg("-C")
+      .arg(dir)
+      .args(args)
+      .output()
+      .context("failed to run git")
+  };
+
+  let toplevel = git(&["rev-parse", "--show-toplevel"])?;
+  if !toplevel.status.success() {
+    return Ok(format!("{} is not inside a git repository", path.display()));
+  }
+  let repository = String::from_utf8_lossy(&toplevel.stdout).trim().to_string();
+
+  let target = path.as_os_str().to_string_lossy();
+  let tracked = git(&["ls-files", "--", &target])?;
+  let tracked_files = String::from_utf8_lossy(&tracked.stdout).lines().count();
+  let ignored = git(&["check-ignore", "-q", "--", &target])?
+    .status
+    .success();
+
+  Ok(format!(
+    "In the git repository at {}; {} tracked files at {}; {}",
+    repository,
+    tracked_files,
+    path.display(),
+    if ignored {
+      "ignored by .gitignore"
+    } else {
+      "not ignored"
+    }
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn lists_directories_without_reading_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    fs::create_dir(tmp.path().join("src")).unwrap();
+    fs::write(tmp.path().join("Cargo.toml"), "[package]").unwrap();
+
+    let listing = Tool::ListDir
+      .run(&json!({ "path": tmp.path() }), &ScanOptions::default())
+      .unwrap();
+
+    assert_eq!(listing, "Cargo.toml (file, 9 B)\nsrc (directory)");
+    assert!(!Tool::ListDir.reads_contents());
+  }
+
+  #[test]
+  fn reads_only_manifests() {
+    let tmp = tempfile::tempdir().unwrap();
+    fs::write(tmp.path().join("package.json"), r#"{"name": "web"}"#).unwrap();
+    fs::write(tmp.path().join(".env"), "SECRET=1").unwrap();
+    let read = |name: &str| {
+      Tool::ReadManifest.run(
+        &json!({ "path": tmp.path().join(name) }),
+        &ScanOptions::default(),
+      )
+    };
+
+    assert_eq!(read("package.json").unwrap(), r#"{"name": "web"}"#);
+    assert!(read(".env").is_err());
+    assert!(read("missing.json").is_err());
+    assert!(Tool::ReadManifest.reads_contents());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn refuses_links_named_like_manifests() {
+    let tmp = tempfile::tempdir().unwrap();
+    fs::write(tmp.path().join(".env"), "SECRET=1").unwrap();
+    std::os::unix::fs::symlink(tmp.path().join(".env"), tmp.path().join("package.json")).unwrap();
+
+    let result = Tool::ReadManifest.run(
+      &json!({ "path": tmp.path().join("package.json") }),
+      &ScanOptions::default(),
+    );
+
+    assert!(result.is_err());
+  }
+
+  #[test]
+  fn reads_manifests_cut_inside_a_character() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = tmp.path().join("package.json");
+    let padding = "a".repeat(MAX_MANIFEST_BYTES as usize - 1);
+    fs::write(&manifest, format!("{}é", padding)).unwrap();
+
+    let contents = Tool::ReadManifest
+      .run(&json!({ "path": manifest }), &ScanOptions::default())
+      .unwrap();
+
+    assert!(contents.starts_with(&padding));
+  }
+
+  #[test]
+  fn asks_before_walking_outside_the_roots() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("work");
+    fs::create_dir_all(root.join("app")).unwrap();
+    let roots = vec![root.clone()];
+    let at = |path: &Path| json!({ "path": path });
+
+    assert!(!Tool::Scan.needs_confirmation(&at(&root.join("app")), &roots));
+    assert!(!Tool::GetSize.needs_confirmation(&at(&root), &roots));
+    assert!(Tool::Scan.needs_confirmation(&at(tmp.path()), &roots));
+    assert!(Tool::GetSize.needs_confirmation(&at(Path::new("/")), &roots));
+    assert!(!Tool::ListDir.needs_confirmation(&at(Path::new("/")), &roots));
+    assert!(Tool::GitStatus.needs_confirmation(&at(&root), &roots));
+  }
+
+  #[test]
+  fn offers_every_tool_by_name() {
+    let names: Vec<&str> = specs().iter().map(|spec| spec.name).collect();
+
+    assert_eq!(
+      names,
+      vec![
+        "list_dir",
+        "get_size",
+        "read_manifest",
+        "scan",
+        "git_status"
+      ]
+    );
+    assert!(names
+      .iter()
+      .all(|name| Tool::from_name(name).is_some_and(|tool| tool.name() == *name)));
+    assert_eq!(Tool::from_name("delete"), None);
+  }
+}
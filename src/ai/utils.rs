@@ -1,6 +1,20 @@
 use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::time::Duration;
+
+/// Create an animated spinner with consistent styling
+pub fn create_spinner(message: &str, color: &str) -> ProgressBar {
+  let spinner = ProgressBar::new_spinner();
+  spinner.set_style(
+    ProgressStyle::default_spinner()
+      .template(&format!("{{spinner:.{}}} {{msg}}", color))
+      .unwrap(),
+  );
+  spinner.set_message(message.to_string());
+  spinner.enable_steady_tick(Duration::from_millis(100));
+  spinner
+}
 
 /// Get the display name of a folder for AI prompts
 pub fn get_folder_display_name(path: &Path) -> String {
@@ -11,21 +25,6 @@ pub fn get_folder_display_name(path: &Path) -> String {
     .to_string()
 }
 
-/// Calculate directory size
-pub fn calculate_folder_size(path: &Path) -> u64 {
-  WalkDir::new(path)
-    .into_iter()
-    .filter_map(|entry| entry.ok())
-    .filter_map(|entry| {
-      if entry.file_type().is_file() {
-        entry.metadata().ok().map(|metadata| metadata.len())
-      } else {
-        None
-      }
-    })
-    .sum()
-}
-
 /// Format bytes into human readable format
 pub fn format_size(bytes: u64) -> String {
   human_bytes::human_bytes(bytes as f64)
@@ -0,0 +1,260 @@
+use super::backend::LlmBackend;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Items are only preselected when the model is at least this sure
+pub const PRESELECT_CONFIDENCE: f64 = 0.8;
+
+/// The model's structured answer to "can this be deleted?"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+  pub safe_to_delete: bool,
+  /// Between 0 and 1
+  pub confidence: f64,
+  pub reason: String,
+  /// How to get the item back, e.g. `npm install`
+  #[serde(default)]
+  pub regenerate_command: Option<String>,
+}
+
+impl Verdict {
+  /// Whether the verdict is confident enough to select the item for cleaning
+  pub fn preselects(&self) -> bool {
+    self.safe_to_delete && self.confidence >= PRESELECT_CONFIDENCE
+  }
+
+  /// One line for lists: "safe 92%: reason"
+  pub fn summary(&self) -> String {
+    format!(
+      "{} {:.0}%: {}",
+      if self.safe_to_delete { "safe" } else { "keep" },
+      self.confidence * 100.0,
+      self.reason
+    )
+  }
+}
+
+/// JSON schema sent as the response format
+pub fn schema() -> Value {
+  json!({
+      "type": "object",
+      "properties": {
+          "safe_to_delete": { "type": "boolean" },
+          "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+          "reason": { "type": "string" },
+          "regenerate_command": { "type": ["string", "null"] }
+      },
+      "required": ["safe_to_delete", "confidence", "reason", "regenerate_command"]
+  })
+}
+
+/// JSON schema for one verdict per named item, for a whole project at once
+pub fn batch_schema() -> Value {
+  let mut item = schema();
+  item["properties"]["name"] = json!({ "type": "string" });
+  item["required"]
+    .as_array_mut()
+    .expect("schema lists required fields")
+    .insert(0, json!("name"));
+  json!({
+      "type": "object",
+      "properties": {
+          "verdicts": { "type": "array", "items": item }
+      },
+      "required": ["verdicts"]
+  })
+}
+
+/// Ask for a verdict on `prompt`. Fails with the raw answer when the model
+/// does not return a usable verdict, so callers can show it as plain text.
+pub async fn ask_verdict(backend: &dyn LlmBackend, model: &str, prompt: &str) -> Result<Verdict> {
+  let answer = backend
+    .generate_structured(model, prompt, &schema())
+    .await?;
+  parse_verdict(&answer).map_err(|e| anyhow!("{}; the model answered: {}", e, answer.trim()))
+}
+
+/// Ask for verdicts on several items described by `prompt`, keyed by the
+/// item names the prompt lists. Fails with the raw answer like `ask_verdict`.
+pub async fn ask_verdicts(
+  backend: &dyn LlmBackend,
+  model: &str,
+  prompt: &str,
+) -> Result<HashMap<String, Verdict>> {
+  let answer = backend
+    .generate_structured(model, prompt, &batch_schema())
+    .await?;
+  parse_verdicts(&answer).map_err(|e| anyhow!("{}; the model answered: {}", e, answer.trim()))
+}
+
+/// Read a verdict from the model's answer. Models without schema support may
+/// wrap the JSON in prose or a code fence, or give the confidence in percent.
+pub fn parse_verdict(answer: &str) -> Result<Verdict> {
+  let verdict: Verdict =
+    serde_json::from_str(json_object(answer)?).map_err(|e| anyhow!("Malformed verdict: {}", e))?;
+  normalize(verdict)
+}
+
+/// Read the verdicts of a batch answer. Entries that are not a usable verdict
+/// are left out, so the caller reports those items as unanswered.
+pub fn parse_verdicts(answer: &str) -> Result<HashMap<String, Verdict>> {
+  #[derive(Deserialize)]
+  struct Named {
+    name: String,
+    #[serde(flatten)]
+    verdict: Verdict,
+  }
+  #[derive(Deserialize)]
+  struct Batch {
+    verdicts: Vec<Value>,
+  }
+
+  let batch: Batch =
+    serde_json::from_str(json_object(answer)?).map_err(|e| anyhow!("Malformed verdicts: {}", e))?;
+  let verdicts: HashMap<String, Verdict> = batch
+    .verdicts
+    .into_iter()
+    .filter_map(|entry| serde_json::from_value::<Named>(entry).ok())
+    .filter_map(|named| {
+      Some((
+        named.name.trim().to_string(),
+        normalize(named.verdict).ok()?,
+      ))
+    })
+    .collect();
+
+  if verdicts.is_empty() {
+    bail!("No usable verdict in the answer");
+  }
+  Ok(verdicts)
+}
+
+/// The outermost JSON object in `answer`
+fn json_object(answer: &str) -> Result<&str> {
+  match (answer.find('{'), answer.rfind('}')) {
+    (Some(start), Some(end)) if start < end => Ok(&answer[start..=end]),
+    _ => bail!("No JSON verdict in the answer"),
+  }
+}
+
+fn normalize(mut verdict: Verdict) -> Result<Verdict> {
+  if verdict.confidence > 1.0 && verdict.confidence <= 100.0 {
+    verdict.confidence /= 100.0;
+  }
+  if !(0.0..=1.0).contains(&verdict.confidence) {
+    bail!("Verdict confidence {} is out of range", verdict.confidence);
+  }
+  verdict.reason = verdict.reason.trim().to_string();
+  if verdict.reason.is_empty() {
+    bail!("Verdict has no reason");
+  }
+  verdict.regenerate_command = verdict
+    .regenerate_command
+    .map(|command| command.trim().to_string())
+    .filter(|command| !command.is_empty());
+
+  Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ai::mock_ollama::MockOllama;
+
+  #[test]
+  fn parses_and_normalizes_verdicts() {
+    let verdict = parse_verdict(
+      "Sure! ```json\n{\"safe_to_delete\": true, \"confidence\": 95, \"reason\": \" npm packages \", \"regenerate_command\": \"\"}\n```",
+    )
+    .unwrap();
+
+    assert_eq!(
+      verdict,
+      Verdict {
+        safe_to_delete: true,
+        confidence: 0.95,
+        reason: "npm packages".to_string(),
+        regenerate_command: None,
+      }
+    );
+    assert!(verdict.preselects());
+  }
+
+  #[test]
+  fn rejects_unusable_verdicts() {
+    assert!(parse_verdict("Yes, delete it.").is_err());
+    assert!(
+      parse_verdict(r#"{"safe_to_delete": "yes", "confidence": 0.9, "reason": "x"}"#).is_err()
+    );
+    assert!(
+      parse_verdict(r#"{"safe_to_delete": true, "confidence": 250, "reason": "x"}"#).is_err()
+    );
+    assert!(
+      parse_verdict(r#"{"safe_to_delete": true, "confidence": 0.9, "reason": " "}"#).is_err()
+    );
+  }
+
+  #[test]
+  fn unsure_or_unsafe_verdicts_do_not_preselect() {
+    let verdict = |safe_to_delete, confidence| Verdict {
+      safe_to_delete,
+      confidence,
+      reason: "r".to_string(),
+      regenerate_command: None,
+    };
+
+    assert!(!verdict(true, 0.5).preselects());
+    assert!(!verdict(false, 0.99).preselects());
+  }
+
+  #[test]
+  fn parses_batches_and_skips_unusable_entries() {
+    let verdicts = parse_verdicts(
+      r#"{"verdicts": [
+        {"name": "target", "safe_to_delete": true, "confidence": 0.9, "reason": "Build output", "regenerate_command": "cargo build"},
+        {"name": "dist", "safe_to_delete": true, "confidence": 7000, "reason": "?", "regenerate_command": null},
+        {"name": "data", "reason": "no verdict"}
+      ]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(verdicts.len(), 1);
+    assert_eq!(
+      verdicts["target"].regenerate_command.as_deref(),
+      Some("cargo build")
+    );
+    assert!(parse_verdicts(r#"{"verdicts": []}"#).is_err());
+  }
+
+  #[tokio::test]
+  async fn asks_with_the_schema_as_format() {
+    let mock = MockOllama::start().await;
+    mock
+      .generate(&[
+        r#"{"safe_to_delete": false, "confidence": 0.7, "#,
+        r#""reason": "Holds user data", "regenerate_command": null}"#,
+      ])
+      .await;
+
+    let verdict = ask_verdict(&mock.client(), "phi", "data?").await.unwrap();
+
+    assert!(!verdict.safe_to_delete);
+    assert_eq!(verdict.reason, "Holds user data");
+    let request = &mock.requests("/api/generate").await[0];
+    assert_eq!(request["format"], schema());
+  }
+
+  #[tokio::test]
+  async fn malformed_answers_keep_the_raw_text() {
+    let mock = MockOllama::start().await;
+    mock.generate(&["I think it is fine to delete."]).await;
+
+    let error = ask_verdict(&mock.client(), "phi", "cache?")
+      .await
+      .unwrap_err();
+
+    assert!(error.to_string().contains("I think it is fine to delete."));
+  }
+}
@@ -1,10 +1,25 @@
-use crate::core::models::{App, AppState, CleanableItem};
-use crate::services::cleaner::clean_selected_items;
-use crate::services::scanner::{calculate_directory_sizes, scan_directory};
+use crate::ai::{Advisor, Verdict};
+use crate::core::config::{expand_home, Config};
+use crate::core::constants::WATCH_DEBOUNCE_MS;
+use crate::core::models::{
+  AiReview, App, AppState, CleanableItem, ExplainUpdate, Explanation, ScanScope,
+};
+use crate::services::budget::parse_size;
+use crate::services::cleaner::{clean_selected_items, CleanOptions, CleanUpdate};
+use crate::services::global::discover_global_caches;
+use crate::services::index::{self, ScanIndex};
+use crate::services::scanner::{
+  calculate_directory_sizes, dedupe_roots, get_directory_size, scan_roots, ScanOptions,
+};
+use crate::services::toolchains::discover_toolchains;
+use crate::services::watcher::{watch_roots, ChangeResolver, WatchChange};
 use crate::ui::ui as ui_module;
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
@@ -17,6 +32,8 @@ pub async fn run_app(
   app.state = AppState::Scanning;
   app.scanning = true;
   app.scan_start_time = Instant::now();
+  app.refresh_disks();
+  show_cached_results(app);
 
   terminal.draw(|f| ui_module::draw(f, app))?;
 
@@ -24,13 +41,21 @@ pub async fn run_app(
   let scan_tx_clone = scan_tx.clone();
 
   tokio::spawn(scan_background(
-    app.current_dir.clone(),
-    app.use_gitignore,
+    app.roots.clone(),
+    app.scope,
+    app.scan_options(),
     scan_tx_clone.clone(),
     app.scan_start_time,
-    app.max_depth,
   ));
 
+  if app.watching {
+    tokio::spawn(watch_background(
+      app.roots.clone(),
+      app.scan_options(),
+      scan_tx_clone.clone(),
+    ));
+  }
+
   let mut last_key_time = Instant::now();
   let mut last_key_code = None;
   let key_debounce = Duration::from_millis(150);
@@ -42,6 +67,14 @@ pub async fn run_app(
       process_scan_update(app, update);
     }
 
+    while let Some(Ok(update)) = app.clean_rx.as_mut().map(|rx| rx.try_recv()) {
+      process_clean_update(app, update);
+    }
+
+    while let Some(Ok(update)) = app.explain_rx.as_mut().map(|rx| rx.try_recv()) {
+      process_explain_update(app, update);
+    }
+
     terminal.draw(|f| ui_module::draw(f, app))?;
 
     if poll(Duration::from_millis(16))? {
@@ -53,30 +86,17 @@ pub async fn run_app(
             last_key_time = now;
             last_key_code = Some(key.code);
 
-            if key.code == KeyCode::Char('r') && app.state == AppState::Selecting && !app.cleaning {
-              app.state = AppState::Scanning;
-              app.scanning = true;
-              app.scan_start_time = Instant::now();
-              app.scan_duration = Duration::ZERO;
-              app.scanned_items = 0;
-              app.calculating_sizes = false;
-              app.pending_sizes.clear();
-              app.total_size_jobs = 0;
-              app.completed_size_jobs = 0;
-              app.progress = 0.0;
-              app.processing_item = None;
-              app.items.clear();
-              app.list_state.select(None);
-              app.total_size = 0;
-              app.cleaned_size = 0;
-
-              tokio::spawn(scan_background(
-                app.current_dir.clone(),
-                app.use_gitignore,
-                scan_tx_clone.clone(),
-                app.scan_start_time,
-                app.max_depth,
-              ));
+            let rescan = app.state == AppState::Selecting
+              && !app.cleaning
+              && !app.scanning
+              && app.budget_input.is_none();
+            if rescan && key.code == KeyCode::Char('r') {
+              restart_scan(app, &scan_tx_clone);
+            } else if rescan && key.code == KeyCode::Tab {
+              app.scope = app.scope.next();
+              restart_scan(app, &scan_tx_clone);
+            } else if rescan && app.ai_review.is_none() && key.code == KeyCode::Char('a') {
+              start_review(app, &scan_tx_clone);
             } else if !handle_key_event(app, key).await? {
               break;
             }
@@ -89,12 +109,17 @@ pub async fn run_app(
             MouseEventKind::ScrollUp => app.previous(),
             _ => {}
           },
+          AppState::Plan => match mouse.kind {
+            MouseEventKind::ScrollDown => app.plan_scroll += 1,
+            MouseEventKind::ScrollUp if app.plan_scroll > 0 => {
+              app.plan_scroll -= 1;
+            }
+            _ => {}
+          },
           AppState::Help => match mouse.kind {
             MouseEventKind::ScrollDown => app.help_scroll += 1,
-            MouseEventKind::ScrollUp => {
-              if app.help_scroll > 0 {
-                app.help_scroll -= 1;
-              }
+            MouseEventKind::ScrollUp if app.help_scroll > 0 => {
+              app.help_scroll -= 1;
             }
             _ => {}
           },
@@ -113,24 +138,121 @@ pub async fn run_app(
   Ok(())
 }
 
+fn restart_scan(app: &mut App, tx: &mpsc::Sender<ScanUpdate>) {
+  app.state = AppState::Scanning;
+  app.scanning = true;
+  app.scan_start_time = Instant::now();
+  app.scan_duration = Duration::ZERO;
+  app.scanned_items = 0;
+  app.calculating_sizes = false;
+  app.pending_sizes.clear();
+  app.total_size_jobs = 0;
+  app.completed_size_jobs = 0;
+  app.progress = 0.0;
+  app.processing_item = None;
+  app.items.clear();
+  app.list_state.select(None);
+  app.total_size = 0;
+  app.cleaned_size = 0;
+  app.partially_cleaned = 0;
+  app.failed_to_clean = 0;
+  app.deferred_cleans = 0;
+  app.natively_cleaned = 0;
+  app.last_clean_error = None;
+  app.budget = None;
+  app.refresh_disks();
+  show_cached_results(app);
+
+  tokio::spawn(scan_background(
+    app.roots.clone(),
+    app.scope,
+    app.scan_options(),
+    tx.clone(),
+    app.scan_start_time,
+  ));
+}
+
+/// Show the results of the previous scan from the index while the real scan
+/// runs. Rows are not checked here; `scan_background` marks the changed ones.
+fn show_cached_results(app: &mut App) {
+  if app.scope != ScanScope::Projects {
+    return;
+  }
+  let Some(index) = index::load(&app.roots, &app.scan_options()) else {
+    return;
+  };
+
+  let items = index.preview();
+  if items.is_empty() {
+    return;
+  }
+  app.total_size = items.iter().map(|item| item.size).sum();
+  app.scanned_items = items.len();
+  app.items = items;
+  app.state = AppState::Selecting;
+  app.list_state.select(Some(0));
+}
+
 enum ScanUpdate {
+  Stale(Vec<PathBuf>),
   ItemsFound(Vec<CleanableItem>),
   SizeUpdate(PathBuf, u64),
   SizeCalculationComplete,
   ScanComplete(Duration),
   ItemsScanned(usize),
+  Watched(WatchChange),
+  WatchFailed(String),
+  AiConnected(String),
+  AiVerdict(PathBuf, Result<Verdict, String>),
+  AiReviewDone,
+  AiFailed(String),
 }
 
 async fn scan_background(
-  dir: PathBuf,
-  use_gitignore: bool,
+  roots: Vec<PathBuf>,
+  scope: ScanScope,
+  options: ScanOptions,
   tx: mpsc::Sender<ScanUpdate>,
   start_time: Instant,
-  max_depth: usize,
 ) -> Result<()> {
-  let items = tokio::task::spawn_blocking(move || scan_directory(&dir, use_gitignore, max_depth))
+  let index = if scope == ScanScope::Projects {
+    let roots = roots.clone();
+    let options = options.clone();
+    let (index, changed) = tokio::task::spawn_blocking(move || {
+      let index = index::load(&roots, &options);
+      let changed = index.as_ref().map(ScanIndex::changed).unwrap_or_default();
+      (index, changed)
+    })
     .await
     .unwrap();
+    let _ = tx.send(ScanUpdate::Stale(changed)).await;
+    index
+  } else {
+    None
+  };
+
+  let (items, reused) = {
+    let roots = roots.clone();
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || {
+      let mut items = match scope {
+        ScanScope::Projects => scan_roots(&roots, &options),
+        ScanScope::Global => discover_global_caches(),
+        ScanScope::Toolchains => discover_toolchains(&roots, options.max_depth),
+      };
+      let reused = match &index {
+        Some(index) => reuse_indexed_sizes(index, &mut items),
+        None => Vec::new(),
+      };
+      (items, reused)
+    })
+    .await
+    .unwrap()
+  };
+  let mut sizes: HashMap<PathBuf, u64> = items
+    .iter()
+    .map(|item| (item.path.clone(), item.size))
+    .collect();
 
   let _ = tx.send(ScanUpdate::ItemsFound(items.clone())).await;
   let _ = tx.send(ScanUpdate::ItemsScanned(items.len())).await;
@@ -153,6 +275,7 @@ async fn scan_background(
     let total = dir_items;
 
     while let Some((path, size)) = size_rx.recv().await {
+      sizes.insert(path.clone(), size);
       let _ = tx.send(ScanUpdate::SizeUpdate(path, size)).await;
 
       completed += 1;
@@ -174,13 +297,285 @@ async fn scan_background(
   let final_duration = start_time.elapsed();
   let _ = tx.send(ScanUpdate::ScanComplete(final_duration)).await;
 
+  if scope != ScanScope::Projects {
+    return Ok(());
+  }
+
+  // Sizes taken from the index are only as fresh as the directory's own mtime,
+  // so re-measure them now that the list is usable
+  for path in reused {
+    let measured = {
+      let path = path.clone();
+      tokio::task::spawn_blocking(move || get_directory_size(&path))
+        .await
+        .unwrap_or_default()
+    };
+    if sizes.get(&path) != Some(&measured) {
+      sizes.insert(path.clone(), measured);
+      let _ = tx.send(ScanUpdate::SizeUpdate(path, measured)).await;
+    }
+  }
+
+  let mut items = items;
+  for item in &mut items {
+    if let Some(size) = sizes.get(&item.path) {
+      item.size = *size;
+    }
+  }
+  let _ = tokio::task::spawn_blocking(move || index::save(&roots, &options, &items)).await;
+
   Ok(())
 }
 
+/// Feed file system events for `roots` into the scan channel, batched so a
+/// build touching thousands of files re-measures each artifact once per batch,
+/// and no more often than `WATCH_REMEASURE_SECS`
+async fn watch_background(roots: Vec<PathBuf>, options: ScanOptions, tx: mpsc::Sender<ScanUpdate>) {
+  let (path_tx, mut path_rx) = mpsc::unbounded_channel();
+  let _watcher = match watch_roots(&roots, path_tx) {
+    Ok(watcher) => watcher,
+    Err(e) => {
+      let _ = tx.send(ScanUpdate::WatchFailed(e.to_string())).await;
+      return;
+    }
+  };
+
+  let mut resolver = ChangeResolver::new(&roots, &options);
+  loop {
+    // Wake up for the next event, or when a held-back artifact is due
+    let next = match resolver.next_due() {
+      Some(due) => tokio::time::timeout_at(due.into(), path_rx.recv()).await,
+      None => Ok(path_rx.recv().await),
+    };
+    let mut changed = HashSet::new();
+    match next {
+      Ok(Some(first)) => {
+        changed.insert(first);
+        tokio::time::sleep(Duration::from_millis(WATCH_DEBOUNCE_MS)).await;
+        while let Ok(path) = path_rx.try_recv() {
+          changed.insert(path);
+        }
+      }
+      Ok(None) => return,
+      Err(_) => {}
+    }
+
+    let Ok((returned, changes)) = tokio::task::spawn_blocking(move || {
+      let changes = resolver.resolve(changed);
+      (resolver, changes)
+    })
+    .await
+    else {
+      return;
+    };
+    resolver = returned;
+
+    for change in changes {
+      if tx.send(ScanUpdate::Watched(change)).await.is_err() {
+        return;
+      }
+    }
+  }
+}
+
+/// Ask the model about the items that have no verdict yet
+fn start_review(app: &mut App, tx: &mpsc::Sender<ScanUpdate>) {
+  let items: Vec<CleanableItem> = app
+    .items
+    .iter()
+    .filter(|item| !item.protected && !app.verdicts.contains_key(&item.path))
+    .cloned()
+    .collect();
+  app.ai_error = None;
+  if items.is_empty() {
+    return;
+  }
+
+  app.ai_review = Some(AiReview {
+    total: items.len(),
+    ..Default::default()
+  });
+  tokio::spawn(review_background(
+    app.advisor.clone(),
+    app.ai_model.clone(),
+    items,
+    tx.clone(),
+  ));
+}
+
+/// The session's connection to the model server, made on first use. A failed
+/// attempt is not kept, so the next one tries again.
+async fn connect_advisor(
+  advisor: &tokio::sync::OnceCell<Advisor>,
+  model: Option<String>,
+) -> anyhow::Result<&Advisor> {
+  advisor.get_or_try_init(|| Advisor::connect(model)).await
+}
+
+async fn review_background(
+  advisor: Arc<tokio::sync::OnceCell<Advisor>>,
+  model: Option<String>,
+  items: Vec<CleanableItem>,
+  tx: mpsc::Sender<ScanUpdate>,
+) {
+  let advisor = match connect_advisor(&advisor, model).await {
+    Ok(advisor) => advisor,
+    Err(e) => {
+      let _ = tx.send(ScanUpdate::AiFailed(format!("{:#}", e))).await;
+      return;
+    }
+  };
+  let _ = tx
+    .send(ScanUpdate::AiConnected(advisor.model().to_string()))
+    .await;
+
+  for item in items {
+    let verdict = advisor.verdict(&item).await.map_err(|e| format!("{:#}", e));
+    if tx
+      .send(ScanUpdate::AiVerdict(item.path, verdict))
+      .await
+      .is_err()
+    {
+      return;
+    }
+  }
+  let _ = tx.send(ScanUpdate::AiReviewDone).await;
+}
+
+/// Ask the model about the highlighted item, or toggle the panel when it
+/// already has an answer
+fn explain_highlighted(app: &mut App) {
+  let Some(item) = app
+    .list_state
+    .selected()
+    .and_then(|i| app.items.get(i))
+    .cloned()
+  else {
+    return;
+  };
+  if app
+    .explanations
+    .get(&item.path)
+    .is_some_and(|explanation| explanation.error.is_none())
+  {
+    app.show_explanation = !app.show_explanation;
+    return;
+  }
+
+  // One answer streams at a time; an unfinished one is stopped and dropped
+  if let Some(task) = app.explain_task.take() {
+    task.abort();
+  }
+  app
+    .explanations
+    .retain(|_, explanation| explanation.done && explanation.error.is_none());
+  app
+    .explanations
+    .insert(item.path.clone(), Explanation::default());
+  app.show_explanation = true;
+
+  let (tx, rx) = mpsc::unbounded_channel();
+  app.explain_rx = Some(rx);
+  app.explain_task = Some(tokio::spawn(explain_background(
+    app.advisor.clone(),
+    app.ai_model.clone(),
+    item,
+    tx,
+  )));
+}
+
+async fn explain_background(
+  advisor: Arc<tokio::sync::OnceCell<Advisor>>,
+  model: Option<String>,
+  item: CleanableItem,
+  tx: mpsc::UnboundedSender<ExplainUpdate>,
+) {
+  let path = item.path.clone();
+  let result = match connect_advisor(&advisor, model).await {
+    Ok(advisor) => {
+      let mut on_token = |token: &str| {
+        let _ = tx.send(ExplainUpdate::Token(path.clone(), token.to_string()));
+      };
+      advisor.explain(&item, &mut on_token).await
+    }
+    Err(e) => Err(e),
+  };
+
+  let _ = tx.send(match result {
+    Ok(_) => ExplainUpdate::Done(path),
+    Err(e) => ExplainUpdate::Failed(path, format!("{:#}", e)),
+  });
+}
+
+/// Take sizes of unchanged directories from the index; returns the reused paths
+fn reuse_indexed_sizes(index: &ScanIndex, items: &mut [CleanableItem]) -> Vec<PathBuf> {
+  let mut reused = Vec::new();
+  for item in items.iter_mut() {
+    if item.size != 0 || !item.path.is_dir() {
+      continue;
+    }
+    if let Some(size) = index.cached_size(&item.path).filter(|size| *size > 0) {
+      item.size = size;
+      reused.push(item.path.clone());
+    }
+  }
+  reused
+}
+
+fn process_explain_update(app: &mut App, update: ExplainUpdate) {
+  match update {
+    ExplainUpdate::Token(path, token) => {
+      if let Some(explanation) = app.explanations.get_mut(&path) {
+        explanation.text.push_str(&token);
+      }
+    }
+    ExplainUpdate::Done(path) => {
+      if let Some(explanation) = app.explanations.get_mut(&path) {
+        explanation.done = true;
+      }
+      app.explain_rx = None;
+      app.explain_task = None;
+    }
+    ExplainUpdate::Failed(path, error) => {
+      if let Some(explanation) = app.explanations.get_mut(&path) {
+        explanation.done = true;
+        explanation.error = Some(error);
+      }
+      app.explain_rx = None;
+      app.explain_task = None;
+    }
+  }
+}
+
 fn process_scan_update(app: &mut App, update: ScanUpdate) {
   match update {
-    ScanUpdate::ItemsFound(items) => {
+    ScanUpdate::Stale(paths) => {
+      let paths: HashSet<PathBuf> = paths.into_iter().collect();
+      for item in &mut app.items {
+        item.stale |= paths.contains(&item.path);
+      }
+    }
+    ScanUpdate::ItemsFound(mut items) => {
+      // Keep selections made on the cached results while the scan was running
+      let selected: HashSet<PathBuf> = app
+        .items
+        .iter()
+        .filter(|item| item.selected)
+        .map(|item| item.path.clone())
+        .collect();
+      for item in &mut items {
+        item.selected |= selected.contains(&item.path);
+        item.stale = item.size == 0 && item.path.is_dir();
+      }
+
       app.items = items;
+      if app
+        .list_state
+        .selected()
+        .is_some_and(|i| i >= app.items.len())
+      {
+        app.list_state.select(app.items.len().checked_sub(1));
+      }
       app.scanned_items = app.items.len();
       app.total_size_jobs = app.items.len();
       app.completed_size_jobs = 0;
@@ -200,6 +595,7 @@ fn process_scan_update(app: &mut App, update: ScanUpdate) {
       for item in &mut app.items {
         if item.path == path {
           item.size = size;
+          item.stale = false;
           break;
         }
       }
@@ -210,26 +606,141 @@ fn process_scan_update(app: &mut App, update: ScanUpdate) {
       let total_size: u64 = app.items.iter().map(|item| item.size).sum();
       app.total_size = total_size;
 
-      app.state = AppState::Selecting;
-      app.scanning = false;
       app.calculating_sizes = false;
-
-      if !app.items.is_empty() {
-        app.list_state.select(Some(0));
+      show_scan_results(app);
+    }
+    ScanUpdate::Watched(change)
+      if app.scope == ScanScope::Projects && !app.scanning && !app.cleaning =>
+    {
+      apply_watch_change(app, change);
+    }
+    ScanUpdate::Watched(_) => {}
+    ScanUpdate::WatchFailed(error) => {
+      app.watch_error = Some(error);
+    }
+    ScanUpdate::AiConnected(model) => {
+      if let Some(review) = &mut app.ai_review {
+        review.model = Some(model);
+      }
+    }
+    ScanUpdate::AiVerdict(path, verdict) => {
+      if let Some(review) = &mut app.ai_review {
+        review.done += 1;
+        review.failed += verdict.is_err() as usize;
+      }
+      if let Ok(verdict) = verdict {
+        app.apply_verdict(path, verdict);
+      }
+    }
+    ScanUpdate::AiReviewDone => {
+      if let Some(review) = app.ai_review.take().filter(|review| review.failed > 0) {
+        app.ai_error = Some(format!(
+          "no usable verdict for {} of {} items",
+          review.failed, review.total
+        ));
       }
     }
+    ScanUpdate::AiFailed(error) => {
+      app.ai_review = None;
+      app.ai_error = Some(error);
+    }
     ScanUpdate::ScanComplete(duration) => {
       app.scan_duration = duration;
 
       if !app.calculating_sizes {
-        app.state = AppState::Selecting;
-        app.scanning = false;
+        show_scan_results(app);
+      }
+    }
+  }
+}
 
-        if !app.items.is_empty() {
-          app.list_state.select(Some(0));
+fn apply_watch_change(app: &mut App, change: WatchChange) {
+  match change {
+    WatchChange::Updated(item) => {
+      match app
+        .items
+        .iter_mut()
+        .find(|existing| existing.path == item.path)
+      {
+        Some(existing) => {
+          existing.size = item.size;
+          existing.stale = false;
         }
+        None => app.items.push(item),
       }
     }
+    WatchChange::Removed(path) => {
+      app.items.retain(|item| !item.path.starts_with(&path));
+    }
+  }
+
+  app.scanned_items = app.items.len();
+  let in_range = app
+    .list_state
+    .selected()
+    .is_some_and(|i| i < app.items.len());
+  if !in_range {
+    app.list_state.select(app.items.len().checked_sub(1));
+  }
+}
+
+/// Switch to the list once scanning is done, keeping the cursor when the
+/// cached results were already on screen
+fn show_scan_results(app: &mut App) {
+  app.scanning = false;
+  match app.state {
+    AppState::Scanning => app.state = AppState::Selecting,
+    AppState::Help if app.previous_state == Some(AppState::Scanning) => {
+      app.previous_state = Some(AppState::Selecting);
+    }
+    _ => {}
+  }
+
+  let in_range = app
+    .list_state
+    .selected()
+    .is_some_and(|i| i < app.items.len());
+  if !in_range {
+    app
+      .list_state
+      .select(if app.items.is_empty() { None } else { Some(0) });
+  }
+
+  if let Some(target) = app.budget_target.take() {
+    app.apply_budget(target);
+    if app.state == AppState::Selecting {
+      app.state = AppState::Plan;
+    } else if app.previous_state == Some(AppState::Selecting) {
+      app.previous_state = Some(AppState::Plan);
+    }
+  }
+}
+
+/// Keys while typing the size for `f`; Enter plans, Esc cancels
+fn handle_budget_input(app: &mut App, code: KeyCode) {
+  let Some(input) = app.budget_input.as_mut() else {
+    return;
+  };
+
+  match code {
+    KeyCode::Esc => {
+      app.budget_input = None;
+      app.budget_error = None;
+    }
+    KeyCode::Backspace => {
+      input.pop();
+    }
+    KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == '.' => input.push(c),
+    KeyCode::Enter => match parse_size(input) {
+      Ok(target) => {
+        app.budget_input = None;
+        app.budget_error = None;
+        app.apply_budget(target);
+        app.state = AppState::Plan;
+      }
+      Err(error) => app.budget_error = Some(error),
+    },
+    _ => {}
   }
 }
 
@@ -244,35 +755,69 @@ async fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
         app.help_scroll = 0;
       }
     }
-    AppState::Selecting => match key.code {
-      KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+    AppState::Selecting if app.budget_input.is_some() => {
+      handle_budget_input(app, key.code);
+    }
+    AppState::Selecting => {
+      // An AI review error stays on screen until the next key press
+      app.ai_error = None;
+      match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+        KeyCode::Char('h') => {
+          app.previous_state = Some(app.state);
+          app.state = AppState::Help;
+          app.help_scroll = 0;
+        }
+        KeyCode::Char('c') if app.selected_count() > 0 && !app.cleaning && !app.scanning => {
+          start_cleaning(app);
+        }
+        KeyCode::Char(' ') if !app.cleaning => {
+          app.toggle_selection();
+          app.total_size = app.selected_size();
+        }
+        KeyCode::Char('f') if !app.cleaning && !app.scanning => {
+          app.budget_input = Some(String::new());
+          app.budget_error = None;
+        }
+        KeyCode::Char('e') => {
+          explain_highlighted(app);
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+          app.previous();
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+          app.next();
+        }
+        _ => {}
+      }
+    }
+    AppState::Plan => match key.code {
+      KeyCode::Char('q') => return Ok(false),
       KeyCode::Char('h') => {
         app.previous_state = Some(app.state);
         app.state = AppState::Help;
         app.help_scroll = 0;
       }
-      KeyCode::Char('c') => {
-        if app.selected_count() > 0 && !app.cleaning {
-          start_cleaning(app).await;
-        }
+      KeyCode::Char('c') | KeyCode::Enter if app.selected_count() > 0 => {
+        start_cleaning(app);
       }
-      KeyCode::Char(' ') => {
-        if !app.cleaning {
-          app.toggle_selection();
-          app.total_size = app.selected_size();
-        }
+      KeyCode::Esc | KeyCode::Char('b') => {
+        app.state = AppState::Selecting;
       }
-      KeyCode::Up | KeyCode::Char('k') => {
-        app.previous();
+      KeyCode::Up | KeyCode::Char('k') if app.plan_scroll > 0 => {
+        app.plan_scroll -= 1;
       }
       KeyCode::Down | KeyCode::Char('j') => {
-        app.next();
+        app.plan_scroll += 1;
       }
       _ => {}
     },
     AppState::Cleaning => {
-      if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
+      if key.code == KeyCode::Char('q') {
+        cancel_cleaning(app);
         return Ok(false);
+      } else if key.code == KeyCode::Esc || key.code == KeyCode::Char('x') {
+        cancel_cleaning(app);
       } else if key.code == KeyCode::Char('h') {
         app.previous_state = Some(app.state);
         app.state = AppState::Help;
@@ -302,10 +847,8 @@ async fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
           app.state = AppState::Selecting;
         }
       }
-      KeyCode::Up | KeyCode::Char('k') => {
-        if app.help_scroll > 0 {
-          app.help_scroll -= 1;
-        }
+      KeyCode::Up | KeyCode::Char('k') if app.help_scroll > 0 => {
+        app.help_scroll -= 1;
       }
       KeyCode::Down | KeyCode::Char('j') => {
         app.help_scroll += 1;
@@ -327,62 +870,177 @@ async fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
   Ok(true)
 }
 
-async fn start_cleaning(app: &mut App) {
+fn start_cleaning(app: &mut App) {
   app.state = AppState::Cleaning;
   app.cleaning = true;
   app.progress = 0.0;
 
   let total_to_clean = app.selected_size();
   app.total_size = total_to_clean;
+  app.clean_total_bytes = total_to_clean;
+  app.clean_removed_bytes = 0;
+  app.clean_removed_files = 0;
+  app.clean_items_total = app.selected_count();
+  app.clean_active_items.clear();
+  app.clean_results.clear();
+  app.clean_cancelled = false;
+  app.clean_start_time = Instant::now();
 
-  let (tx, mut rx) = mpsc::channel(32);
+  let (tx, rx) = mpsc::channel(256);
+  let options = CleanOptions {
+    concurrency: app.clean_jobs,
+    native: app.native_clean,
+  };
+  app.clean_cancel = Some(clean_selected_items(&app.items, options, tx));
+  app.clean_rx = Some(rx);
+}
 
-  let clean_handle = clean_selected_items(&app.items, tx);
+fn cancel_cleaning(app: &mut App) {
+  if let Some(cancel) = &app.clean_cancel {
+    cancel.store(true, Ordering::Relaxed);
+    app.clean_cancelled = true;
+  }
+}
 
-  while let Some((done, total, item)) = rx.recv().await {
-    app.progress = done as f32 / total as f32;
-    app.processing_item = item;
+fn process_clean_update(app: &mut App, update: CleanUpdate) {
+  match update {
+    CleanUpdate::ItemStarted(path) => {
+      app.processing_item = Some(path.clone());
+      app.clean_active_items.push(path);
+    }
+    CleanUpdate::Progress { bytes, files } => {
+      app.clean_removed_bytes += bytes;
+      app.clean_removed_files += files;
+      app.progress = if app.clean_total_bytes > 0 {
+        (app.clean_removed_bytes as f32 / app.clean_total_bytes as f32).min(1.0)
+      } else {
+        0.0
+      };
+    }
+    CleanUpdate::ItemFinished(result) => {
+      app.clean_active_items.retain(|path| *path != result.path);
+      app.processing_item = app.clean_active_items.last().cloned();
+      app.clean_results.push(result);
+    }
+    CleanUpdate::ItemFailed(result) => {
+      app.clean_results.retain(|r| r.path != result.path);
+      app.clean_results.push(result);
+    }
+    CleanUpdate::Finished => finish_cleaning(app),
   }
+}
 
-  let results = clean_handle.await.unwrap();
+fn finish_cleaning(app: &mut App) {
+  let results = std::mem::take(&mut app.clean_results);
 
   app.cleaned_size = results.iter().map(|r| r.size).sum();
+  app.partially_cleaned = results.iter().filter(|r| r.partial).count();
+  app.deferred_cleans = results.iter().filter(|r| r.deferred.is_some()).count();
+  app.failed_to_clean = results
+    .iter()
+    .filter(|r| !r.success && !r.partial && r.deferred.is_none())
+    .count();
+  app.last_clean_error = results.iter().find_map(|r| r.error.clone());
+  app.natively_cleaned = results
+    .iter()
+    .filter(|r| r.native.as_ref().is_some_and(|native| native.success))
+    .count();
+
+  for result in results.iter().filter(|r| r.partial) {
+    if let Some(item) = app
+      .items
+      .iter_mut()
+      .find(|item| item.display_path() == result.path)
+    {
+      item.size = item.size.saturating_sub(result.size);
+    }
+  }
 
+  // A deferred item was already moved out of its project
   app.items.retain(|item| {
     !results
       .iter()
-      .any(|r| r.path == item.display_path() && r.success)
+      .any(|r| r.path == item.display_path() && (r.success || r.deferred.is_some()))
   });
 
+  if app.scope == ScanScope::Projects {
+    let roots = app.roots.clone();
+    let options = app.scan_options();
+    let items = app.items.clone();
+    tokio::task::spawn_blocking(move || index::save(&roots, &options, &items));
+  }
+  app.refresh_disks();
+
+  app.clean_rx = None;
+  app.clean_cancel = None;
+  app.processing_item = None;
+  app.clean_active_items.clear();
   app.state = AppState::Complete;
   app.cleaning = false;
 }
 
-pub fn initialize_app(
-  target_dir: Option<String>,
-  use_gitignore: bool,
-  max_depth: usize,
-) -> Result<App> {
-  let dir = match target_dir {
-    Some(path) => {
-      let path = PathBuf::from(path);
+/// Roots to scan: the `--path` arguments, else the roots from the config file,
+/// else the current directory. Overlapping roots are collapsed.
+pub fn resolve_roots(paths: &[String]) -> Result<Vec<PathBuf>> {
+  let mut roots = Vec::new();
+
+  if !paths.is_empty() {
+    for path in paths {
+      let path = expand_home(path);
       if !path.exists() {
         anyhow::bail!("Directory does not exist: {}", path.display());
       }
       if !path.is_dir() {
         anyhow::bail!("Not a directory: {}", path.display());
       }
-      path.canonicalize()?
+      roots.push(path);
     }
-    None => std::env::current_dir()?,
-  };
+  } else {
+    for path in Config::load()?.root_paths() {
+      if path.is_dir() {
+        roots.push(path);
+      } else {
+        log::warn!(
+          "Skipping configured root {}: not a directory",
+          path.display()
+        );
+      }
+    }
+  }
+
+  if roots.is_empty() {
+    roots.push(std::env::current_dir()?);
+  }
+
+  Ok(dedupe_roots(&roots))
+}
 
-  if use_gitignore {
-    let gitignore_path = dir.join(".gitignore");
-    if !gitignore_path.exists() {
-      anyhow::bail!("No .gitignore file found in {}", dir.display());
+pub fn initialize_app(
+  paths: Vec<String>,
+  use_gitignore: bool,
+  include_lockfiles: bool,
+  max_depth: usize,
+  clean_jobs: usize,
+  native_clean: bool,
+  scope: ScanScope,
+) -> Result<App> {
+  let roots = resolve_roots(&paths)?;
+
+  if use_gitignore && scope == ScanScope::Projects {
+    for root in &roots {
+      if !root.join(".gitignore").exists() {
+        anyhow::bail!("No .gitignore file found in {}", root.display());
+      }
     }
   }
 
-  Ok(App::new(dir, use_gitignore, max_depth))
+  let config = Config::load()?;
+  let mut app = App::new(roots, use_gitignore, max_depth);
+  app.include_lockfiles = include_lockfiles;
+  app.exclude = config.exclude.clone();
+  app.protected = config.protected_paths();
+  app.clean_jobs = clean_jobs.max(1);
+  app.native_clean = native_clean;
+  app.scope = scope;
+  Ok(app)
 }
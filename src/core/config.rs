@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings read from `config.toml` in the DevTidy config directory
+/// (`~/.config/devtidy/config.toml` on Linux)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// Directories scanned when no `--path` is given; `~/` is expanded
+  pub roots: Vec<String>,
+  /// Extra `--exclude` patterns applied to every scan
+  pub exclude: Vec<String>,
+  /// Paths that are never scanned and never deleted; `~/` is expanded
+  pub protected: Vec<String>,
+  /// Model server used by the `ai-*` commands
+  pub ai: AiConfig,
+}
+
+/// The `[ai]` section of the config file
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiConfig {
+  pub provider: AiProvider,
+  /// Server URL; for `openai` this includes the `/v1` prefix
+  pub base_url: Option<String>,
+  /// Environment variable holding the API key sent to an `openai` server
+  pub api_key_env: Option<String>,
+  /// Model to use instead of picking one for the hardware
+  pub model: Option<String>,
+  pub temperature: Option<f32>,
+  /// Context window in tokens (Ollama `num_ctx`)
+  pub context_window: Option<u32>,
+  /// Longest answer in tokens (Ollama `num_predict`, OpenAI `max_tokens`)
+  pub max_tokens: Option<u32>,
+  /// Seconds to wait for a response to start
+  pub timeout_secs: Option<u64>,
+  /// Days a cached answer of `ai-explain` and `ai-suggest` is reused
+  pub cache_ttl_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiProvider {
+  /// A local Ollama daemon, started on demand
+  #[default]
+  Ollama,
+  /// Any server implementing the OpenAI chat completions API
+  #[serde(rename = "openai")]
+  OpenAi,
+}
+
+impl Config {
+  pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("devtidy").join("config.toml"))
+  }
+
+  /// Load the config file, or the defaults when there is none
+  pub fn load() -> Result<Self> {
+    match Self::path() {
+      Some(path) if path.exists() => Self::load_from(&path),
+      _ => Ok(Self::default()),
+    }
+  }
+
+  pub fn load_from(path: &Path) -> Result<Self> {
+    let contents =
+      fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+  }
+
+  /// Configured roots with `~/` expanded
+  pub fn root_paths(&self) -> Vec<PathBuf> {
+    self.roots.iter().map(|root| expand_home(root)).collect()
+  }
+
+  /// Protected paths with `~/` expanded
+  pub fn protected_paths(&self) -> Vec<PathBuf> {
+    self
+      .protected
+      .iter()
+      .map(|path| expand_home(path))
+      .collect()
+  }
+}
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+  match (path.strip_prefix('~'), dirs::home_dir()) {
+    (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+      home.join(rest.trim_start_matches('/'))
+    }
+    _ => PathBuf::from(path),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_roots() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("config.toml");
+    fs::write(
+      &path,
+      "roots = [\"~/work\", \"/data/builds\"]\nprotected = [\"~/work/legacy-build\"]\n",
+    )
+    .unwrap();
+
+    let config = Config::load_from(&path).unwrap();
+    let home = dirs::home_dir().unwrap();
+
+    assert_eq!(
+      config.root_paths(),
+      vec![home.join("work"), PathBuf::from("/data/builds")]
+    );
+    assert_eq!(
+      config.protected_paths(),
+      vec![home.join("work/legacy-build")]
+    );
+  }
+
+  #[test]
+  fn reads_ai_provider() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("config.toml");
+    fs::write(
+      &path,
+      "[ai]\nprovider = \"openai\"\nbase_url = \"http://gpu-box:8000/v1\"\napi_key_env = \"VLLM_KEY\"\n",
+    )
+    .unwrap();
+
+    let config = Config::load_from(&path).unwrap();
+
+    assert_eq!(config.ai.provider, AiProvider::OpenAi);
+    assert_eq!(
+      config.ai.base_url.as_deref(),
+      Some("http://gpu-box:8000/v1")
+    );
+    assert_eq!(config.ai.api_key_env.as_deref(), Some("VLLM_KEY"));
+    assert_eq!(Config::default().ai.provider, AiProvider::Ollama);
+  }
+
+  #[test]
+  fn rejects_unknown_keys() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("config.toml");
+    fs::write(&path, "rots = []\n").unwrap();
+
+    assert!(Config::load_from(&path).is_err());
+  }
+}
@@ -3,12 +3,29 @@ use std::collections::HashMap;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Default number of items removed in parallel
+pub const DEFAULT_CLEAN_JOBS: usize = 4;
+
+/// Per-directory exclusion file, in gitignore syntax
+pub const DEVTIDY_IGNORE_FILE: &str = ".devtidyignore";
+
+/// Free space below this share of a filesystem is shown as a warning
+pub const LOW_DISK_SPACE_PERCENT: f64 = 10.0;
+
+/// How long `dd watch` collects file system events before re-measuring
+pub const WATCH_DEBOUNCE_MS: u64 = 1000;
+
+/// Shortest time between two full re-walks of the same artifact in `dd watch`
+pub const WATCH_REMEASURE_SECS: u64 = 10;
+
 pub static CLEANABLE_PATTERNS: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
   let mut patterns = HashMap::new();
 
   // ───── JavaScript / Node.js ─────
   patterns.insert("node_modules", "Node.js dependencies");
   patterns.insert("pnpm-lock.yaml", "pnpm lock file");
+  patterns.insert("package-lock.json", "npm lock file");
+  patterns.insert("yarn.lock", "Yarn lock file");
   patterns.insert(".yarn", "Yarn cache directory");
   patterns.insert(".parcel-cache", "Parcel bundler cache");
   patterns.insert(".next", "Next.js build artifacts");
@@ -21,6 +38,7 @@ pub static CLEANABLE_PATTERNS: Lazy<HashMap<&'static str, &'static str>> = Lazy:
 
   // ───── Rust ─────
   patterns.insert("target", "Rust build artifacts");
+  patterns.insert("Cargo.lock", "Cargo lock file");
   patterns.insert("debug", "Rust debug output");
   patterns.insert("release", "Rust release output");
   patterns.insert("deps", "Rust/Elixir dependencies");
@@ -33,6 +51,8 @@ pub static CLEANABLE_PATTERNS: Lazy<HashMap<&'static str, &'static str>> = Lazy:
   patterns.insert("venv", "Python virtual environment");
   patterns.insert(".venv", "Python virtual environment");
   patterns.insert("env", "Python virtual environment");
+  patterns.insert("poetry.lock", "Poetry lock file");
+  patterns.insert("Pipfile.lock", "Pipenv lock file");
   patterns.insert("*.pyc", "Compiled Python files");
   patterns.insert("*.pyo", "Optimized Python files");
 
@@ -75,3 +95,367 @@ pub static CLEANABLE_PATTERNS: Lazy<HashMap<&'static str, &'static str>> = Lazy:
 
   patterns
 });
+
+/// Names in `CLEANABLE_PATTERNS` that are only reported when `pyvenv.cfg`
+/// confirms they are Python virtual environments
+pub const PYTHON_ENV_PATTERNS: &[&str] = &["venv", ".venv", "env"];
+
+/// Lockfiles in `CLEANABLE_PATTERNS`; they are only listed when orphaned,
+/// unless `--lockfiles` is given
+pub const LOCKFILE_PATTERNS: &[&str] = &[
+  "pnpm-lock.yaml",
+  "package-lock.json",
+  "yarn.lock",
+  "Cargo.lock",
+  "poetry.lock",
+  "Pipfile.lock",
+];
+
+const NODE_MANIFESTS: &[&str] = &["package.json"];
+const PYTHON_MANIFESTS: &[&str] = &["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"];
+
+/// Manifests owning an artifact. When none of them sits next to the artifact,
+/// the project it belonged to is gone and the artifact is orphaned.
+pub static ORPHAN_MANIFESTS: Lazy<HashMap<&'static str, &'static [&'static str]>> =
+  Lazy::new(|| {
+    let mut manifests: HashMap<&'static str, &'static [&'static str]> = HashMap::new();
+
+    manifests.insert("node_modules", NODE_MANIFESTS);
+    manifests.insert("pnpm-lock.yaml", NODE_MANIFESTS);
+    manifests.insert("package-lock.json", NODE_MANIFESTS);
+    manifests.insert("yarn.lock", NODE_MANIFESTS);
+
+    manifests.insert("target", &["Cargo.toml", "pom.xml", "build.sbt"]);
+    manifests.insert("Cargo.lock", &["Cargo.toml"]);
+
+    manifests.insert("venv", PYTHON_MANIFESTS);
+    manifests.insert(".venv", PYTHON_MANIFESTS);
+    manifests.insert("env", PYTHON_MANIFESTS);
+    manifests.insert("poetry.lock", &["pyproject.toml"]);
+    manifests.insert("Pipfile.lock", &["Pipfile"]);
+
+    manifests
+  });
+
+/// Manifests that identify a project's ecosystem, for `dd ai-report`
+pub const ECOSYSTEM_MANIFESTS: &[(&str, &str)] = &[
+  ("package.json", "Node.js"),
+  ("Cargo.toml", "Rust"),
+  ("pyproject.toml", "Python"),
+  ("requirements.txt", "Python"),
+  ("setup.py", "Python"),
+  ("Pipfile", "Python"),
+  ("go.mod", "Go"),
+  ("pom.xml", "JVM"),
+  ("build.gradle", "JVM"),
+  ("build.gradle.kts", "JVM"),
+  ("build.sbt", "JVM"),
+  ("mix.exs", "Elixir"),
+  ("CMakeLists.txt", "C/C++"),
+  ("composer.json", "PHP"),
+  ("Gemfile", "Ruby"),
+  ("pubspec.yaml", "Dart"),
+  ("Package.swift", "Swift"),
+];
+
+/// How much it costs to get an artifact back after deleting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegenerationCost {
+  /// Recreated automatically the next time the tool runs
+  Free,
+  /// Rebuilt by an incremental build
+  Cheap,
+  /// Downloaded or installed again
+  Moderate,
+  /// A full rebuild or reinstall
+  Expensive,
+  /// Holds data or pinned versions that cannot be regenerated as they were
+  Irreplaceable,
+}
+
+/// Regeneration cost and how the artifact comes back, keyed by the same names
+/// as `CLEANABLE_PATTERNS`. Used to rank candidates for `--free`.
+pub static REGENERATION_COSTS: Lazy<HashMap<&'static str, (RegenerationCost, &'static str)>> =
+  Lazy::new(|| {
+    use RegenerationCost::*;
+
+    let mut costs = HashMap::new();
+    for name in [
+      "__pycache__",
+      ".pytest_cache",
+      ".mypy_cache",
+      ".ruff_cache",
+      "*.pyc",
+      "*.pyo",
+      ".parcel-cache",
+      ".vite",
+      ".turbo",
+      ".cache",
+      "node_modules/.cache",
+      ".scannerwork",
+      "coverage",
+      ".DS_Store",
+      "*.tmp",
+      "*.swp",
+      "*.swo",
+    ] {
+      costs.insert(name, (Free, "recreated automatically on the next run"));
+    }
+    for name in [
+      "dist",
+      "build",
+      "out",
+      ".next",
+      ".svelte-kit",
+      "_build",
+      "debug",
+      "release",
+      "cmake-build-debug",
+      "cmake-build-release",
+      "build-*",
+      "DerivedData",
+    ] {
+      costs.insert(name, (Cheap, "rebuilt by the next build"));
+    }
+    costs.insert(
+      "node_modules",
+      (Moderate, "reinstalled by npm/pnpm/yarn install"),
+    );
+    costs.insert(".yarn", (Moderate, "downloaded again by yarn install"));
+    costs.insert("deps", (Moderate, "fetched again by the package manager"));
+    costs.insert(".gradle", (Moderate, "recreated by the next Gradle build"));
+    costs.insert("target", (Expensive, "needs a full cargo rebuild"));
+    for name in PYTHON_ENV_PATTERNS {
+      costs.insert(
+        name,
+        (
+          Expensive,
+          "the environment must be recreated and packages reinstalled",
+        ),
+      );
+    }
+    for name in LOCKFILE_PATTERNS {
+      costs.insert(
+        name,
+        (
+          Irreplaceable,
+          "re-resolving may pick different dependency versions",
+        ),
+      );
+    }
+    for name in [
+      ".env",
+      ".vscode",
+      ".idea",
+      "docker-compose.override.yml",
+      "*.db",
+      "*.sqlite3",
+      "*.log",
+      "*.bak",
+      "*.old",
+    ] {
+      costs.insert(
+        name,
+        (Irreplaceable, "holds local data that cannot be regenerated"),
+      );
+    }
+
+    costs
+  });
+
+/// A clean command provided by the tool that created an artifact
+pub struct NativeClean {
+  pub program: &'static str,
+  pub args: &'static [&'static str],
+  /// Manifests (glob patterns) one of which must sit next to the artifact;
+  /// empty for global caches, which are only cleaned at their global location
+  /// (`GLOBAL_CACHE_LOCATIONS`) since the command wipes the user's whole cache
+  pub markers: &'static [&'static str],
+}
+
+/// Optional native clean actions, keyed by the same names as `CLEANABLE_PATTERNS`
+pub static NATIVE_CLEAN_ACTIONS: Lazy<HashMap<&'static str, NativeClean>> = Lazy::new(|| {
+  let mut actions = HashMap::new();
+
+  actions.insert(
+    "target",
+    NativeClean {
+      program: "cargo",
+      args: &["clean"],
+      markers: &["Cargo.toml"],
+    },
+  );
+  actions.insert(
+    "build",
+    NativeClean {
+      program: "gradle",
+      args: &["clean"],
+      markers: &["build.gradle", "build.gradle.kts"],
+    },
+  );
+  actions.insert(
+    "obj",
+    NativeClean {
+      program: "dotnet",
+      args: &["clean"],
+      markers: &["*.csproj", "*.fsproj", "*.sln"],
+    },
+  );
+  actions.insert(
+    "go-build",
+    NativeClean {
+      program: "go",
+      args: &["clean", "-cache"],
+      markers: &[],
+    },
+  );
+  actions.insert(
+    "_cacache",
+    NativeClean {
+      program: "npm",
+      args: &["cache", "clean", "--force"],
+      markers: &[],
+    },
+  );
+  actions.insert(
+    ".pnpm-store",
+    NativeClean {
+      program: "pnpm",
+      args: &["store", "prune"],
+      markers: &[],
+    },
+  );
+
+  actions
+});
+
+/// Directory a global cache location is relative to
+pub enum CacheBase {
+  Home,
+  /// `~/.cache` on Linux, `~/Library/Caches` on macOS, `%LOCALAPPDATA%` on Windows
+  Cache,
+}
+
+/// A well-known developer cache that lives outside project trees
+pub struct GlobalCacheLocation {
+  pub base: CacheBase,
+  pub path: &'static str,
+  pub name: &'static str,
+  pub description: &'static str,
+}
+
+pub static GLOBAL_CACHE_LOCATIONS: &[GlobalCacheLocation] = &[
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".cargo/registry",
+    name: "Cargo registry",
+    description: "Downloaded crate sources and registry index; re-fetched by cargo on the next build",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".cargo/git",
+    name: "Cargo git checkouts",
+    description: "Git dependencies checked out by cargo; re-cloned on the next build",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".npm/_cacache",
+    name: "npm cache",
+    description: "npm's content-addressable package cache; packages are downloaded again on install",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Cache,
+    path: "pip",
+    name: "pip cache",
+    description: "Downloaded wheels and HTTP cache for pip; safe to delete at any time",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".gradle/caches",
+    name: "Gradle caches",
+    description: "Gradle dependency and build caches; rebuilt on the next Gradle run",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".m2/repository",
+    name: "Maven repository",
+    description: "Local Maven repository; artifacts are downloaded again when needed (locally installed SNAPSHOTs are lost)",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Cache,
+    path: "go-build",
+    name: "Go build cache",
+    description: "Compiled Go packages; rebuilt automatically by the go tool",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Home,
+    path: ".pnpm-store",
+    name: "pnpm store",
+    description: "pnpm's content-addressable package store; projects re-link from it after reinstall",
+  },
+  GlobalCacheLocation {
+    base: CacheBase::Cache,
+    path: "yarn",
+    name: "Yarn cache",
+    description: "Yarn v1 package cache; packages are downloaded again on install",
+  },
+];
+
+/// A version manager that keeps one directory per installed toolchain
+pub struct ToolchainManager {
+  pub name: &'static str,
+  /// Environment variable overriding the manager's home directory
+  pub env_var: &'static str,
+  /// Default home directory, relative to the user's home
+  pub home: &'static str,
+  /// Directory holding the installed versions, relative to the manager's home
+  pub versions: &'static str,
+  /// Versions are grouped by candidate (`candidates/java/17.0.8-tem`)
+  pub grouped: bool,
+  pub description: &'static str,
+}
+
+pub static TOOLCHAIN_MANAGERS: &[ToolchainManager] = &[
+  ToolchainManager {
+    name: "rustup",
+    env_var: "RUSTUP_HOME",
+    home: ".rustup",
+    versions: "toolchains",
+    grouped: false,
+    description: "Rust toolchain",
+  },
+  ToolchainManager {
+    name: "nvm",
+    env_var: "NVM_DIR",
+    home: ".nvm",
+    versions: "versions/node",
+    grouped: false,
+    description: "Node.js version",
+  },
+  ToolchainManager {
+    name: "pyenv",
+    env_var: "PYENV_ROOT",
+    home: ".pyenv",
+    versions: "versions",
+    grouped: false,
+    description: "Python version",
+  },
+  ToolchainManager {
+    name: "sdkman",
+    env_var: "SDKMAN_DIR",
+    home: ".sdkman",
+    versions: "candidates",
+    grouped: true,
+    description: "SDKMAN candidate",
+  },
+];
+
+/// Project files pinning a toolchain version, and the manager they refer to
+pub static TOOLCHAIN_PIN_FILES: &[(&str, &str)] = &[
+  ("rust-toolchain.toml", "rustup"),
+  ("rust-toolchain", "rustup"),
+  (".nvmrc", "nvm"),
+  (".node-version", "nvm"),
+  (".python-version", "pyenv"),
+  (".sdkmanrc", "sdkman"),
+];
@@ -1,6 +1,15 @@
+use crate::ai::{Advisor, Verdict};
+use crate::core::constants::DEFAULT_CLEAN_JOBS;
+use crate::services::budget::{self, BudgetPlan};
+use crate::services::cleaner::{CleanResult, CleanUpdate};
+use crate::services::disks::{disk_space, mounted_disks, DiskSpace};
+use crate::services::scanner::ScanOptions;
 use ratatui::widgets::ListState;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 #[derive(Clone, Debug)]
 pub struct CleanableItem {
@@ -9,6 +18,14 @@ pub struct CleanableItem {
   pub size: u64,
   pub info: String,
   pub selected: bool,
+  /// Shown but never selectable, e.g. a toolchain a project still pins
+  pub protected: bool,
+  /// The manifest of the project this belonged to no longer exists
+  pub orphaned: bool,
+  /// Scan root the item was found under
+  pub root: Option<PathBuf>,
+  /// Shown from the scan index and not yet confirmed by the running scan
+  pub stale: bool,
 }
 
 impl CleanableItem {
@@ -19,6 +36,10 @@ impl CleanableItem {
       size,
       info,
       selected: false,
+      protected: false,
+      orphaned: false,
+      root: None,
+      stale: false,
     }
   }
 
@@ -46,6 +67,67 @@ pub enum AppState {
   Cleaning,
   Complete,
   Help,
+  /// Reviewing the items `--free` picked before cleaning them
+  Plan,
+}
+
+/// What the TUI is scanning: project trees under the target directory,
+/// well-known global caches in the home directory, or installed toolchains
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanScope {
+  Projects,
+  Global,
+  Toolchains,
+}
+
+impl ScanScope {
+  pub const ALL: [ScanScope; 3] = [
+    ScanScope::Projects,
+    ScanScope::Global,
+    ScanScope::Toolchains,
+  ];
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      ScanScope::Projects => "Projects",
+      ScanScope::Global => "Global caches",
+      ScanScope::Toolchains => "Toolchains",
+    }
+  }
+
+  pub fn next(&self) -> Self {
+    match self {
+      ScanScope::Projects => ScanScope::Global,
+      ScanScope::Global => ScanScope::Toolchains,
+      ScanScope::Toolchains => ScanScope::Projects,
+    }
+  }
+}
+
+/// Progress of asking the model for a verdict on every item (`a`)
+#[derive(Debug, Clone, Default)]
+pub struct AiReview {
+  /// Set once connected
+  pub model: Option<String>,
+  pub done: usize,
+  pub total: usize,
+  /// Items the model gave no usable verdict for
+  pub failed: usize,
+}
+
+/// The AI's answer about one item (`e`), streamed in and kept for the session
+#[derive(Debug, Clone, Default)]
+pub struct Explanation {
+  pub text: String,
+  pub done: bool,
+  pub error: Option<String>,
+}
+
+/// Progress of an explanation, sent from the background task
+pub enum ExplainUpdate {
+  Token(PathBuf, String),
+  Done(PathBuf),
+  Failed(PathBuf, String),
 }
 
 pub struct App {
@@ -57,8 +139,48 @@ pub struct App {
   pub cleaning: bool,
   pub total_size: u64,
   pub cleaned_size: u64,
-  pub current_dir: PathBuf,
+  pub partially_cleaned: usize,
+  pub failed_to_clean: usize,
+  /// Items moved aside whose deletion failed and resumes on the next start
+  pub deferred_cleans: usize,
+  pub natively_cleaned: usize,
+  pub last_clean_error: Option<String>,
+  pub roots: Vec<PathBuf>,
+  /// Filesystems of the scanned paths, for the header
+  pub disks: Vec<DiskSpace>,
+  /// Every mounted filesystem, to tell which one an item lives on
+  pub mounts: Vec<DiskSpace>,
+  pub scope: ScanScope,
   pub use_gitignore: bool,
+  pub include_lockfiles: bool,
+  pub exclude: Vec<String>,
+  pub protected: Vec<PathBuf>,
+  /// `dd watch`: keep item sizes up to date from file system events
+  pub watching: bool,
+  pub watch_error: Option<String>,
+  /// `--free`: bytes to free once the scan finishes
+  pub budget_target: Option<u64>,
+  pub budget: Option<BudgetPlan>,
+  /// Size being typed after pressing `f`
+  pub budget_input: Option<String>,
+  pub budget_error: Option<String>,
+  pub plan_scroll: usize,
+  /// `--model` for the AI review
+  pub ai_model: Option<String>,
+  /// The model server, connected on first use and shared by the review and
+  /// the explanations for the rest of the session
+  pub advisor: Arc<tokio::sync::OnceCell<Advisor>>,
+  /// Verdicts of the AI review by item path, kept across rescans
+  pub verdicts: std::collections::HashMap<PathBuf, Verdict>,
+  pub ai_review: Option<AiReview>,
+  pub ai_error: Option<String>,
+  /// AI explanations by item path
+  pub explanations: std::collections::HashMap<PathBuf, Explanation>,
+  pub explain_rx: Option<mpsc::UnboundedReceiver<ExplainUpdate>>,
+  /// The task streaming the explanation in progress
+  pub explain_task: Option<tokio::task::JoinHandle<()>>,
+  /// Show the explanation of the highlighted item next to the list
+  pub show_explanation: bool,
   pub scan_start_time: Instant,
   pub scan_duration: Duration,
   pub scanned_items: usize,
@@ -70,6 +192,18 @@ pub struct App {
   pub processing_item: Option<String>,
   pub max_depth: usize,
   pub help_scroll: usize,
+  pub clean_jobs: usize,
+  pub native_clean: bool,
+  pub clean_start_time: Instant,
+  pub clean_total_bytes: u64,
+  pub clean_removed_bytes: u64,
+  pub clean_removed_files: usize,
+  pub clean_items_total: usize,
+  pub clean_active_items: Vec<String>,
+  pub clean_results: Vec<CleanResult>,
+  pub clean_rx: Option<mpsc::Receiver<CleanUpdate>>,
+  pub clean_cancel: Option<Arc<AtomicBool>>,
+  pub clean_cancelled: bool,
 }
 
 impl Default for App {
@@ -83,8 +217,35 @@ impl Default for App {
       cleaning: false,
       total_size: 0,
       cleaned_size: 0,
-      current_dir: std::env::current_dir().unwrap_or_default(),
+      partially_cleaned: 0,
+      failed_to_clean: 0,
+      deferred_cleans: 0,
+      natively_cleaned: 0,
+      last_clean_error: None,
+      roots: vec![std::env::current_dir().unwrap_or_default()],
+      disks: Vec::new(),
+      mounts: Vec::new(),
+      scope: ScanScope::Projects,
       use_gitignore: false,
+      include_lockfiles: false,
+      exclude: Vec::new(),
+      protected: Vec::new(),
+      watching: false,
+      watch_error: None,
+      budget_target: None,
+      budget: None,
+      budget_input: None,
+      budget_error: None,
+      plan_scroll: 0,
+      ai_model: None,
+      advisor: Arc::new(tokio::sync::OnceCell::new()),
+      verdicts: std::collections::HashMap::new(),
+      ai_review: None,
+      ai_error: None,
+      explanations: std::collections::HashMap::new(),
+      explain_rx: None,
+      explain_task: None,
+      show_explanation: false,
       scan_start_time: Instant::now(),
       scan_duration: Duration::from_secs(0),
       scanned_items: 0,
@@ -96,20 +257,61 @@ impl Default for App {
       processing_item: None,
       max_depth: 10,
       help_scroll: 0,
+      clean_jobs: DEFAULT_CLEAN_JOBS,
+      native_clean: false,
+      clean_start_time: Instant::now(),
+      clean_total_bytes: 0,
+      clean_removed_bytes: 0,
+      clean_removed_files: 0,
+      clean_items_total: 0,
+      clean_active_items: Vec::new(),
+      clean_results: Vec::new(),
+      clean_rx: None,
+      clean_cancel: None,
+      clean_cancelled: false,
     }
   }
 }
 
 impl App {
-  pub fn new(target_dir: PathBuf, use_gitignore: bool, max_depth: usize) -> Self {
+  pub fn new(roots: Vec<PathBuf>, use_gitignore: bool, max_depth: usize) -> Self {
     Self {
-      current_dir: target_dir,
+      roots,
       use_gitignore,
       max_depth,
       ..Default::default()
     }
   }
 
+  pub fn display_roots(&self) -> String {
+    self
+      .roots
+      .iter()
+      .map(|root| root.display().to_string())
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  /// Re-read free space for the filesystems the current scope looks at
+  pub fn refresh_disks(&mut self) {
+    let mut paths = self.roots.clone();
+    if self.scope != ScanScope::Projects {
+      paths.extend(dirs::home_dir());
+    }
+    self.mounts = mounted_disks();
+    self.disks = disk_space(&self.mounts, &paths);
+  }
+
+  pub fn scan_options(&self) -> ScanOptions {
+    ScanOptions {
+      use_gitignore: self.use_gitignore,
+      max_depth: self.max_depth,
+      include_lockfiles: self.include_lockfiles,
+      exclude: self.exclude.clone(),
+      protected: self.protected.clone(),
+    }
+  }
+
   pub fn next(&mut self) {
     if self.items.is_empty() {
       return;
@@ -148,12 +350,39 @@ impl App {
 
   pub fn toggle_selection(&mut self) {
     if let Some(i) = self.list_state.selected() {
-      if i < self.items.len() {
+      if i < self.items.len() && !self.items[i].protected {
         self.items[i].selected = !self.items[i].selected;
       }
     }
   }
 
+  /// Select exactly the items the cheapest plan for `target` bytes picks
+  pub fn apply_budget(&mut self, target: u64) {
+    let plan = budget::plan(&self.items, target);
+    for item in &mut self.items {
+      item.selected = plan.choices.iter().any(|choice| choice.path == item.path);
+    }
+    self.total_size = self.selected_size();
+    self.budget = Some(plan);
+    self.plan_scroll = 0;
+  }
+
+  /// Record the model's verdict on `path`, selecting the item when the model
+  /// is confident it is safe to delete
+  pub fn apply_verdict(&mut self, path: PathBuf, verdict: Verdict) {
+    if verdict.preselects() {
+      if let Some(item) = self
+        .items
+        .iter_mut()
+        .find(|item| item.path == path && !item.protected)
+      {
+        item.selected = true;
+      }
+      self.total_size = self.selected_size();
+    }
+    self.verdicts.insert(path, verdict);
+  }
+
   pub fn selected_count(&self) -> usize {
     self.items.iter().filter(|item| item.selected).count()
   }
@@ -167,8 +396,22 @@ impl App {
       .sum()
   }
 
+  /// Estimated time left for the running clean, based on the byte rate so far
+  pub fn clean_eta(&self) -> Option<Duration> {
+    let elapsed = self.clean_start_time.elapsed().as_secs_f64();
+    if self.clean_removed_bytes == 0 || elapsed < 0.5 {
+      return None;
+    }
+
+    let rate = self.clean_removed_bytes as f64 / elapsed;
+    let remaining = self
+      .clean_total_bytes
+      .saturating_sub(self.clean_removed_bytes) as f64;
+    Some(Duration::from_secs_f64(remaining / rate))
+  }
+
   pub fn sort_by_size(&mut self) {
-    self.items.sort_by(|a, b| b.size.cmp(&a.size));
+    self.items.sort_by_key(|item| std::cmp::Reverse(item.size));
   }
 
   pub fn get_selected_info(&self) -> String {
@@ -16,23 +16,56 @@ use std::io;
 /// DevTidy - Clean development artifacts from your projects
 #[derive(Parser, Debug)]
 #[clap(version = core::constants::VERSION, about, long_about = None)]
-#[clap(disable_version_flag = true)]
+#[clap(disable_version_flag = true, disable_help_flag = true)]
 struct Args {
   #[clap(subcommand)]
   command: Option<Commands>,
 
-  /// Target directory to scan (defaults to current working directory)
+  /// Directory to scan; repeat to scan several roots (defaults to the roots in
+  /// the config file, then the current working directory)
   #[clap(short = 'p', long, value_parser, global = true)]
-  path: Option<String>,
+  path: Vec<String>,
 
   /// Scan files matching .gitignore patterns
   #[clap(long, global = true)]
   gitignore: bool,
 
+  /// Skip paths matching this gitignore-style glob, or this absolute path; repeatable
+  #[clap(long, value_name = "GLOB", value_parser = services::exclude::parse_exclude, global = true)]
+  exclude: Vec<String>,
+
+  /// Also list lockfiles of projects whose manifest still exists
+  #[clap(long, global = true)]
+  lockfiles: bool,
+
   /// Maximum depth for directory scanning (default: 6)
   #[clap(short, long, default_value = "6", global = true)]
   depth: usize,
 
+  /// Number of items removed in parallel while cleaning (default: 4)
+  #[clap(short, long, default_value_t = core::constants::DEFAULT_CLEAN_JOBS, global = true)]
+  jobs: usize,
+
+  /// Use the ecosystem's own clean command (cargo clean, gradle clean, ...) when available
+  #[clap(long, global = true)]
+  native: bool,
+
+  /// Preselect the cheapest items that free this much space, e.g. 20G
+  #[clap(long, value_name = "SIZE", value_parser = services::budget::parse_size, global = true)]
+  free: Option<u64>,
+
+  /// Model used by the ai-* commands instead of the configured or hardware-picked one
+  #[clap(long, global = true)]
+  model: Option<String>,
+
+  /// Ask the model again instead of reusing cached ai-explain and ai-suggest answers
+  #[clap(long, global = true)]
+  no_cache: bool,
+
+  /// Print the cleanable items instead of opening the TUI
+  #[clap(short, long)]
+  list: bool,
+
   /// Show version information
   #[clap(short, long = "version")]
   version: bool,
@@ -42,33 +75,108 @@ struct Args {
   install: bool,
 
   /// Show help information
-  #[clap(short, long)]
+  #[clap(short, long, global = true)]
   help: bool,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+  /// Find and clean global developer caches outside project trees
+  Global {
+    /// Print the caches with their sizes instead of opening the TUI
+    #[clap(long)]
+    list: bool,
+  },
+  /// Keep the TUI open and follow artifact growth with file system events
+  Watch,
+  /// Find old rustup, nvm, pyenv and SDKMAN toolchains
+  Toolchains {
+    /// Print the toolchains with their sizes instead of opening the TUI
+    #[clap(long)]
+    list: bool,
+    /// Remove every toolchain that is not a default, active or pinned by a project
+    #[clap(long)]
+    prune: bool,
+    /// Do not ask for confirmation before pruning
+    #[clap(short, long)]
+    yes: bool,
+  },
+  /// Report stale git worktrees and .git sizes, and prune them
+  Git {
+    /// Remove worktrees whose branch is merged or deleted, or whose directory is gone
+    #[clap(long)]
+    prune: bool,
+    /// Run git gc in repositories with loose objects or garbage
+    #[clap(long)]
+    gc: bool,
+    /// Do not ask for confirmation
+    #[clap(short, long)]
+    yes: bool,
+  },
   /// Explain what a folder is used for using AI
   AiExplain {
     /// Path to the folder to explain (defaults to current directory)
     path: Option<String>,
   },
-  /// Get AI suggestions for cleaning the current project
-  AiSuggest,
+  /// Get AI suggestions for the cleanable folders the scan finds
+  AiSuggest {
+    /// Directory to scan, in addition to any --path roots
+    path: Option<String>,
+    /// Offer to remove the folders the model is confident are safe to delete
+    #[clap(long)]
+    clean: bool,
+    /// Do not ask for confirmation before cleaning
+    #[clap(short, long)]
+    yes: bool,
+  },
+  /// Write a project-wide cleanup report with an AI plan
+  AiReport {
+    /// Directory to scan, in addition to any --path roots
+    path: Option<String>,
+    /// File to write; HTML if it ends in .html, Markdown otherwise
+    #[clap(short, long)]
+    output: Option<String>,
+    /// Write HTML
+    #[clap(long)]
+    html: bool,
+  },
   /// Start an interactive AI chat for cleaning advice
-  AiChat,
+  AiChat {
+    /// Saved session to resume or start (default: "default")
+    #[clap(long)]
+    session: Option<String>,
+    /// Let the model inspect the filesystem with read-only tools
+    #[clap(long)]
+    tools: bool,
+  },
   /// Run AI system diagnostics
   AiDiagnose,
   /// Test AI context functionality (debug)
   AiTestContext,
 }
 
+/// Scan options from the command line and the config file, for the headless commands
+fn scan_options(args: &Args) -> Result<services::scanner::ScanOptions> {
+  let config = core::config::Config::load()?;
+  Ok(services::scanner::ScanOptions {
+    use_gitignore: args.gitignore,
+    max_depth: args.depth,
+    include_lockfiles: args.lockfiles,
+    protected: config.protected_paths(),
+    exclude: config
+      .exclude
+      .into_iter()
+      .chain(args.exclude.iter().cloned())
+      .collect(),
+  })
+}
+
 async fn run() -> Result<()> {
   use std::env;
   use std::fs;
   use std::io::Write;
 
-  let args = Args::parse();
+  let mut args = Args::parse();
 
   if args.version {
     println!("DevTidy v{}", core::constants::VERSION);
@@ -88,45 +196,131 @@ async fn run() -> Result<()> {
     println!("  dd [OPTIONS] [COMMAND]");
     println!();
     println!("OPTIONS:");
-    println!("  -p, --path <PATH>          Target directory to scan (defaults to current working directory)");
+    println!("  -p, --path <PATH>          Directory to scan; repeat for several roots (default: config roots or current directory)");
     println!("  --gitignore                Scan files matching .gitignore patterns");
+    println!(
+      "  --lockfiles                Also list lockfiles of projects that still have a manifest"
+    );
     println!("  -d, --depth <DEPTH>        Maximum depth for directory scanning (default: 6)");
+    println!("  -j, --jobs <JOBS>          Number of items removed in parallel (default: 4)");
+    println!(
+      "  --native                   Use cargo clean, gradle clean, ... when the tool is installed"
+    );
+    println!("  --exclude <GLOB>           Skip matching paths (gitignore syntax or absolute path); repeatable");
+    println!("  --free <SIZE>              Preselect the cheapest items that free SIZE (e.g. 20G)");
+    println!("  --model <MODEL>            Model for the ai-* commands (default: config, then picked for the hardware)");
+    println!(
+      "  --no-cache                 Ask the model again instead of reusing cached AI answers"
+    );
+    println!("  -l, --list                 Print cleanable items instead of opening the TUI");
     println!("  -v, --version              Show version information");
     println!("  -i, --install              Install devtidy globally");
     println!("  -h, --help                 Show help information");
     println!();
     println!("COMMANDS:");
+    println!(
+      "  global [--list]            Find and clean global developer caches (~/.cargo, ~/.npm, ...)"
+    );
+    println!("  watch                      Keep the TUI open and follow artifact growth live");
+    println!("  toolchains [--list|--prune] Find old rustup, nvm, pyenv and SDKMAN toolchains");
+    println!("  git [--prune] [--gc]       Find stale worktrees and bloated .git directories");
     println!("  ai-explain <PATH>          Explain what a folder is used for using AI");
-    println!("  ai-suggest [PATH]          Get AI suggestions for cleaning (file/folder or current directory)");
-    println!("  ai-chat                    Start an interactive AI chat for cleaning advice");
+    println!("  ai-suggest [PATH] [--clean] Get AI verdicts on the scan, one prompt per project; --clean removes the safe ones");
+    println!("  ai-report [PATH] [-o FILE] Write a per-project cleanup report with an AI plan (Markdown or --html)");
+    println!(
+      "  ai-chat [--session NAME]   Start or resume a saved AI chat (/help lists its commands)"
+    );
     println!("  ai-diagnose                Run AI system diagnostics");
     println!("  ai-test-context            Test AI context functionality (debug)");
     println!();
     println!("EXAMPLES:");
     println!("  dd                         Scan current directory");
     println!("  dd -p /path/to/project     Scan specific directory");
+    println!("  dd -p ~/work -p ~/oss      Scan several roots at once");
     println!("  dd --gitignore             Scan with .gitignore patterns");
+    println!("  dd --exclude vendor/       Never scan vendor directories");
+    println!("  dd --free 20G              Pick the cheapest artifacts that free 20 GB");
+    println!("  dd global --list           List global caches and their sizes");
+    println!("  dd watch -p ~/work         Watch target/ and node_modules grow while you build");
+    println!("  dd toolchains --prune      Remove toolchains no project pins");
+    println!("  dd git --prune --gc        Remove stale worktrees and run git gc");
     println!("  dd ai-explain              Explain current directory with AI");
     println!("  dd ai-explain node_modules Explain specific folder with AI");
     println!("  dd ai-suggest              Get AI cleaning suggestions for current directory");
-    println!("  dd ai-suggest package-lock.json Get AI suggestion for specific file");
+    println!("  dd ai-suggest ~/work -d 3  Get AI suggestions for every project under ~/work");
+    println!(
+      "  dd ai-suggest --clean      Remove the folders the AI is confident are safe to delete"
+    );
+    println!("  dd ai-report ~/work -o report.html  Write a cleanup report to attach to a ticket");
     return Ok(());
   }
 
-  // Handle AI subcommands
-  if let Some(command) = args.command {
+  let mut scope = core::models::ScanScope::Projects;
+  let mut watching = false;
+
+  // Handle subcommands
+  if let Some(command) = args.command.take() {
     match command {
+      Commands::Global { list: true } => {
+        return services::global::print_global_caches();
+      }
+      Commands::Global { list: false } => {
+        scope = core::models::ScanScope::Global;
+      }
+      Commands::Watch => {
+        watching = true;
+      }
+      Commands::Toolchains { list, prune, yes } => {
+        if list || prune {
+          let roots = core::app::resolve_roots(&args.path)?;
+          return if prune {
+            services::toolchains::prune_toolchains(&roots, args.depth, args.jobs, yes).await
+          } else {
+            services::toolchains::print_toolchains(&roots, args.depth)
+          };
+        }
+        scope = core::models::ScanScope::Toolchains;
+      }
+      Commands::Git { prune, gc, yes } => {
+        let roots = core::app::resolve_roots(&args.path)?;
+        return if prune || gc {
+          services::git::prune_repositories(&roots, args.depth, prune, gc, yes)
+        } else {
+          services::git::print_git_report(&roots, args.depth)
+        };
+      }
       Commands::AiExplain { path } => {
-        return ai::handle_ai_explain(path).await;
+        return ai::handle_ai_explain(path, args.model, args.no_cache).await;
+      }
+      Commands::AiSuggest { path, clean, yes } => {
+        args.path.extend(path);
+        let roots = core::app::resolve_roots(&args.path)?;
+        let scan_options = scan_options(&args)?;
+        let options = ai::SuggestOptions {
+          clean,
+          yes,
+          jobs: args.jobs,
+          no_cache: args.no_cache,
+        };
+        return ai::handle_ai_suggest(args.model, roots, scan_options, options).await;
       }
-      Commands::AiSuggest => {
-        return ai::handle_ai_suggest().await;
+      Commands::AiReport { path, output, html } => {
+        args.path.extend(path);
+        let roots = core::app::resolve_roots(&args.path)?;
+        let scan_options = scan_options(&args)?;
+        let options = ai::ReportOptions {
+          output: output.map(|output| core::config::expand_home(&output)),
+          html,
+        };
+        return ai::handle_ai_report(args.model, roots, scan_options, options).await;
       }
-      Commands::AiChat => {
-        return ai::handle_ai_chat().await;
+      Commands::AiChat { session, tools } => {
+        let roots = core::app::resolve_roots(&args.path)?;
+        let scan_options = scan_options(&args)?;
+        return ai::handle_ai_chat(args.model, session, tools, roots, scan_options).await;
       }
       Commands::AiDiagnose => {
-        return ai::handle_ai_diagnose().await;
+        return ai::handle_ai_diagnose(args.model).await;
       }
       Commands::AiTestContext => {
         return ai::handle_ai_test_context().await;
@@ -215,11 +409,11 @@ async fn run() -> Result<()> {
         .split(':')
         .any(|p| p == install_dir.to_string_lossy())
       {
-        let export_line = format!("export PATH=\"$HOME/.devtidy:$PATH\"\n");
+        let export_line = "export PATH=\"$HOME/.devtidy:$PATH\"\n".to_string();
 
         let mut needs_append = true;
         if let Ok(existing) = fs::read_to_string(&profile_file) {
-          if existing.contains(&export_line.trim()) {
+          if existing.contains(export_line.trim()) {
             needs_append = false;
           }
         }
@@ -245,13 +439,37 @@ async fn run() -> Result<()> {
     return Ok(());
   }
 
-  let mut app = match core::app::initialize_app(args.path, args.gitignore, args.depth) {
+  if args.list {
+    let roots = core::app::resolve_roots(&args.path)?;
+    let options = scan_options(&args)?;
+    return match args.free {
+      Some(target) => services::budget::print_plan(&roots, &options, target),
+      None => services::scanner::print_scan(&roots, &options),
+    };
+  }
+
+  let mut app = match core::app::initialize_app(
+    args.path,
+    args.gitignore,
+    args.lockfiles,
+    args.depth,
+    args.jobs,
+    args.native,
+    scope,
+  ) {
     Ok(app) => app,
     Err(err) => {
       eprintln!("Error: {}", err);
       return Err(err);
     }
   };
+  app.watching = watching;
+  app.budget_target = args.free;
+  app.ai_model = args.model;
+  app.exclude.extend(args.exclude);
+
+  // Finish deleting anything an interrupted clean left behind
+  tokio::task::spawn_blocking(services::tombstone::recover);
 
   enable_raw_mode()?;
   let mut stdout = io::stdout();
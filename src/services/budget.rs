@@ -0,0 +1,355 @@
+use crate::core::constants::{RegenerationCost, REGENERATION_COSTS};
+use crate::core::models::CleanableItem;
+use crate::services::scanner::{scan_with_sizes, ScanOptions};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Age in days after which an artifact counts half as much as a fresh one
+const HALF_IMPACT_DAYS: f64 = 30.0;
+
+/// Parse a size such as `20G`, `512M`, `1.5TB` or `800MiB` into bytes.
+/// Units are powers of 1024, matching the sizes shown everywhere else.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+  let input = input.trim();
+  let split = input
+    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+    .unwrap_or(input.len());
+  let (number, unit) = input.split_at(split);
+
+  let number: f64 = number
+    .parse()
+    .map_err(|_| format!("invalid size '{}', expected e.g. 20G or 500M", input))?;
+  let exponent = match unit.trim().to_ascii_uppercase().as_str() {
+    "" | "B" => 0,
+    "K" | "KB" | "KIB" => 1,
+    "M" | "MB" | "MIB" => 2,
+    "G" | "GB" | "GIB" => 3,
+    "T" | "TB" | "TIB" => 4,
+    other => return Err(format!("unknown size unit '{}'", other)),
+  };
+
+  Ok((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// An item picked to reach the budget, with why it was considered cheap
+#[derive(Debug, Clone)]
+pub struct BudgetChoice {
+  pub path: PathBuf,
+  pub size: u64,
+  pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BudgetPlan {
+  pub target: u64,
+  pub freed: u64,
+  pub choices: Vec<BudgetChoice>,
+}
+
+impl BudgetPlan {
+  pub fn reached(&self) -> bool {
+    self.freed >= self.target
+  }
+
+  pub fn summary(&self) -> String {
+    let freed = human_bytes::human_bytes(self.freed as f64);
+    let target = human_bytes::human_bytes(self.target as f64);
+    if self.reached() {
+      format!(
+        "{} item(s) free {} of the {} target",
+        self.choices.len(),
+        freed,
+        target
+      )
+    } else {
+      format!(
+        "Only {} of the {} target can be freed; every candidate is selected",
+        freed, target
+      )
+    }
+  }
+}
+
+struct Candidate<'a> {
+  item: &'a CleanableItem,
+  age_days: Option<u64>,
+  cost: RegenerationCost,
+  how: &'static str,
+  /// What losing the item costs, regardless of its size
+  impact: f64,
+}
+
+impl<'a> Candidate<'a> {
+  /// `artifacts` are the paths of all scanned items; an item inside another one
+  /// (`target/debug/deps`) costs what its enclosing artifact costs, since the
+  /// same name means something else elsewhere (`deps` of Elixir)
+  fn new(item: &'a CleanableItem, artifacts: &HashSet<&Path>, now: SystemTime) -> Self {
+    let age_days = fs::symlink_metadata(&item.path)
+      .and_then(|metadata| metadata.modified())
+      .ok()
+      .and_then(|modified| now.duration_since(modified).ok())
+      .map(|age| age.as_secs() / (24 * 60 * 60));
+    let owner = item
+      .path
+      .ancestors()
+      .skip(1)
+      .filter(|ancestor| artifacts.contains(ancestor))
+      .last()
+      .unwrap_or(&item.path);
+    let (cost, how) = regeneration(owner);
+
+    let risk = if item.orphaned { 0.25 } else { 1.0 };
+    let recency = age_days.map_or(1.0, |days| {
+      HALF_IMPACT_DAYS / (HALF_IMPACT_DAYS + days as f64)
+    });
+
+    Self {
+      item,
+      age_days,
+      cost,
+      how,
+      impact: weight(cost) * risk * recency,
+    }
+  }
+
+  /// Impact per GiB freed; the ranking key
+  fn score(&self) -> f64 {
+    self.impact / (self.item.size as f64 / GIB).max(1.0 / 1024.0)
+  }
+
+  fn reason(&self) -> String {
+    let mut parts = vec![match self.age_days {
+      Some(0) => "modified today".to_string(),
+      Some(1) => "untouched for 1 day".to_string(),
+      Some(days) => format!("untouched for {} days", days),
+      None => "age unknown".to_string(),
+    }];
+    if self.item.orphaned {
+      parts.push("orphaned".to_string());
+    }
+    parts.push(format!("{} ({})", label(self.cost), self.how));
+    parts.join(", ")
+  }
+}
+
+/// Pick the items with the least impact per byte until `target` bytes are
+/// freed, then drop picks the target no longer needs. Protected items are
+/// never chosen, and neither is an item inside (or around) one already
+/// chosen, so every byte is counted once.
+pub fn plan(items: &[CleanableItem], target: u64) -> BudgetPlan {
+  let now = SystemTime::now();
+  let artifacts: HashSet<&Path> = items.iter().map(|item| item.path.as_path()).collect();
+  let mut candidates: Vec<Candidate> = items
+    .iter()
+    .filter(|item| !item.protected && item.size > 0)
+    .map(|item| Candidate::new(item, &artifacts, now))
+    .collect();
+  candidates.sort_by(|a, b| a.score().total_cmp(&b.score()));
+
+  let mut chosen: Vec<Candidate> = Vec::new();
+  let mut freed = 0u64;
+  for candidate in candidates {
+    if freed >= target {
+      break;
+    }
+    let path = &candidate.item.path;
+    let overlaps = chosen
+      .iter()
+      .any(|picked| path.starts_with(&picked.item.path) || picked.item.path.starts_with(path));
+    if overlaps {
+      continue;
+    }
+    freed += candidate.item.size;
+    chosen.push(candidate);
+  }
+
+  // A late large pick can make earlier small ones unnecessary
+  chosen.sort_by(|a, b| b.impact.total_cmp(&a.impact));
+  chosen.retain(|candidate| {
+    if freed >= target && freed - candidate.item.size >= target {
+      freed -= candidate.item.size;
+      false
+    } else {
+      true
+    }
+  });
+  chosen.sort_by_key(|candidate| std::cmp::Reverse(candidate.item.size));
+
+  BudgetPlan {
+    target,
+    freed,
+    choices: chosen
+      .iter()
+      .map(|candidate| BudgetChoice {
+        path: candidate.item.path.clone(),
+        size: candidate.item.size,
+        reason: candidate.reason(),
+      })
+      .collect(),
+  }
+}
+
+/// `dd --free <SIZE> --list`: print the plan without cleaning anything
+pub fn print_plan(roots: &[PathBuf], options: &ScanOptions, target: u64) -> Result<()> {
+  let items = scan_with_sizes(roots, options);
+  let plan = plan(&items, target);
+
+  println!("{}", plan.summary());
+  println!();
+  for choice in &plan.choices {
+    println!(
+      "{:>10}  {}",
+      human_bytes::human_bytes(choice.size as f64),
+      choice.path.display()
+    );
+    println!("{:>10}  {}", "", choice.reason);
+  }
+  Ok(())
+}
+
+fn regeneration(path: &Path) -> (RegenerationCost, &'static str) {
+  let name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let extension = path
+    .extension()
+    .map(|extension| format!("*.{}", extension.to_string_lossy()));
+
+  REGENERATION_COSTS
+    .get(name.as_str())
+    .or_else(|| REGENERATION_COSTS.get(extension?.as_str()))
+    .or_else(|| {
+      name
+        .starts_with("build-")
+        .then(|| REGENERATION_COSTS.get("build-*"))
+        .flatten()
+    })
+    .copied()
+    .unwrap_or((
+      RegenerationCost::Moderate,
+      "downloaded or rebuilt again when needed",
+    ))
+}
+
+fn weight(cost: RegenerationCost) -> f64 {
+  match cost {
+    RegenerationCost::Free => 0.25,
+    RegenerationCost::Cheap => 1.0,
+    RegenerationCost::Moderate => 2.0,
+    RegenerationCost::Expensive => 4.0,
+    RegenerationCost::Irreplaceable => 16.0,
+  }
+}
+
+fn label(cost: RegenerationCost) -> &'static str {
+  match cost {
+    RegenerationCost::Free => "free to regenerate",
+    RegenerationCost::Cheap => "cheap to regenerate",
+    RegenerationCost::Moderate => "moderate to regenerate",
+    RegenerationCost::Expensive => "expensive to regenerate",
+    RegenerationCost::Irreplaceable => "cannot be regenerated",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MIB: u64 = 1024 * 1024;
+
+  fn item(path: &str, size: u64) -> CleanableItem {
+    CleanableItem::new(PathBuf::from(path), String::new(), size, String::new())
+  }
+
+  #[test]
+  fn parses_sizes() {
+    assert_eq!(parse_size("20G"), Ok(20 * 1024 * MIB));
+    assert_eq!(parse_size("512m"), Ok(512 * MIB));
+    assert_eq!(parse_size("1.5GiB"), Ok(1536 * MIB));
+    assert_eq!(parse_size("100"), Ok(100));
+    assert!(parse_size("lots").is_err());
+    assert!(parse_size("5X").is_err());
+  }
+
+  #[test]
+  fn prefers_cheap_artifacts_and_stops_at_the_target() {
+    let items = vec![
+      item("/w/app/target", 800 * MIB),
+      item("/w/app/dist", 300 * MIB),
+      item("/w/web/.next", 300 * MIB),
+      item("/w/web/pnpm-lock.yaml", 900 * MIB),
+    ];
+
+    let plan = plan(&items, 500 * MIB);
+
+    assert!(plan.reached());
+    let paths: Vec<_> = plan.choices.iter().map(|c| c.path.clone()).collect();
+    assert_eq!(
+      paths,
+      vec![PathBuf::from("/w/app/dist"), PathBuf::from("/w/web/.next")]
+    );
+    assert!(plan.choices[0].reason.contains("rebuilt by the next build"));
+  }
+
+  #[test]
+  fn drops_picks_a_larger_one_makes_unnecessary() {
+    let mut orphan = item("/w/old/dist", 1500 * MIB);
+    orphan.orphaned = true;
+    let items = vec![item("/w/app/__pycache__", 600 * MIB), orphan];
+
+    let plan = plan(&items, 1024 * MIB);
+
+    assert_eq!(plan.freed, 1500 * MIB);
+    assert_eq!(plan.choices.len(), 1);
+    assert_eq!(plan.choices[0].path, PathBuf::from("/w/old/dist"));
+    assert!(plan.choices[0].reason.contains("orphaned"));
+  }
+
+  #[test]
+  fn nested_items_are_counted_once() {
+    let items = vec![
+      item("/w/app/target", 3 * MIB),
+      item("/w/app/target/debug", 3 * MIB),
+      item("/w/app/target/debug/deps", 2 * MIB),
+    ];
+
+    let plan = plan(&items, 5 * MIB);
+
+    assert!(!plan.reached());
+    assert_eq!(plan.freed, 3 * MIB);
+    assert_eq!(plan.choices.len(), 1);
+  }
+
+  #[test]
+  fn nested_items_cost_what_their_artifact_costs() {
+    let items = vec![
+      item("/w/app/target", 3 * MIB),
+      item("/w/app/target/debug/deps", 2 * MIB),
+    ];
+
+    let plan = plan(&items, MIB);
+
+    assert_eq!(plan.choices.len(), 1);
+    assert!(plan.choices[0].reason.contains("cargo rebuild"));
+    assert!(!plan.choices[0].reason.contains("package manager"));
+  }
+
+  #[test]
+  fn unreachable_targets_take_every_unprotected_item() {
+    let mut pinned = item("/toolchains/1.75", 4096 * MIB);
+    pinned.protected = true;
+    let items = vec![item("/w/a/target", 100 * MIB), pinned];
+
+    let plan = plan(&items, 1024 * MIB);
+
+    assert!(!plan.reached());
+    assert_eq!(plan.freed, 100 * MIB);
+    assert_eq!(plan.choices.len(), 1);
+  }
+}
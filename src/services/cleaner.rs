@@ -1,77 +1,676 @@
 use crate::core::models::CleanableItem;
+use crate::services::exclude::Protection;
+use crate::services::native::{find_native_clean, run_native_clean, NativeOutcome};
+use crate::services::tombstone;
+use std::collections::HashMap;
 use std::fs;
-use std::sync::{Arc, Mutex};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
 
 #[derive(Debug)]
 pub struct CleanResult {
   pub path: String,
   pub success: bool,
+  /// Bytes actually freed, which may be less than the item size on partial removal
   pub size: u64,
+  /// Some entries were removed but the item itself is still on disk
+  pub partial: bool,
+  pub error: Option<String>,
+  /// Set when the tool that created the item cleaned it (`cargo clean`, ...)
+  pub native: Option<NativeOutcome>,
+  /// Tombstone holding what is left of the item when deleting it failed after
+  /// it was moved aside; the item is gone from its project and its deletion
+  /// resumes on the next start
+  pub deferred: Option<PathBuf>,
 }
 
+/// Outcome of removing a single file or directory tree
+#[derive(Debug, Default)]
+pub struct RemovalOutcome {
+  pub freed: u64,
+  pub removed_entries: usize,
+  pub failed_entries: usize,
+  pub first_error: Option<io::Error>,
+  pub cancelled: bool,
+}
+
+impl RemovalOutcome {
+  pub fn is_complete(&self) -> bool {
+    self.failed_entries == 0 && !self.cancelled
+  }
+
+  fn record_error(&mut self, err: io::Error) {
+    self.failed_entries += 1;
+    if self.first_error.is_none() {
+      self.first_error = Some(err);
+    }
+  }
+}
+
+/// Progress messages sent from the cleaning workers to the UI
+#[derive(Debug)]
+pub enum CleanUpdate {
+  ItemStarted(String),
+  /// Bytes and files removed since the previous progress message
+  Progress {
+    bytes: u64,
+    files: usize,
+  },
+  ItemFinished(CleanResult),
+  /// A tombstoned item already reported as finished could not be fully deleted;
+  /// replaces the earlier result for the same path
+  ItemFailed(CleanResult),
+  Finished,
+}
+
+/// How often a worker flushes accumulated byte/file counts to the UI
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Settings for a cleaning run
+#[derive(Debug, Clone, Copy)]
+pub struct CleanOptions {
+  /// Maximum number of items removed in parallel
+  pub concurrency: usize,
+  /// Prefer the ecosystem's own clean command when one is registered
+  pub native: bool,
+}
+
+/// Remove all of `items` without the TUI, printing one line per item.
+/// Returns the bytes freed and the number of items that could not be removed.
+pub async fn clean_headless(items: &[CleanableItem], options: CleanOptions) -> (u64, usize) {
+  let mut items = items.to_vec();
+  for item in &mut items {
+    item.selected = true;
+  }
+
+  let (tx, mut rx) = mpsc::channel(100);
+  clean_selected_items(&items, options, tx);
+
+  let mut freed = 0;
+  let mut failed = 0;
+  let mut reported = HashMap::new();
+  while let Some(update) = rx.recv().await {
+    match update {
+      CleanUpdate::ItemFinished(result) => {
+        freed += result.size;
+        reported.insert(result.path.clone(), result.size);
+        if result.success {
+          println!("Removed {}", result.path);
+        } else {
+          failed += 1;
+          println!(
+            "Failed to remove {}: {}",
+            result.path,
+            result.error.unwrap_or_default()
+          );
+        }
+      }
+      CleanUpdate::ItemFailed(result) => {
+        freed -= reported.remove(&result.path).unwrap_or(0);
+        freed += result.size;
+        failed += 1;
+        println!(
+          "Failed to finish removing {}: {}",
+          result.path,
+          result.error.unwrap_or_default()
+        );
+      }
+      CleanUpdate::Finished => break,
+      _ => {}
+    }
+  }
+  (freed, failed)
+}
+
+/// Remove the selected items on a pool of at most `concurrency` blocking threads.
+///
+/// Returns a cancellation flag: once set, workers stop between files and items
+/// that have not been started are skipped.
 pub fn clean_selected_items(
   items: &[CleanableItem],
-  sender: tokio::sync::mpsc::Sender<(usize, usize, Option<String>)>,
-) -> tokio::task::JoinHandle<Vec<CleanResult>> {
+  options: CleanOptions,
+  sender: mpsc::Sender<CleanUpdate>,
+) -> Arc<AtomicBool> {
+  let CleanOptions {
+    concurrency,
+    native,
+  } = options;
   let selected: Vec<CleanableItem> = items.iter().filter(|item| item.selected).cloned().collect();
-
-  let total = selected.len();
+  let cancel = Arc::new(AtomicBool::new(false));
+  let cancel_flag = Arc::clone(&cancel);
 
   tokio::spawn(async move {
-    if total == 0 {
-      return Vec::new();
-    }
-
-    let results = Arc::new(Mutex::new(Vec::new()));
+    let protection = match tokio::task::spawn_blocking(Protection::load).await {
+      Ok(protection) => Arc::new(protection),
+      Err(e) => Arc::new(Protection::Unreadable(e.to_string())),
+    };
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
     let mut handles = Vec::new();
 
-    for (index, item) in selected.iter().enumerate() {
-      let results_clone = Arc::clone(&results);
-      let sender_clone = sender.clone();
-      let path_str = item.display_path();
-      let size = item.size;
-      let path = item.path.clone();
-
-      let handle = tokio::spawn(async move {
-        let _ = sender_clone
-          .send((index, total, Some(path_str.clone())))
-          .await;
-
-        let success = if path.is_dir() {
-          match fs::remove_dir_all(&path) {
-            Ok(_) => true,
-            Err(_) => false,
-          }
+    for item in selected {
+      let Ok(permit) = Arc::clone(&semaphore).acquire_owned().await else {
+        break;
+      };
+      if cancel_flag.load(Ordering::Relaxed) {
+        break;
+      }
+
+      let sender = sender.clone();
+      let cancel = Arc::clone(&cancel_flag);
+      let protection = Arc::clone(&protection);
+
+      handles.push(tokio::task::spawn_blocking(move || {
+        let _ = sender.blocking_send(CleanUpdate::ItemStarted(item.display_path()));
+
+        if let Some(error) = protection.error_for(&item.path) {
+          let _ = sender.blocking_send(CleanUpdate::ItemFinished(CleanResult {
+            path: item.display_path(),
+            success: false,
+            size: 0,
+            partial: false,
+            error: Some(error),
+            native: None,
+            deferred: None,
+          }));
+          drop(permit);
+          return;
+        }
+
+        let native = if native {
+          find_native_clean(&item.path)
+            .and_then(|action| run_native_clean(action, &item.path, &cancel))
         } else {
-          match fs::remove_file(&path) {
-            Ok(_) => true,
-            Err(_) => false,
-          }
+          None
         };
+        match native {
+          // Only trust the tool when the item is really gone; `dotnet clean` and
+          // friends can exit successfully and still leave files behind
+          Some(outcome) if item.path.symlink_metadata().is_err() => {
+            let result = native_result(&item, outcome, &sender);
+            let _ = sender.blocking_send(CleanUpdate::ItemFinished(result));
+          }
+          Some(outcome) if cancel.load(Ordering::Relaxed) => {
+            let _ = sender.blocking_send(CleanUpdate::ItemFinished(CleanResult {
+              path: item.display_path(),
+              success: false,
+              size: 0,
+              partial: false,
+              error: Some("Cancelled".to_string()),
+              native: Some(outcome),
+              deferred: None,
+            }));
+          }
+          other => {
+            if let Some(outcome) = other {
+              log::info!(
+                "`{}` left {} on disk ({}), deleting it directly: {}",
+                outcome.command,
+                item.display_path(),
+                outcome.status_text(),
+                outcome.output
+              );
+            }
+            remove_item(&item, &cancel, &sender)
+          }
+        }
 
-        let result = CleanResult {
-          path: path_str,
-          success,
-          size: if success { size } else { 0 },
-        };
+        drop(permit);
+      }));
+    }
 
-        {
-          let mut results = results_clone.lock().unwrap();
-          results.push(result);
-        }
+    for handle in handles {
+      let _ = handle.await;
+    }
+
+    let _ = sender.send(CleanUpdate::Finished).await;
+  });
 
-        let _ = sender_clone.send((index + 1, total, None)).await;
+  cancel
+}
+
+/// Delete an item ourselves, reporting byte progress as files go.
+///
+/// Directories are first renamed to a tombstone so an interrupted run never
+/// leaves a half-deleted `node_modules` or `target` behind. Once the rename
+/// succeeds the item is gone from its project, so it is reported as finished
+/// straight away and the tombstone is deleted afterwards; if that fails, an
+/// [`CleanUpdate::ItemFailed`] replaces the earlier result.
+fn remove_item(item: &CleanableItem, cancel: &AtomicBool, sender: &mpsc::Sender<CleanUpdate>) {
+  let tombstone = if item.path.is_dir() {
+    tombstone::bury(&item.path).ok()
+  } else {
+    None
+  };
+
+  let Some(tombstone) = tombstone else {
+    let outcome = remove_with_progress(&item.path, cancel, sender);
+    let result = removal_result(item, &item.path, outcome);
+    let _ = sender.blocking_send(CleanUpdate::ItemFinished(result));
+    return;
+  };
+
+  let _ = sender.blocking_send(CleanUpdate::ItemFinished(CleanResult {
+    path: item.display_path(),
+    success: true,
+    size: item.size,
+    partial: false,
+    error: None,
+    native: None,
+    deferred: None,
+  }));
+
+  let outcome = remove_with_progress(&tombstone, cancel, sender);
+  let mut result = removal_result(item, &tombstone, outcome);
+  if result.success {
+    tombstone::release(&tombstone);
+  } else {
+    result.error = Some(format!(
+      "{} (left in {}, deletion resumes on the next start)",
+      result.error.unwrap_or_default(),
+      tombstone.display()
+    ));
+    // Nothing is left at the item's own path to retry on
+    result.partial = false;
+    result.deferred = Some(tombstone);
+    let _ = sender.blocking_send(CleanUpdate::ItemFailed(result));
+  }
+}
+
+/// Remove `target`, flushing byte/file counts to the UI every [`PROGRESS_INTERVAL`]
+fn remove_with_progress(
+  target: &Path,
+  cancel: &AtomicBool,
+  sender: &mpsc::Sender<CleanUpdate>,
+) -> RemovalOutcome {
+  let mut pending_bytes = 0;
+  let mut pending_files = 0;
+  let mut last_flush = Instant::now();
+  let outcome = remove_path_with(target, cancel, &mut |bytes| {
+    pending_bytes += bytes;
+    pending_files += 1;
+    if last_flush.elapsed() >= PROGRESS_INTERVAL {
+      let _ = sender.blocking_send(CleanUpdate::Progress {
+        bytes: pending_bytes,
+        files: pending_files,
       });
+      pending_bytes = 0;
+      pending_files = 0;
+      last_flush = Instant::now();
+    }
+  });
+  if pending_files > 0 {
+    let _ = sender.blocking_send(CleanUpdate::Progress {
+      bytes: pending_bytes,
+      files: pending_files,
+    });
+  }
+  outcome
+}
+
+/// Result for `item` after removing `target`, which is either the item itself
+/// or the tombstone it was renamed to
+fn removal_result(item: &CleanableItem, target: &Path, outcome: RemovalOutcome) -> CleanResult {
+  let gone = target.symlink_metadata().is_err();
+  let error = match outcome.first_error {
+    Some(e) => Some(e.to_string()),
+    None if outcome.cancelled && !gone => Some("Cancelled".to_string()),
+    None => None,
+  };
+
+  CleanResult {
+    path: item.display_path(),
+    success: gone,
+    size: outcome.freed,
+    partial: !gone && outcome.removed_entries > 0,
+    error,
+    native: None,
+    deferred: None,
+  }
+}
+
+/// Turn a native clean command that removed the item into a result
+fn native_result(
+  item: &CleanableItem,
+  outcome: NativeOutcome,
+  sender: &mpsc::Sender<CleanUpdate>,
+) -> CleanResult {
+  let _ = sender.blocking_send(CleanUpdate::Progress {
+    bytes: item.size,
+    files: 0,
+  });
+
+  CleanResult {
+    path: item.display_path(),
+    success: true,
+    size: item.size,
+    partial: false,
+    error: None,
+    native: Some(outcome),
+    deferred: None,
+  }
+}
+
+/// Remove a file or directory tree, restoring write access on read-only entries
+/// owned by the current user (Go module caches, git objects, some npm packages).
+///
+/// Unlike `fs::remove_dir_all`, this keeps going after a failure so that as much
+/// as possible is removed, and reports how many bytes were actually freed.
+pub fn remove_path(path: &Path) -> RemovalOutcome {
+  remove_path_with(path, &AtomicBool::new(false), &mut |_| {})
+}
 
-      handles.push(handle);
+/// Like [`remove_path`], calling `on_file` with the size of every removed file and
+/// stopping between files once `cancel` is set.
+pub fn remove_path_with(
+  path: &Path,
+  cancel: &AtomicBool,
+  on_file: &mut dyn FnMut(u64),
+) -> RemovalOutcome {
+  let mut outcome = RemovalOutcome::default();
+  remove_entry(path, cancel, on_file, &mut outcome);
+  outcome
+}
 
-      tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+fn remove_entry(
+  path: &Path,
+  cancel: &AtomicBool,
+  on_file: &mut dyn FnMut(u64),
+  outcome: &mut RemovalOutcome,
+) {
+  if cancel.load(Ordering::Relaxed) {
+    outcome.cancelled = true;
+    return;
+  }
+
+  let metadata = match fs::symlink_metadata(path) {
+    Ok(metadata) => metadata,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+    Err(e) => return outcome.record_error(e),
+  };
+
+  if !metadata.is_dir() {
+    let len = metadata.len();
+    match retry_with_write_access(path, || fs::remove_file(path)) {
+      Ok(()) => {
+        outcome.freed += len;
+        outcome.removed_entries += 1;
+        on_file(len);
+      }
+      Err(e) => outcome.record_error(e),
     }
+    return;
+  }
 
-    for handle in handles {
-      let _ = handle.await;
+  let entries = match fs::read_dir(path) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == io::ErrorKind::PermissionDenied && make_writable(path) => {
+      match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => return outcome.record_error(e),
+      }
+    }
+    Err(e) => return outcome.record_error(e),
+  };
+
+  for entry in entries {
+    match entry {
+      Ok(entry) => remove_entry(&entry.path(), cancel, on_file, outcome),
+      Err(e) => outcome.record_error(e),
+    }
+  }
+
+  if outcome.cancelled {
+    return;
+  }
+
+  match retry_with_write_access(path, || fs::remove_dir(path)) {
+    Ok(()) => outcome.removed_entries += 1,
+    Err(e) => outcome.record_error(e),
+  }
+}
+
+/// Run a removal; on permission errors, make the entry and its parent writable and retry once
+fn retry_with_write_access<F>(path: &Path, op: F) -> io::Result<()>
+where
+  F: Fn() -> io::Result<()>,
+{
+  match op() {
+    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+      let mut fixed = make_writable(path);
+      if let Some(parent) = path.parent() {
+        fixed |= make_writable(parent);
+      }
+      if fixed {
+        op()
+      } else {
+        Err(e)
+      }
+    }
+    result => result,
+  }
+}
+
+/// Grant the owner write access (plus read/search on directories). Returns true if
+/// the permissions were changed; entries owned by other users are left alone.
+#[cfg(unix)]
+fn make_writable(path: &Path) -> bool {
+  use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+  let Ok(metadata) = fs::symlink_metadata(path) else {
+    return false;
+  };
+  if metadata.file_type().is_symlink() || metadata.uid() != unsafe { libc::geteuid() } {
+    return false;
+  }
+
+  let mode = metadata.permissions().mode();
+  let wanted = if metadata.is_dir() {
+    mode | 0o700
+  } else {
+    mode | 0o200
+  };
+  if wanted == mode {
+    return false;
+  }
+
+  fs::set_permissions(path, fs::Permissions::from_mode(wanted)).is_ok()
+}
+
+#[cfg(not(unix))]
+fn make_writable(path: &Path) -> bool {
+  let Ok(metadata) = fs::symlink_metadata(path) else {
+    return false;
+  };
+  let mut permissions = metadata.permissions();
+  if !permissions.readonly() {
+    return false;
+  }
+
+  #[allow(clippy::permissions_set_readonly_false)]
+  permissions.set_readonly(false);
+  fs::set_permissions(path, permissions).is_ok()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use std::os::unix::fs::PermissionsExt;
+
+  fn write_file(path: &Path, len: usize) {
+    fs::write(path, vec![b'x'; len]).unwrap();
+  }
+
+  fn chmod(path: &Path, mode: u32) {
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+  }
+
+  #[test]
+  fn removes_read_only_tree() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("mod");
+    let nested = root.join("github.com/foo/bar@v1.0.0");
+    fs::create_dir_all(&nested).unwrap();
+    write_file(&nested.join("go.mod"), 100);
+    write_file(&nested.join("main.go"), 250);
+    write_file(&root.join("cache.lock"), 10);
+
+    chmod(&nested.join("go.mod"), 0o444);
+    chmod(&nested.join("main.go"), 0o444);
+    chmod(&nested, 0o555);
+    chmod(nested.parent().unwrap(), 0o555);
+    chmod(&root, 0o555);
+
+    let outcome = remove_path(&root);
+
+    assert!(outcome.is_complete(), "{:?}", outcome.first_error);
+    assert_eq!(outcome.freed, 360);
+    assert!(!root.exists());
+  }
+
+  #[test]
+  fn removes_unreadable_directory() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("objects");
+    let locked = root.join("pack");
+    fs::create_dir_all(&locked).unwrap();
+    write_file(&locked.join("pack-1.pack"), 64);
+    chmod(&locked, 0o000);
+
+    let outcome = remove_path(&root);
+
+    assert!(outcome.is_complete(), "{:?}", outcome.first_error);
+    assert_eq!(outcome.freed, 64);
+    assert!(!root.exists());
+  }
+
+  #[test]
+  fn removes_single_read_only_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("debug.log");
+    write_file(&file, 42);
+    chmod(&file, 0o444);
+
+    let outcome = remove_path(&file);
+
+    assert!(outcome.is_complete());
+    assert_eq!(outcome.freed, 42);
+    assert!(!file.exists());
+  }
+
+  #[test]
+  fn cancellation_stops_between_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("target");
+    fs::create_dir_all(&root).unwrap();
+    for i in 0..10 {
+      write_file(&root.join(format!("{}.o", i)), 10);
     }
 
-    Arc::try_unwrap(results).unwrap().into_inner().unwrap()
-  })
+    let cancel = AtomicBool::new(false);
+    let mut removed = 0;
+    let outcome = remove_path_with(&root, &cancel, &mut |bytes| {
+      assert_eq!(bytes, 10);
+      removed += 1;
+      if removed == 3 {
+        cancel.store(true, Ordering::Relaxed);
+      }
+    });
+
+    assert!(outcome.cancelled);
+    assert!(!outcome.is_complete());
+    assert_eq!(outcome.freed, 30);
+    assert!(root.exists());
+    assert_eq!(fs::read_dir(&root).unwrap().count(), 7);
+  }
+
+  #[tokio::test]
+  async fn pool_reports_bytes_and_results() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut items = Vec::new();
+    for name in ["a", "b", "c"] {
+      let dir = tmp.path().join(name);
+      fs::create_dir_all(&dir).unwrap();
+      write_file(&dir.join("blob"), 100);
+      let mut item = CleanableItem::new(dir, "test".into(), 100, String::new());
+      item.selected = true;
+      items.push(item);
+    }
+
+    let (tx, mut rx) = mpsc::channel(32);
+    clean_selected_items(
+      &items,
+      CleanOptions {
+        concurrency: 2,
+        native: false,
+      },
+      tx,
+    );
+
+    let mut bytes = 0;
+    let mut files = 0;
+    let mut results = Vec::new();
+    while let Some(update) = rx.recv().await {
+      match update {
+        CleanUpdate::Progress { bytes: b, files: f } => {
+          bytes += b;
+          files += f;
+        }
+        CleanUpdate::ItemFinished(result) => results.push(result),
+        CleanUpdate::ItemFailed(result) => panic!("{:?}", result),
+        CleanUpdate::ItemStarted(_) => {}
+        CleanUpdate::Finished => break,
+      }
+    }
+
+    assert_eq!(bytes, 300);
+    assert_eq!(files, 3);
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| r.success && r.size == 100));
+  }
+
+  #[test]
+  fn cancelling_after_the_rename_defers_to_the_tombstone() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().join("node_modules");
+    fs::create_dir_all(&root).unwrap();
+    write_file(&root.join("a.js"), 100);
+    let item = CleanableItem::new(root.clone(), "test".into(), 100, String::new());
+
+    let (tx, mut rx) = mpsc::channel(32);
+    remove_item(&item, &AtomicBool::new(true), &tx);
+    drop(tx);
+
+    let mut finished = Vec::new();
+    let mut failed = Vec::new();
+    while let Ok(update) = rx.try_recv() {
+      match update {
+        CleanUpdate::ItemFinished(result) => finished.push(result),
+        CleanUpdate::ItemFailed(result) => failed.push(result),
+        _ => {}
+      }
+    }
+
+    assert_eq!(finished.len(), 1);
+    assert_eq!(failed.len(), 1);
+    let tombstone = failed[0].deferred.clone().unwrap();
+    assert!(!failed[0].success && !failed[0].partial);
+    assert!(failed[0].error.as_deref().unwrap().starts_with("Cancelled"));
+    assert!(!root.exists());
+    assert!(tombstone.join("a.js").exists());
+    tombstone::release(&tombstone);
+  }
+
+  #[test]
+  fn missing_path_is_a_no_op() {
+    let tmp = tempfile::tempdir().unwrap();
+    let outcome = remove_path(&tmp.path().join("gone"));
+
+    assert!(outcome.is_complete());
+    assert_eq!(outcome.freed, 0);
+    assert_eq!(outcome.removed_entries, 0);
+  }
 }
@@ -0,0 +1,144 @@
+use crate::core::constants::LOW_DISK_SPACE_PERCENT;
+use crate::core::models::CleanableItem;
+use crate::services::scanner::outermost_items;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+/// Capacity of a filesystem holding one of the scanned paths
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSpace {
+  pub mount_point: PathBuf,
+  pub total: u64,
+  pub available: u64,
+}
+
+impl DiskSpace {
+  pub fn is_low(&self) -> bool {
+    self.total > 0 && (self.available as f64 / self.total as f64) * 100.0 < LOW_DISK_SPACE_PERCENT
+  }
+
+  /// Free space once the selected items on this filesystem are removed.
+  /// `mounted` lists every filesystem, so an item on a mount that is not shown
+  /// is not attributed to the shown one containing its mount point.
+  pub fn projected_available(&self, mounted: &[DiskSpace], items: &[CleanableItem]) -> u64 {
+    let freed: u64 = outermost_items(items.iter().filter(|item| item.selected))
+      .into_iter()
+      .filter(|item| {
+        find_disk(mounted, &item.path).is_some_and(|disk| disk.mount_point == self.mount_point)
+      })
+      .map(|item| item.size)
+      .sum();
+    (self.available + freed).min(self.total)
+  }
+}
+
+/// Every mounted filesystem
+pub fn mounted_disks() -> Vec<DiskSpace> {
+  Disks::new_with_refreshed_list()
+    .list()
+    .iter()
+    .map(|disk| DiskSpace {
+      mount_point: disk.mount_point().to_path_buf(),
+      total: disk.total_space(),
+      available: disk.available_space(),
+    })
+    .collect()
+}
+
+/// The filesystems out of `mounted` that `paths` live on, once each, in the
+/// order of `paths`
+pub fn disk_space(mounted: &[DiskSpace], paths: &[PathBuf]) -> Vec<DiskSpace> {
+  let mut disks: Vec<DiskSpace> = Vec::new();
+  for path in paths {
+    if let Some(disk) = find_disk(mounted, path) {
+      if !disks.contains(disk) {
+        disks.push(disk.clone());
+      }
+    }
+  }
+  disks
+}
+
+/// The filesystem with the longest mount point containing `path`
+pub fn find_disk<'a>(disks: &'a [DiskSpace], path: &Path) -> Option<&'a DiskSpace> {
+  disks
+    .iter()
+    .filter(|disk| path.starts_with(&disk.mount_point))
+    .max_by_key(|disk| disk.mount_point.components().count())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const GIB: u64 = 1024 * 1024 * 1024;
+
+  fn disk(mount_point: &str, total: u64, available: u64) -> DiskSpace {
+    DiskSpace {
+      mount_point: PathBuf::from(mount_point),
+      total,
+      available,
+    }
+  }
+
+  fn selected(path: &str, size: u64) -> CleanableItem {
+    let mut item = CleanableItem::new(PathBuf::from(path), String::new(), size, String::new());
+    item.selected = true;
+    item
+  }
+
+  #[test]
+  fn picks_the_innermost_mount() {
+    let disks = vec![disk("/", 100, 50), disk("/home", 200, 20)];
+
+    assert_eq!(
+      find_disk(&disks, Path::new("/home/me/app")).map(|d| &d.mount_point),
+      Some(&PathBuf::from("/home"))
+    );
+    assert_eq!(
+      find_disk(&disks, Path::new("/var/tmp")).map(|d| &d.mount_point),
+      Some(&PathBuf::from("/"))
+    );
+  }
+
+  #[test]
+  fn projects_free_space_per_filesystem() {
+    let disks = vec![
+      disk("/", 100 * GIB, 5 * GIB),
+      disk("/home", 200 * GIB, 50 * GIB),
+    ];
+    let items = vec![
+      selected("/home/me/app/target", 8 * GIB),
+      selected("/opt/build", 3 * GIB),
+      CleanableItem::new(
+        PathBuf::from("/opt/dist"),
+        String::new(),
+        GIB,
+        String::new(),
+      ),
+    ];
+
+    assert!(disks[0].is_low());
+    assert!(!disks[1].is_low());
+    assert_eq!(disks[0].projected_available(&disks, &items), 8 * GIB);
+    assert_eq!(disks[1].projected_available(&disks, &items), 58 * GIB);
+  }
+
+  #[test]
+  fn unlisted_mounts_and_nested_items_are_not_counted() {
+    let mounted = vec![
+      disk("/", 100 * GIB, 5 * GIB),
+      disk("/home", 200 * GIB, 50 * GIB),
+      disk("/home/me/scratch", 50 * GIB, 10 * GIB),
+    ];
+    let shown = disk_space(&mounted, &[PathBuf::from("/home/me")]);
+    let items = vec![
+      selected("/home/me/app/target", 8 * GIB),
+      selected("/home/me/app/target/debug", 6 * GIB),
+      selected("/home/me/scratch/app/target", 4 * GIB),
+    ];
+
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].projected_available(&mounted, &items), 58 * GIB);
+  }
+}
@@ -0,0 +1,230 @@
+use crate::core::config::{expand_home, Config};
+use crate::core::constants::DEVTIDY_IGNORE_FILE;
+use crate::services::scanner::ScanOptions;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// What a scan of one root must skip: `--exclude` and config globs, absolute
+/// excluded or protected paths, and `.devtidyignore` files inside the root
+pub struct Exclusions {
+  root: PathBuf,
+  globs: Gitignore,
+  paths: Vec<PathBuf>,
+  /// Parsed `.devtidyignore` per directory, `None` when the directory has none
+  ignore_files: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl Exclusions {
+  pub fn new(root: &Path, options: &ScanOptions) -> Self {
+    let mut builder = GitignoreBuilder::new(root);
+    let mut paths: Vec<PathBuf> = options.protected.iter().map(|p| canonical(p)).collect();
+    for pattern in &options.exclude {
+      let path = expand_home(pattern);
+      if path.is_absolute() {
+        paths.push(canonical(&path));
+      } else {
+        // Invalid globs are rejected when the arguments are parsed
+        let _ = builder.add_line(None, pattern);
+      }
+    }
+
+    Self {
+      root: root.to_path_buf(),
+      globs: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+      paths,
+      ignore_files: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Whether the walk should skip `path` (and, for directories, everything below it)
+  pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+    if self.paths.iter().any(|excluded| path.starts_with(excluded)) {
+      return true;
+    }
+    if self.globs.matched(path, is_dir).is_ignore() {
+      return true;
+    }
+
+    // Like .gitignore, the closest file with a matching rule decides, so a
+    // nested `!pattern` can re-include what a parent file excluded
+    for dir in path.ancestors().skip(1) {
+      if !dir.starts_with(&self.root) {
+        break;
+      }
+      if let Some(ignore) = self.ignore_file(dir) {
+        match ignore.matched(path, is_dir) {
+          Match::Ignore(_) => return true,
+          Match::Whitelist(_) => return false,
+          Match::None => {}
+        }
+      }
+    }
+    false
+  }
+
+  fn ignore_file(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+    let mut cache = self.ignore_files.lock().unwrap();
+    cache
+      .entry(dir.to_path_buf())
+      .or_insert_with(|| {
+        let file = dir.join(DEVTIDY_IGNORE_FILE);
+        if !file.is_file() {
+          return None;
+        }
+        let mut builder = GitignoreBuilder::new(dir);
+        builder.add(file);
+        builder.build().ok().map(Arc::new)
+      })
+      .clone()
+  }
+}
+
+/// clap value parser for `--exclude`: absolute paths, or gitignore-style globs
+pub fn parse_exclude(pattern: &str) -> Result<String, String> {
+  GitignoreBuilder::new("/")
+    .add_line(None, pattern)
+    .map(|_| pattern.to_string())
+    .map_err(|e| format!("invalid exclude pattern '{}': {}", pattern, e))
+}
+
+/// Paths protected in the config file, which must never be deleted. Loaded
+/// once at the start of each cleaning run, so a protection added while the TUI
+/// was open still applies; an unreadable config protects everything.
+#[derive(Debug, Clone)]
+pub enum Protection {
+  Paths(Vec<PathBuf>),
+  Unreadable(String),
+}
+
+impl Protection {
+  pub fn load() -> Self {
+    match Config::load() {
+      Ok(config) => Self::Paths(
+        config
+          .protected_paths()
+          .iter()
+          .map(|path| canonical(path))
+          .collect(),
+      ),
+      Err(e) => Self::Unreadable(format!("{:#}", e)),
+    }
+  }
+
+  /// The reason `path` must not be deleted, if removing it would touch a
+  /// protected path
+  pub fn error_for(&self, path: &Path) -> Option<String> {
+    match self {
+      Self::Paths(protected) => protected_by(path, protected).map(|protected| {
+        format!(
+          "Refusing to delete: {} is protected in the config file",
+          protected.display()
+        )
+      }),
+      Self::Unreadable(error) => Some(format!("Refusing to delete: {}", error)),
+    }
+  }
+}
+
+/// The protected path that `path` is, lies inside, or contains
+fn protected_by<'a>(path: &Path, protected: &'a [PathBuf]) -> Option<&'a PathBuf> {
+  let path = canonical(path);
+  protected
+    .iter()
+    .find(|protected| path.starts_with(protected) || protected.starts_with(&path))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::scanner::scan_directory;
+  use std::fs;
+
+  fn options(exclude: &[&str], protected: &[PathBuf]) -> ScanOptions {
+    ScanOptions {
+      max_depth: 6,
+      exclude: exclude.iter().map(|s| s.to_string()).collect(),
+      protected: protected.to_vec(),
+      ..Default::default()
+    }
+  }
+
+  fn project(root: &Path, name: &str) -> PathBuf {
+    let modules = root.join(name).join("node_modules");
+    fs::create_dir_all(&modules).unwrap();
+    fs::write(root.join(name).join("package.json"), "{}").unwrap();
+    modules
+  }
+
+  #[test]
+  fn globs_and_absolute_paths_prune_the_walk() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    let kept = project(&root, "app");
+    project(&root, "vendor/lib");
+    project(&root, "legacy-build");
+
+    let legacy = root.join("legacy-build").display().to_string();
+    for use_gitignore in [false, true] {
+      fs::write(root.join(".gitignore"), "node_modules\n").unwrap();
+      let mut options = options(&["vendor/", &legacy], &[]);
+      options.use_gitignore = use_gitignore;
+
+      let items = scan_directory(&root, &options);
+      let paths: Vec<_> = items.iter().map(|item| item.path.clone()).collect();
+      assert_eq!(paths, vec![kept.clone()], "gitignore: {}", use_gitignore);
+    }
+  }
+
+  #[test]
+  fn devtidyignore_files_apply_below_their_directory() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    let skipped = project(&root, "work/old");
+    let reincluded = project(&root, "work/keep");
+    fs::write(root.join(DEVTIDY_IGNORE_FILE), "node_modules/\n").unwrap();
+    fs::write(
+      root.join("work/keep").join(DEVTIDY_IGNORE_FILE),
+      "!node_modules/\n",
+    )
+    .unwrap();
+
+    let exclusions = Exclusions::new(&root, &options(&[], &[]));
+
+    assert!(exclusions.is_excluded(&skipped, true));
+    assert!(!exclusions.is_excluded(&reincluded, true));
+  }
+
+  #[test]
+  fn protected_paths_are_skipped() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    project(&root, "app");
+    let protected = root.join("app");
+
+    let items = scan_directory(&root, &options(&[], &[protected]));
+
+    assert!(items.is_empty());
+  }
+
+  #[test]
+  fn deleting_around_a_protected_path_is_refused() {
+    let protected = vec![PathBuf::from("/work/legacy-build")];
+
+    assert!(protected_by(Path::new("/work/legacy-build/target"), &protected).is_some());
+    assert!(protected_by(Path::new("/work"), &protected).is_some());
+    assert!(protected_by(Path::new("/work/app/target"), &protected).is_none());
+  }
+
+  #[test]
+  fn rejects_invalid_globs() {
+    assert!(parse_exclude("vendor/").is_ok());
+    assert!(parse_exclude("src/{a,b").is_err());
+  }
+}
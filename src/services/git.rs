@@ -0,0 +1,572 @@
+use crate::core::constants::CLEANABLE_PATTERNS;
+use crate::services::exclude::Protection;
+use crate::services::scanner::{get_directory_size, scan_directory, ScanOptions};
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+/// Why a linked worktree is, or is not, worth removing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorktreeStatus {
+  /// Its branch still has work that is not in the default branch
+  Active,
+  /// Detached HEAD; we cannot tell whether it is still needed
+  Detached,
+  /// Its branch is merged into the default branch
+  Merged,
+  /// Its branch no longer exists
+  BranchDeleted,
+  /// The checkout directory is gone; only git's bookkeeping is left
+  Missing,
+}
+
+impl WorktreeStatus {
+  pub fn is_stale(&self) -> bool {
+    !matches!(self, WorktreeStatus::Active | WorktreeStatus::Detached)
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      WorktreeStatus::Active => "active",
+      WorktreeStatus::Detached => "detached HEAD",
+      WorktreeStatus::Merged => "branch merged",
+      WorktreeStatus::BranchDeleted => "branch deleted",
+      WorktreeStatus::Missing => "directory missing",
+    }
+  }
+}
+
+/// A linked worktree (`git worktree add`) of a repository
+#[derive(Debug)]
+pub struct Worktree {
+  pub path: PathBuf,
+  pub branch: Option<String>,
+  pub status: WorktreeStatus,
+  /// Size of the whole checkout
+  pub size: u64,
+  /// Size of the build outputs (`target`, `node_modules`, ...) inside it
+  pub artifacts_size: u64,
+  /// `git status --porcelain` is not empty, so removing it would lose work
+  pub dirty: bool,
+}
+
+/// Object database sizes as reported by `git count-objects -v`, in bytes
+#[derive(Debug, Default, PartialEq)]
+pub struct ObjectStats {
+  pub loose_objects: u64,
+  pub loose_size: u64,
+  pub packs: u64,
+  pub pack_size: u64,
+  pub garbage_size: u64,
+}
+
+impl ObjectStats {
+  /// Upper bound of what `git gc` can free: loose objects are packed (and
+  /// unreachable ones pruned) and garbage files are removed
+  pub fn gc_estimate(&self) -> u64 {
+    self.loose_size + self.garbage_size
+  }
+}
+
+#[derive(Debug)]
+pub struct RepoReport {
+  pub path: PathBuf,
+  pub default_branch: Option<String>,
+  pub git_dir_size: u64,
+  pub objects: ObjectStats,
+  pub worktrees: Vec<Worktree>,
+}
+
+/// Find git repositories under `root`, without walking into their `.git` directories
+pub fn find_repositories(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+  WalkDir::new(root)
+    .max_depth(max_depth)
+    .into_iter()
+    .filter_entry(|e| {
+      if e.depth() == 0 {
+        return true;
+      }
+      let name = e.file_name().to_string_lossy();
+      e.file_type().is_dir()
+        && !name.starts_with('.')
+        && !CLEANABLE_PATTERNS.contains_key(name.as_ref())
+    })
+    .filter_map(Result::ok)
+    .filter(|entry| entry.path().join(".git").is_dir())
+    .map(|entry| entry.into_path())
+    .collect()
+}
+
+/// Inspect a repository's worktrees and object database. Only local git
+/// commands are used, so this works offline.
+pub fn inspect_repository(repo: &Path, max_depth: usize) -> Result<RepoReport> {
+  let listing = git(repo, &["worktree", "list", "--porcelain"])?;
+  let entries = parse_worktree_list(&listing);
+
+  let default_branch = entries.first().and_then(|main| main.branch.clone());
+  let branches = lines(&git(
+    repo,
+    &["for-each-ref", "--format=%(refname)", "refs/heads"],
+  )?);
+  let merged = match &default_branch {
+    Some(branch) => lines(&git(
+      repo,
+      &[
+        "for-each-ref",
+        "--format=%(refname)",
+        "--merged",
+        branch,
+        "refs/heads",
+      ],
+    )?),
+    None => Vec::new(),
+  };
+
+  let worktrees = entries
+    .into_iter()
+    .skip(1)
+    .map(|entry| {
+      let status = if entry.prunable || !entry.path.exists() {
+        WorktreeStatus::Missing
+      } else {
+        match &entry.branch {
+          None => WorktreeStatus::Detached,
+          Some(branch) if !branches.contains(branch) => WorktreeStatus::BranchDeleted,
+          Some(branch) if merged.contains(branch) => WorktreeStatus::Merged,
+          Some(_) => WorktreeStatus::Active,
+        }
+      };
+
+      let (size, artifacts_size, dirty) = if status == WorktreeStatus::Missing {
+        (0, 0, false)
+      } else {
+        (
+          get_directory_size(&entry.path),
+          artifacts_size(&entry.path, max_depth),
+          has_uncommitted_changes(&entry.path),
+        )
+      };
+
+      Worktree {
+        path: entry.path,
+        branch: entry.branch,
+        status,
+        size,
+        artifacts_size,
+        dirty,
+      }
+    })
+    .collect();
+
+  Ok(RepoReport {
+    path: repo.to_path_buf(),
+    default_branch: default_branch.map(|branch| short_branch(&branch).to_string()),
+    git_dir_size: get_directory_size(&repo.join(".git")),
+    objects: parse_count_objects(&git(repo, &["count-objects", "-v"])?),
+    worktrees,
+  })
+}
+
+/// Print worktrees and `.git` sizes for every repository under `roots` (for `dd git`)
+pub fn print_git_report(roots: &[PathBuf], max_depth: usize) -> Result<()> {
+  let reports = inspect_all(roots, max_depth)?;
+  if reports.is_empty() {
+    println!("No git repositories found.");
+    return Ok(());
+  }
+
+  let mut reclaimable = 0;
+  let mut current_root = None;
+  for (root, report) in &reports {
+    if roots.len() > 1 && current_root != Some(root) {
+      println!("== {} ==", root.display());
+      current_root = Some(root);
+    }
+    match &report.default_branch {
+      Some(branch) => println!("{} [{}]", report.path.display(), branch),
+      None => println!("{}", report.path.display()),
+    }
+    println!(
+      "  .git: {} (loose: {} objects, {}; packs: {}, {}; garbage: {})",
+      bytes(report.git_dir_size),
+      report.objects.loose_objects,
+      bytes(report.objects.loose_size),
+      report.objects.packs,
+      bytes(report.objects.pack_size),
+      bytes(report.objects.garbage_size),
+    );
+    if report.objects.gc_estimate() > 0 {
+      println!(
+        "  git gc could reclaim up to {}",
+        bytes(report.objects.gc_estimate())
+      );
+    }
+
+    for worktree in &report.worktrees {
+      let branch = worktree.branch.as_deref().map(short_branch).unwrap_or("-");
+      let removable = worktree.status.is_stale() && !worktree.dirty;
+      println!(
+        "  {} worktree {} [{}] {}{}: {} (artifacts: {})",
+        if removable { "*" } else { " " },
+        worktree.path.display(),
+        branch,
+        worktree.status.label(),
+        if worktree.dirty {
+          ", uncommitted changes"
+        } else {
+          ""
+        },
+        bytes(worktree.size),
+        bytes(worktree.artifacts_size),
+      );
+      if removable {
+        reclaimable += worktree.size;
+      }
+    }
+    println!();
+  }
+
+  println!(
+    "* stale. Removing stale worktrees frees {}",
+    bytes(reclaimable)
+  );
+  println!("Run 'dd git --prune' to remove stale worktrees, add '--gc' to also run git gc.");
+  Ok(())
+}
+
+/// Remove stale worktrees and optionally run `git gc` (for `dd git --prune` / `--gc`)
+pub fn prune_repositories(
+  roots: &[PathBuf],
+  max_depth: usize,
+  prune: bool,
+  gc: bool,
+  yes: bool,
+) -> Result<()> {
+  let reports: Vec<RepoReport> = inspect_all(roots, max_depth)?
+    .into_iter()
+    .map(|(_, report)| report)
+    .collect();
+  let stale: Vec<(&RepoReport, &Worktree)> = reports
+    .iter()
+    .flat_map(|report| {
+      report
+        .worktrees
+        .iter()
+        .map(move |worktree| (report, worktree))
+    })
+    .filter(|(_, worktree)| prune && worktree.status.is_stale())
+    .collect();
+
+  // `git worktree remove` runs outside the cleaner, so check what it would
+  // check: never drop uncommitted work or anything protected in the config
+  let protection = Protection::load();
+  let (stale, skipped): (Vec<_>, Vec<_>) = stale.into_iter().partition(|(_, worktree)| {
+    worktree.status == WorktreeStatus::Missing
+      || (!worktree.dirty && protection.error_for(&worktree.path).is_none())
+  });
+  for (_, worktree) in &skipped {
+    let reason = protection
+      .error_for(&worktree.path)
+      .unwrap_or_else(|| "it has uncommitted changes".to_string());
+    println!(
+      "skip worktree {} ({}): {}",
+      worktree.path.display(),
+      worktree.status.label(),
+      reason
+    );
+  }
+  let gc_repos: Vec<&RepoReport> = reports
+    .iter()
+    .filter(|report| gc && report.objects.gc_estimate() > 0)
+    .collect();
+
+  if stale.is_empty() && gc_repos.is_empty() {
+    println!("Nothing to prune.");
+    return Ok(());
+  }
+
+  for (_, worktree) in &stale {
+    println!(
+      "remove worktree {} ({}, {})",
+      worktree.path.display(),
+      worktree.status.label(),
+      bytes(worktree.size)
+    );
+  }
+  for report in &gc_repos {
+    println!(
+      "git gc in {} (up to {})",
+      report.path.display(),
+      bytes(report.objects.gc_estimate())
+    );
+  }
+
+  if !yes {
+    print!("Proceed? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+      println!("Aborted.");
+      return Ok(());
+    }
+  }
+
+  let mut failed = 0;
+  for (report, worktree) in &stale {
+    let result = if worktree.status == WorktreeStatus::Missing {
+      git(&report.path, &["worktree", "prune"])
+    } else {
+      let path = worktree.path.to_string_lossy();
+      git(&report.path, &["worktree", "remove", &path])
+    };
+    match result {
+      Ok(_) => println!("Removed {}", worktree.path.display()),
+      Err(e) => {
+        failed += 1;
+        println!("Failed to remove {}: {}", worktree.path.display(), e);
+      }
+    }
+  }
+
+  for report in &gc_repos {
+    match git(&report.path, &["gc", "--quiet"]) {
+      Ok(_) => println!("Ran git gc in {}", report.path.display()),
+      Err(e) => {
+        failed += 1;
+        println!("git gc failed in {}: {}", report.path.display(), e);
+      }
+    }
+  }
+
+  if failed > 0 {
+    anyhow::bail!("{} git maintenance actions failed", failed);
+  }
+  Ok(())
+}
+
+/// Inspect every repository under `roots`, paired with the root it was found in
+fn inspect_all(roots: &[PathBuf], max_depth: usize) -> Result<Vec<(PathBuf, RepoReport)>> {
+  which::which("git").context("git is not installed")?;
+
+  let mut reports = Vec::new();
+  for root in roots {
+    for repo in find_repositories(root, max_depth) {
+      match inspect_repository(&repo, max_depth) {
+        Ok(report) => reports.push((root.clone(), report)),
+        Err(e) => eprintln!("Skipping {}: {}", repo.display(), e),
+      }
+    }
+  }
+  Ok(reports)
+}
+
+/// Whether a worktree has modified, staged or untracked files. Errors count as
+/// changes so a worktree we cannot inspect is never removed.
+fn has_uncommitted_changes(path: &Path) -> bool {
+  git(path, &["status", "--porcelain"]).map_or(true, |status| !status.trim().is_empty())
+}
+
+/// Size of the cleanable items the regular scanner finds inside a worktree
+fn artifacts_size(path: &Path, max_depth: usize) -> u64 {
+  let options = ScanOptions {
+    max_depth,
+    ..Default::default()
+  };
+  scan_directory(path, &options)
+    .iter()
+    .map(|item| {
+      if item.size == 0 && item.path.is_dir() {
+        get_directory_size(&item.path)
+      } else {
+        item.size
+      }
+    })
+    .sum()
+}
+
+struct WorktreeEntry {
+  path: PathBuf,
+  branch: Option<String>,
+  prunable: bool,
+}
+
+/// Parse `git worktree list --porcelain`; the main worktree comes first
+fn parse_worktree_list(output: &str) -> Vec<WorktreeEntry> {
+  output
+    .split("\n\n")
+    .filter_map(|block| {
+      let mut entry = WorktreeEntry {
+        path: PathBuf::new(),
+        branch: None,
+        prunable: false,
+      };
+      for line in block.lines() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+          "worktree" => entry.path = PathBuf::from(value),
+          "branch" => entry.branch = Some(value.to_string()),
+          "prunable" => entry.prunable = true,
+          _ => {}
+        }
+      }
+      (!entry.path.as_os_str().is_empty()).then_some(entry)
+    })
+    .collect()
+}
+
+/// Parse `git count-objects -v`, whose sizes are in KiB
+fn parse_count_objects(output: &str) -> ObjectStats {
+  let mut stats = ObjectStats::default();
+  for line in output.lines() {
+    let Some((key, value)) = line.split_once(':') else {
+      continue;
+    };
+    let value: u64 = value.trim().parse().unwrap_or(0);
+    match key {
+      "count" => stats.loose_objects = value,
+      "size" => stats.loose_size = value * 1024,
+      "packs" => stats.packs = value,
+      "size-pack" => stats.pack_size = value * 1024,
+      "size-garbage" => stats.garbage_size = value * 1024,
+      _ => {}
+    }
+  }
+  stats
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+  let output = Command::new("git")
+    .arg("-C")
+    .arg(repo)
+    .args(args)
+    .output()
+    .context("failed to run git")?;
+
+  if !output.status.success() {
+    anyhow::bail!(
+      "git {} failed: {}",
+      args.join(" "),
+      String::from_utf8_lossy(&output.stderr).trim()
+    );
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn lines(output: &str) -> Vec<String> {
+  output.lines().map(str::to_string).collect()
+}
+
+fn short_branch(branch: &str) -> &str {
+  branch.strip_prefix("refs/heads/").unwrap_or(branch)
+}
+
+fn bytes(size: u64) -> String {
+  human_bytes::human_bytes(size as f64)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  fn run(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+      .arg("-C")
+      .arg(dir)
+      .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+      .args(args)
+      .output()
+      .unwrap();
+    assert!(status.status.success(), "git {:?} failed", args);
+  }
+
+  #[test]
+  fn classifies_worktrees() {
+    if which::which("git").is_err() {
+      return;
+    }
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    fs::create_dir_all(&repo).unwrap();
+    run(&repo, &["init", "-q", "-b", "main"]);
+    fs::write(repo.join("README"), "hello").unwrap();
+    fs::write(repo.join(".gitignore"), "node_modules\n").unwrap();
+    run(&repo, &["add", "."]);
+    run(&repo, &["commit", "-q", "-m", "init"]);
+
+    let merged = tmp.path().join("merged");
+    let active = tmp.path().join("active");
+    let deleted = tmp.path().join("deleted");
+    let missing = tmp.path().join("missing");
+    for (path, branch) in [
+      (&merged, "merged"),
+      (&active, "active"),
+      (&deleted, "deleted"),
+      (&missing, "missing"),
+    ] {
+      run(
+        &repo,
+        &[
+          "worktree",
+          "add",
+          "-q",
+          "-b",
+          branch,
+          &path.to_string_lossy(),
+        ],
+      );
+    }
+
+    fs::write(active.join("feature"), "wip").unwrap();
+    run(&active, &["add", "."]);
+    run(&active, &["commit", "-q", "-m", "feature"]);
+    fs::create_dir_all(merged.join("node_modules/dep")).unwrap();
+    fs::write(merged.join("node_modules/dep/index.js"), vec![0u8; 64]).unwrap();
+    run(&repo, &["update-ref", "-d", "refs/heads/deleted"]);
+    fs::remove_dir_all(&missing).unwrap();
+    fs::write(active.join("notes.txt"), "uncommitted").unwrap();
+
+    let report = inspect_repository(&repo, 4).unwrap();
+    let status = |path: &Path| {
+      report
+        .worktrees
+        .iter()
+        .find(|worktree| worktree.path.file_name() == path.file_name())
+        .unwrap()
+    };
+
+    assert_eq!(report.default_branch.as_deref(), Some("main"));
+    assert_eq!(status(&merged).status, WorktreeStatus::Merged);
+    assert_eq!(status(&merged).artifacts_size, 64);
+    assert_eq!(status(&active).status, WorktreeStatus::Active);
+    assert_eq!(status(&deleted).status, WorktreeStatus::BranchDeleted);
+    assert!(status(&active).dirty);
+    assert!(!status(&merged).dirty);
+    assert_eq!(status(&missing).status, WorktreeStatus::Missing);
+    assert!(report.objects.loose_objects > 0);
+
+    assert_eq!(find_repositories(tmp.path(), 3), vec![repo]);
+  }
+
+  #[test]
+  fn parses_count_objects() {
+    let stats = parse_count_objects(
+      "count: 12\nsize: 48\nin-pack: 100\npacks: 2\nsize-pack: 1024\nprune-packable: 0\ngarbage: 1\nsize-garbage: 4\n",
+    );
+
+    assert_eq!(
+      stats,
+      ObjectStats {
+        loose_objects: 12,
+        loose_size: 48 * 1024,
+        packs: 2,
+        pack_size: 1024 * 1024,
+        garbage_size: 4 * 1024,
+      }
+    );
+    assert_eq!(stats.gc_estimate(), 52 * 1024);
+  }
+}
@@ -0,0 +1,117 @@
+use crate::core::constants::{CacheBase, GlobalCacheLocation, GLOBAL_CACHE_LOCATIONS};
+use crate::core::models::CleanableItem;
+use crate::services::scanner::get_directory_size;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Find well-known developer caches that live outside project trees
+/// (`~/.cargo/registry`, `~/.npm/_cacache`, `~/.cache/pip`, ...)
+pub fn discover_global_caches() -> Vec<CleanableItem> {
+  discover_in(dirs::home_dir().as_deref(), dirs::cache_dir().as_deref())
+}
+
+/// Print discovered global caches with their sizes (for `dd global --list`)
+pub fn print_global_caches() -> Result<()> {
+  let mut items = discover_global_caches();
+  if items.is_empty() {
+    println!("No global developer caches found.");
+    return Ok(());
+  }
+
+  for item in &mut items {
+    item.size = get_directory_size(&item.path);
+  }
+  items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+  for item in &items {
+    println!(
+      "{:>10}  {} ({})",
+      item.display_size(),
+      item.item_type,
+      item.path.display()
+    );
+    println!("            {}", item.info);
+  }
+
+  let total: u64 = items.iter().map(|item| item.size).sum();
+  println!("\nTotal: {}", human_bytes::human_bytes(total as f64));
+  println!("Run 'dd global' to select and clean them interactively.");
+  Ok(())
+}
+
+/// Check whether `path` is one of the well-known global cache locations
+pub fn is_global_cache(path: &Path) -> bool {
+  location_paths(dirs::home_dir().as_deref(), dirs::cache_dir().as_deref())
+    .any(|(_, location)| location == path)
+}
+
+/// Resolve every global cache location against the home and cache directories
+fn location_paths<'a>(
+  home: Option<&'a Path>,
+  cache: Option<&'a Path>,
+) -> impl Iterator<Item = (&'static GlobalCacheLocation, PathBuf)> + 'a {
+  GLOBAL_CACHE_LOCATIONS.iter().filter_map(move |location| {
+    let base = match location.base {
+      CacheBase::Home => home,
+      CacheBase::Cache => cache,
+    }?;
+    Some((location, base.join(location.path)))
+  })
+}
+
+fn discover_in(home: Option<&Path>, cache: Option<&Path>) -> Vec<CleanableItem> {
+  let mut seen: Vec<PathBuf> = Vec::new();
+  let mut items = Vec::new();
+
+  for (location, path) in location_paths(home, cache) {
+    if !path.is_dir() || seen.contains(&path) {
+      continue;
+    }
+    seen.push(path.clone());
+
+    items.push(CleanableItem::new(
+      path,
+      location.name.to_string(),
+      0,
+      location.description.to_string(),
+    ));
+  }
+
+  items
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn finds_only_existing_locations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = tmp.path().join("home");
+    let cache = home.join(".cache");
+    fs::create_dir_all(home.join(".cargo/registry")).unwrap();
+    fs::create_dir_all(home.join(".m2/repository")).unwrap();
+    fs::create_dir_all(cache.join("pip")).unwrap();
+    fs::create_dir_all(home.join(".cargo/bin")).unwrap();
+
+    let items = discover_in(Some(&home), Some(&cache));
+    let mut paths: Vec<_> = items.iter().map(|item| item.path.clone()).collect();
+    paths.sort();
+
+    assert_eq!(
+      paths,
+      vec![
+        home.join(".cache/pip"),
+        home.join(".cargo/registry"),
+        home.join(".m2/repository"),
+      ]
+    );
+    assert!(items.iter().all(|item| !item.info.is_empty()));
+  }
+
+  #[test]
+  fn missing_base_directories_are_skipped() {
+    assert!(discover_in(None, None).is_empty());
+  }
+}
@@ -0,0 +1,350 @@
+use crate::core::models::CleanableItem;
+use crate::services::scanner::ScanOptions;
+use crate::utils::hash::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the on-disk format changes; older indexes are ignored
+const INDEX_VERSION: u32 = 3;
+
+/// How many levels of subdirectories are checked before a recorded size is
+/// reused. Changes deeper down (a file rewritten in `node_modules/pkg/lib`)
+/// are only picked up when the size is re-measured after the scan.
+const VALIDATE_DEPTH: usize = 2;
+
+/// Results of the previous project scan, persisted so the next run can show
+/// them instantly and skip re-measuring directories that did not change
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanIndex {
+  version: u32,
+  entries: HashMap<PathBuf, IndexEntry>,
+  /// Directories seen while walking: the ones the items were found in, and
+  /// the items themselves with their subdirectories down to `VALIDATE_DEPTH`
+  dirs: HashMap<PathBuf, DirRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+  root: Option<PathBuf>,
+  item_type: String,
+  info: String,
+  size: u64,
+  orphaned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DirRecord {
+  mtime: u64,
+  /// Subdirectories at the time of the walk; only recorded inside items
+  subdirs: Vec<PathBuf>,
+}
+
+impl ScanIndex {
+  pub fn from_items(items: &[CleanableItem]) -> Self {
+    let mut dirs = HashMap::new();
+    for item in items {
+      if let Some(parent) = item.path.parent() {
+        if let Some(mtime) = mtime(parent) {
+          let record = DirRecord {
+            mtime,
+            subdirs: Vec::new(),
+          };
+          dirs.entry(parent.to_path_buf()).or_insert(record);
+        }
+      }
+      record_tree(&item.path, 0, &mut dirs);
+    }
+
+    Self {
+      version: INDEX_VERSION,
+      entries: items
+        .iter()
+        .map(|item| {
+          let entry = IndexEntry {
+            root: item.root.clone(),
+            item_type: item.item_type.clone(),
+            info: item.info.clone(),
+            size: item.size,
+            orphaned: item.orphaned,
+          };
+          (item.path.clone(), entry)
+        })
+        .collect(),
+      dirs,
+    }
+  }
+
+  /// The previous results as they were recorded. Nothing is read from disk, so
+  /// this is cheap enough to call before the first frame; `changed` tells which
+  /// rows are out of date.
+  pub fn preview(&self) -> Vec<CleanableItem> {
+    let mut items: Vec<CleanableItem> = self
+      .entries
+      .iter()
+      .map(|(path, entry)| {
+        let mut item = CleanableItem::new(
+          path.clone(),
+          entry.item_type.clone(),
+          entry.size,
+          entry.info.clone(),
+        );
+        item.root = entry.root.clone();
+        item.orphaned = entry.orphaned;
+        item
+      })
+      .collect();
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+    items
+  }
+
+  /// Recorded items that may have changed since the index was written: their
+  /// parent directory, the item or one of its directories down to
+  /// `VALIDATE_DEPTH` levels was modified or is gone
+  pub fn changed(&self) -> Vec<PathBuf> {
+    self
+      .entries
+      .keys()
+      .filter(|path| {
+        let parent_unchanged = path.parent().is_some_and(|parent| {
+          self
+            .dirs
+            .get(parent)
+            .is_some_and(|record| mtime(parent) == Some(record.mtime))
+        });
+        !parent_unchanged || !self.tree_unchanged(path, 0)
+      })
+      .cloned()
+      .collect()
+  }
+
+  /// The recorded size of `path`, if neither it nor its directories down to
+  /// `VALIDATE_DEPTH` levels were modified. Deeper changes are not seen, so
+  /// callers should still re-measure the directory later.
+  pub fn cached_size(&self, path: &Path) -> Option<u64> {
+    let entry = self.entries.get(path)?;
+    self.tree_unchanged(path, 0).then_some(entry.size)
+  }
+
+  /// Whether `dir` and its recorded subdirectories still have the recorded
+  /// modification times. A directory with an unchanged mtime has the same
+  /// entries as before, so its recorded subdirectories are stat'ed instead of
+  /// reading the directory again.
+  fn tree_unchanged(&self, dir: &Path, depth: usize) -> bool {
+    let Some(record) = self.dirs.get(dir) else {
+      return false;
+    };
+    if mtime(dir) != Some(record.mtime) {
+      return false;
+    }
+    depth >= VALIDATE_DEPTH
+      || record
+        .subdirs
+        .iter()
+        .all(|subdir| self.tree_unchanged(subdir, depth + 1))
+  }
+}
+
+/// Load the index written by the last scan of the same roots with the same options
+pub fn load(roots: &[PathBuf], options: &ScanOptions) -> Option<ScanIndex> {
+  load_from(&index_path(roots, options)?)
+}
+
+pub fn save(roots: &[PathBuf], options: &ScanOptions, items: &[CleanableItem]) -> io::Result<()> {
+  match index_path(roots, options) {
+    Some(path) => save_to(&path, items),
+    None => Ok(()),
+  }
+}
+
+fn load_from(path: &Path) -> Option<ScanIndex> {
+  let contents = fs::read_to_string(path).ok()?;
+  let index: ScanIndex = serde_json::from_str(&contents).ok()?;
+  (index.version == INDEX_VERSION).then_some(index)
+}
+
+fn save_to(path: &Path, items: &[CleanableItem]) -> io::Result<()> {
+  if let Some(dir) = path.parent() {
+    fs::create_dir_all(dir)?;
+  }
+
+  // Write to a temporary file first so a crash never leaves a truncated index
+  let tmp = path.with_extension("json.tmp");
+  fs::write(&tmp, serde_json::to_vec(&ScanIndex::from_items(items))?)?;
+  fs::rename(tmp, path)
+}
+
+/// One index file per set of roots and scan options
+fn index_path(roots: &[PathBuf], options: &ScanOptions) -> Option<PathBuf> {
+  let key = serde_json::to_vec(&(
+    roots,
+    options.use_gitignore,
+    options.max_depth,
+    options.include_lockfiles,
+    &options.exclude,
+    &options.protected,
+  ))
+  .ok()?;
+
+  dirs::cache_dir().map(|dir| {
+    dir
+      .join("devtidy")
+      .join("index")
+      .join(format!("{:016x}.json", fnv1a(&key)))
+  })
+}
+
+/// Record `dir` and its subdirectories down to `VALIDATE_DEPTH` levels
+fn record_tree(dir: &Path, depth: usize, dirs: &mut HashMap<PathBuf, DirRecord>) {
+  let Some(mtime) = mtime(dir) else {
+    return;
+  };
+  let subdirs: Vec<PathBuf> = if depth < VALIDATE_DEPTH {
+    fs::read_dir(dir)
+      .map(|entries| {
+        entries
+          .filter_map(Result::ok)
+          .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+          .map(|entry| entry.path())
+          .collect()
+      })
+      .unwrap_or_default()
+  } else {
+    Vec::new()
+  };
+
+  for subdir in &subdirs {
+    record_tree(subdir, depth + 1, dirs);
+  }
+  dirs.insert(dir.to_path_buf(), DirRecord { mtime, subdirs });
+}
+
+/// Modification time in nanoseconds since the epoch
+fn mtime(path: &Path) -> Option<u64> {
+  let modified = fs::symlink_metadata(path).ok()?.modified().ok()?;
+  Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item(path: &Path, size: u64) -> CleanableItem {
+    CleanableItem::new(
+      path.to_path_buf(),
+      "Node.js dependencies".to_string(),
+      size,
+      "Node.js dependencies".to_string(),
+    )
+  }
+
+  #[test]
+  fn round_trips_and_reuses_unchanged_sizes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let modules = tmp.path().join("app/node_modules");
+    fs::create_dir_all(&modules).unwrap();
+    let index_file = tmp.path().join("cache/index.json");
+
+    save_to(&index_file, &[item(&modules, 4096)]).unwrap();
+    let index = load_from(&index_file).unwrap();
+
+    assert_eq!(index.cached_size(&modules), Some(4096));
+    assert!(index.changed().is_empty());
+    let preview = index.preview();
+    assert_eq!(preview.len(), 1);
+    assert_eq!(preview[0].size, 4096);
+  }
+
+  #[test]
+  fn changed_directories_are_stale() {
+    let tmp = tempfile::tempdir().unwrap();
+    let app = tmp.path().join("app");
+    let modules = app.join("node_modules");
+    let gone = app.join("dist");
+    fs::create_dir_all(&modules).unwrap();
+    fs::create_dir_all(&gone).unwrap();
+    let index_file = tmp.path().join("index.json");
+
+    save_to(&index_file, &[item(&modules, 10), item(&gone, 20)]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::remove_dir(&gone).unwrap();
+    fs::write(modules.join("new-package"), "x").unwrap();
+
+    let index = load_from(&index_file).unwrap();
+
+    assert_eq!(index.cached_size(&modules), None);
+    assert_eq!(index.cached_size(&gone), None);
+    let mut changed = index.changed();
+    changed.sort();
+    assert_eq!(changed, vec![gone, modules]);
+    // The preview is shown as recorded; the scan task marks changed rows
+    assert!(index.preview().iter().all(|item| !item.stale));
+  }
+
+  #[test]
+  fn nested_changes_invalidate_the_size() {
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("app/target");
+    let deps = target.join("debug/deps");
+    fs::create_dir_all(&deps).unwrap();
+    let index_file = tmp.path().join("index.json");
+
+    save_to(&index_file, &[item(&target, 10)]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(deps.join("libfoo.rlib"), "x").unwrap();
+
+    let index = load_from(&index_file).unwrap();
+
+    assert_eq!(index.cached_size(&target), None);
+  }
+
+  #[test]
+  fn unchanged_directories_are_not_read_again() {
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("app/target");
+    let debug = target.join("debug");
+    fs::create_dir_all(&debug).unwrap();
+    let index_file = tmp.path().join("index.json");
+
+    save_to(&index_file, &[item(&target, 10)]).unwrap();
+    let recorded = fs::metadata(&target).unwrap().modified().unwrap();
+    fs::create_dir(target.join("release")).unwrap();
+    fs::File::open(&target)
+      .unwrap()
+      .set_modified(recorded)
+      .unwrap();
+
+    let index = load_from(&index_file).unwrap();
+
+    // With the mtime put back, the recorded listing of `target` is trusted
+    assert_eq!(index.cached_size(&target), Some(10));
+  }
+
+  #[test]
+  fn index_names_do_not_depend_on_the_std_hasher() {
+    let roots = vec![PathBuf::from("/work")];
+    let options = ScanOptions::default();
+
+    let name = index_path(&roots, &options)
+      .unwrap()
+      .file_name()
+      .unwrap()
+      .to_string_lossy()
+      .to_string();
+
+    assert_eq!(name, "8385f1ed4c5b900b.json");
+  }
+
+  #[test]
+  fn ignores_other_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let index_file = tmp.path().join("index.json");
+    fs::write(&index_file, r#"{"version":2,"entries":{},"dirs":{}}"#).unwrap();
+
+    assert!(load_from(&index_file).is_none());
+  }
+}
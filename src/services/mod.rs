@@ -1,2 +1,14 @@
+pub mod budget;
 pub mod cleaner;
+pub mod disks;
+pub mod exclude;
+pub mod git;
+pub mod global;
+pub mod index;
+pub mod native;
+pub mod orphans;
 pub mod scanner;
+pub mod tombstone;
+pub mod toolchains;
+pub mod venv;
+pub mod watcher;
@@ -0,0 +1,244 @@
+use crate::core::constants::{NativeClean, NATIVE_CLEAN_ACTIONS};
+use crate::services::global::is_global_cache;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Keep at most this many bytes of command output in a clean result
+const MAX_OUTPUT_LEN: usize = 4000;
+
+/// How often a running clean command is checked for completion or cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Captured result of running a native clean command
+#[derive(Debug)]
+pub struct NativeOutcome {
+  pub command: String,
+  pub success: bool,
+  pub exit_code: Option<i32>,
+  pub output: String,
+}
+
+impl NativeOutcome {
+  /// Exit status in words, for messages
+  pub fn status_text(&self) -> String {
+    match self.exit_code {
+      Some(code) => format!("exit code {}", code),
+      None => "terminated by signal".to_string(),
+    }
+  }
+}
+
+/// Find the native clean action registered for `path`, provided the manifest it
+/// needs (e.g. `Cargo.toml` for `target`) sits next to it, or, for global cache
+/// commands, that `path` is the global cache itself rather than a project folder
+/// with the same name
+pub fn find_native_clean(path: &Path) -> Option<&'static NativeClean> {
+  find_native_clean_with(path, &is_global_cache)
+}
+
+fn find_native_clean_with(
+  path: &Path,
+  is_global: &dyn Fn(&Path) -> bool,
+) -> Option<&'static NativeClean> {
+  let name = path.file_name()?.to_str()?;
+  let action = NATIVE_CLEAN_ACTIONS.get(name)?;
+
+  if action.markers.is_empty() {
+    return is_global(path).then_some(action);
+  }
+
+  let parent = path.parent()?;
+  let names: Vec<String> = fs::read_dir(parent)
+    .ok()?
+    .filter_map(Result::ok)
+    .map(|entry| entry.file_name().to_string_lossy().to_string())
+    .collect();
+
+  let has_marker = action.markers.iter().any(|marker| {
+    glob::Pattern::new(marker)
+      .map(|pattern| names.iter().any(|name| pattern.matches(name)))
+      .unwrap_or(false)
+  });
+
+  has_marker.then_some(action)
+}
+
+/// Run `action` from the directory containing `path`. Returns `None` when the
+/// tool is not installed or cannot be started, so the caller can fall back to
+/// deleting the files itself. The command is killed once `cancel` is set.
+pub fn run_native_clean(
+  action: &NativeClean,
+  path: &Path,
+  cancel: &AtomicBool,
+) -> Option<NativeOutcome> {
+  let program = which::which(action.program).ok()?;
+  let dir = path.parent()?;
+
+  let mut child = Command::new(program)
+    .args(action.args)
+    .current_dir(dir)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .ok()?;
+
+  // Drain both pipes while waiting so a chatty tool cannot block on a full pipe
+  let stdout = child.stdout.take().map(read_all);
+  let stderr = child.stderr.take().map(read_all);
+
+  let mut cancelled = false;
+  let status = loop {
+    match child.try_wait() {
+      Ok(Some(status)) => break Some(status),
+      Ok(None) if cancel.load(Ordering::Relaxed) => {
+        cancelled = true;
+        let _ = child.kill();
+        break child.wait().ok();
+      }
+      Ok(None) => thread::sleep(POLL_INTERVAL),
+      Err(_) => break None,
+    }
+  };
+
+  let mut text = String::new();
+  for reader in [stdout, stderr].into_iter().flatten() {
+    text.push_str(&String::from_utf8_lossy(&reader.join().unwrap_or_default()));
+  }
+  if cancelled {
+    text.push_str("\nCancelled");
+  }
+
+  Some(NativeOutcome {
+    command: format!("{} {}", action.program, action.args.join(" ")),
+    success: !cancelled && status.is_some_and(|status| status.success()),
+    exit_code: status.and_then(|status| status.code()),
+    output: tail(text.trim(), MAX_OUTPUT_LEN).to_string(),
+  })
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+  thread::spawn(move || {
+    let mut buffer = Vec::new();
+    let _ = pipe.read_to_end(&mut buffer);
+    buffer
+  })
+}
+
+/// The last `max` bytes of `text`, cut on a character boundary
+fn tail(text: &str, max: usize) -> &str {
+  if text.len() <= max {
+    return text;
+  }
+
+  let mut start = text.len() - max;
+  while !text.is_char_boundary(start) {
+    start += 1;
+  }
+  &text[start..]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn requires_manifest_next_to_artifact() {
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("target");
+    fs::create_dir_all(&target).unwrap();
+
+    assert!(find_native_clean(&target).is_none());
+
+    fs::write(tmp.path().join("Cargo.toml"), "[package]").unwrap();
+    let action = find_native_clean(&target).unwrap();
+    assert_eq!(action.program, "cargo");
+  }
+
+  #[test]
+  fn matches_glob_markers() {
+    let tmp = tempfile::tempdir().unwrap();
+    let obj = tmp.path().join("obj");
+    fs::create_dir_all(&obj).unwrap();
+    fs::write(tmp.path().join("App.csproj"), "<Project />").unwrap();
+
+    assert_eq!(find_native_clean(&obj).unwrap().program, "dotnet");
+  }
+
+  #[test]
+  fn global_cache_commands_only_run_on_the_global_cache() {
+    let tmp = tempfile::tempdir().unwrap();
+    let global = tmp.path().join("home/.npm/_cacache");
+    let project = tmp.path().join("project/_cacache");
+    let is_global = |path: &Path| path == global;
+
+    assert_eq!(
+      find_native_clean_with(&global, &is_global).unwrap().program,
+      "npm"
+    );
+    assert!(find_native_clean_with(&project, &is_global).is_none());
+  }
+
+  #[test]
+  fn missing_tool_falls_back() {
+    let tmp = tempfile::tempdir().unwrap();
+    let action = NativeClean {
+      program: "devtidy-definitely-not-installed",
+      args: &[],
+      markers: &[],
+    };
+
+    assert!(
+      run_native_clean(&action, &tmp.path().join("target"), &AtomicBool::new(false)).is_none()
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn captures_output_and_exit_status() {
+    let tmp = tempfile::tempdir().unwrap();
+    let action = NativeClean {
+      program: "sh",
+      args: &["-c", "echo cleaning; echo oops >&2; exit 3"],
+      markers: &[],
+    };
+
+    let outcome =
+      run_native_clean(&action, &tmp.path().join("target"), &AtomicBool::new(false)).unwrap();
+
+    assert!(!outcome.success);
+    assert_eq!(outcome.exit_code, Some(3));
+    assert!(outcome.output.contains("cleaning"));
+    assert!(outcome.output.contains("oops"));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn cancellation_kills_the_command() {
+    let tmp = tempfile::tempdir().unwrap();
+    let action = NativeClean {
+      program: "sleep",
+      args: &["30"],
+      markers: &[],
+    };
+    let cancel = AtomicBool::new(true);
+
+    let started = std::time::Instant::now();
+    let outcome = run_native_clean(&action, &tmp.path().join("target"), &cancel).unwrap();
+
+    assert!(!outcome.success);
+    assert!(outcome.output.contains("Cancelled"));
+    assert!(started.elapsed() < Duration::from_secs(10));
+  }
+
+  #[test]
+  fn tail_keeps_char_boundaries() {
+    assert_eq!(tail("héllo", 4), "llo");
+    assert_eq!(tail("short", 10), "short");
+  }
+}
@@ -0,0 +1,95 @@
+use crate::core::constants::ORPHAN_MANIFESTS;
+use std::path::Path;
+
+/// The manifests that should own `path` when none of them exists next to it,
+/// i.e. the artifact outlived its project. `None` for healthy artifacts and for
+/// names without an owning manifest.
+pub fn missing_manifests(path: &Path) -> Option<&'static [&'static str]> {
+  let name = path.file_name()?.to_str()?;
+  let manifests = *ORPHAN_MANIFESTS.get(name)?;
+  let parent = path.parent()?;
+
+  let owned = manifests
+    .iter()
+    .any(|manifest| parent.join(manifest).exists());
+  (!owned).then_some(manifests)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::scanner::{scan_directory, ScanOptions};
+  use std::fs;
+
+  #[test]
+  fn flags_artifacts_without_manifest() {
+    let tmp = tempfile::tempdir().unwrap();
+    let live = tmp.path().join("live");
+    let gone = tmp.path().join("gone");
+    fs::create_dir_all(live.join("node_modules")).unwrap();
+    fs::create_dir_all(gone.join("node_modules")).unwrap();
+    fs::write(live.join("package.json"), "{}").unwrap();
+
+    assert!(missing_manifests(&live.join("node_modules")).is_none());
+    assert_eq!(
+      missing_manifests(&gone.join("node_modules")),
+      Some(&["package.json"][..])
+    );
+    assert!(missing_manifests(&gone.join("dist")).is_none());
+  }
+
+  #[test]
+  fn any_manifest_owns_the_artifact() {
+    let tmp = tempfile::tempdir().unwrap();
+    fs::create_dir_all(tmp.path().join(".venv")).unwrap();
+    fs::write(tmp.path().join("requirements.txt"), "flask").unwrap();
+
+    assert!(missing_manifests(&tmp.path().join(".venv")).is_none());
+  }
+
+  #[test]
+  fn lockfiles_are_only_listed_when_orphaned() {
+    let tmp = tempfile::tempdir().unwrap();
+    let live = tmp.path().join("live");
+    let gone = tmp.path().join("gone");
+    fs::create_dir_all(&live).unwrap();
+    fs::create_dir_all(&gone).unwrap();
+    fs::write(live.join("package.json"), "{}").unwrap();
+    fs::write(live.join("pnpm-lock.yaml"), "lockfileVersion: 6").unwrap();
+    fs::write(gone.join("pnpm-lock.yaml"), "lockfileVersion: 6").unwrap();
+
+    let mut options = ScanOptions {
+      max_depth: 3,
+      ..Default::default()
+    };
+    let items = scan_directory(tmp.path(), &options);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].path, gone.join("pnpm-lock.yaml"));
+    assert!(items[0].orphaned);
+
+    options.include_lockfiles = true;
+    let items = scan_directory(tmp.path(), &options);
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().any(|item| !item.orphaned));
+  }
+
+  #[test]
+  fn gitignore_scans_skip_live_lockfiles() {
+    let tmp = tempfile::tempdir().unwrap();
+    let live = tmp.path().join("live");
+    fs::create_dir_all(&live).unwrap();
+    fs::write(live.join("Cargo.toml"), "[package]").unwrap();
+    fs::write(live.join("Cargo.lock"), "version = 3").unwrap();
+    fs::write(tmp.path().join(".gitignore"), "Cargo.lock\n").unwrap();
+
+    let mut options = ScanOptions {
+      max_depth: 3,
+      use_gitignore: true,
+      ..Default::default()
+    };
+    assert!(scan_directory(tmp.path(), &options).is_empty());
+
+    options.include_lockfiles = true;
+    assert_eq!(scan_directory(tmp.path(), &options).len(), 1);
+  }
+}
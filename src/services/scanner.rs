@@ -1,5 +1,10 @@
-use crate::core::constants::CLEANABLE_PATTERNS;
+use crate::core::constants::{CLEANABLE_PATTERNS, LOCKFILE_PATTERNS, PYTHON_ENV_PATTERNS};
 use crate::core::models::CleanableItem;
+use crate::services::exclude::Exclusions;
+use crate::services::orphans::missing_manifests;
+use crate::services::tombstone::is_tombstone;
+use crate::services::venv::analyze_venv;
+use anyhow::Result;
 use ignore::WalkBuilder;
 use std::collections::HashSet;
 use std::fs::File;
@@ -9,15 +14,153 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use walkdir::WalkDir;
 
-pub fn scan_directory(dir: &Path, use_gitignore: bool, max_depth: usize) -> Vec<CleanableItem> {
-  if use_gitignore {
-    scan_gitignore_items(dir, max_depth)
+/// What a project scan looks for
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+  pub use_gitignore: bool,
+  pub max_depth: usize,
+  /// Also list lockfiles of projects whose manifest still exists
+  pub include_lockfiles: bool,
+  /// `--exclude` and config patterns: absolute paths or gitignore-style globs
+  pub exclude: Vec<String>,
+  /// Paths from the config file that are never scanned or deleted
+  pub protected: Vec<PathBuf>,
+}
+
+pub fn scan_directory(dir: &Path, options: &ScanOptions) -> Vec<CleanableItem> {
+  let exclusions = Arc::new(Exclusions::new(dir, options));
+  if options.use_gitignore {
+    scan_gitignore_items(
+      dir,
+      options.max_depth,
+      options.include_lockfiles,
+      exclusions,
+    )
   } else {
-    scan_cleanable_items(dir, max_depth)
+    scan_cleanable_items(
+      dir,
+      options.max_depth,
+      options.include_lockfiles,
+      &exclusions,
+    )
   }
 }
 
-fn scan_cleanable_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
+/// Scan several roots concurrently. Overlapping roots are scanned once and every
+/// item records the root it was found under.
+pub fn scan_roots(roots: &[PathBuf], options: &ScanOptions) -> Vec<CleanableItem> {
+  let roots = dedupe_roots(roots);
+
+  let mut items: Vec<CleanableItem> = thread::scope(|scope| {
+    let handles: Vec<_> = roots
+      .iter()
+      .map(|root| {
+        scope.spawn(move || {
+          let mut items = scan_directory(root, options);
+          for item in &mut items {
+            item.root = Some(root.clone());
+          }
+          items
+        })
+      })
+      .collect();
+
+    handles
+      .into_iter()
+      .flat_map(|handle| handle.join().unwrap_or_default())
+      .collect()
+  });
+
+  let mut seen = HashSet::new();
+  items.retain(|item| seen.insert(item.path.clone()));
+  items
+}
+
+/// Scan `roots` and measure every directory, largest first; for the headless commands
+pub fn scan_with_sizes(roots: &[PathBuf], options: &ScanOptions) -> Vec<CleanableItem> {
+  let mut items = scan_roots(roots, options);
+  for item in &mut items {
+    if item.size == 0 && item.path.is_dir() {
+      item.size = get_directory_size(&item.path);
+    }
+  }
+  items.sort_by_key(|item| std::cmp::Reverse(item.size));
+  items
+}
+
+/// Print the cleanable items under `roots`, grouped by root (for `dd --list`)
+pub fn print_scan(roots: &[PathBuf], options: &ScanOptions) -> Result<()> {
+  let items = scan_with_sizes(roots, options);
+
+  for root in dedupe_roots(roots) {
+    let in_root: Vec<&CleanableItem> = items
+      .iter()
+      .filter(|item| item.root.as_ref() == Some(&root))
+      .collect();
+    let total: u64 = in_root.iter().map(|item| item.size).sum();
+
+    println!(
+      "{} ({} items, {})",
+      root.display(),
+      in_root.len(),
+      human_bytes::human_bytes(total as f64)
+    );
+    for item in in_root {
+      let path = item.path.strip_prefix(&root).unwrap_or(&item.path);
+      let badge = if item.orphaned { " [orphaned]" } else { "" };
+      println!(
+        "{:>10}  {}{}  {}",
+        item.display_size(),
+        path.display(),
+        badge,
+        item.display_info()
+      );
+    }
+    println!();
+  }
+
+  let total: u64 = items.iter().map(|item| item.size).sum();
+  println!("Total: {}", human_bytes::human_bytes(total as f64));
+  Ok(())
+}
+
+/// Canonicalize roots and drop the ones nested inside (or equal to) another root
+pub fn dedupe_roots(roots: &[PathBuf]) -> Vec<PathBuf> {
+  let mut roots: Vec<PathBuf> = roots
+    .iter()
+    .map(|root| root.canonicalize().unwrap_or_else(|_| root.clone()))
+    .collect();
+  roots.sort();
+  roots.dedup();
+
+  let mut kept: Vec<PathBuf> = Vec::new();
+  for root in roots {
+    if !kept.iter().any(|parent| root.starts_with(parent)) {
+      kept.push(root);
+    }
+  }
+  kept
+}
+
+/// Drop items that lie inside another of the items (`target/debug` when
+/// `target` is listed), so sizes are not counted twice
+pub fn outermost_items<'a>(
+  items: impl IntoIterator<Item = &'a CleanableItem>,
+) -> Vec<&'a CleanableItem> {
+  let items: Vec<&CleanableItem> = items.into_iter().collect();
+  let paths: HashSet<&Path> = items.iter().map(|item| item.path.as_path()).collect();
+  items
+    .into_iter()
+    .filter(|item| !item.path.ancestors().skip(1).any(|a| paths.contains(a)))
+    .collect()
+}
+
+fn scan_cleanable_items(
+  dir: &Path,
+  max_depth: usize,
+  include_lockfiles: bool,
+  exclusions: &Exclusions,
+) -> Vec<CleanableItem> {
   let items = Arc::new(Mutex::new(Vec::new()));
   let thread_count = num_cpus::get().max(2);
 
@@ -26,8 +169,8 @@ fn scan_cleanable_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
     .max_depth(max_depth)
     .into_iter()
     .filter_entry(|e| {
-      let name = e.file_name().to_string_lossy();
-      !(name.starts_with(".") && name != ".git")
+      is_scanned_name(&e.file_name().to_string_lossy())
+        && !exclusions.is_excluded(e.path(), e.file_type().is_dir())
     });
 
   let entries: Vec<_> = walker.filter_map(Result::ok).collect();
@@ -41,31 +184,13 @@ fn scan_cleanable_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
       let mut local_items = Vec::new();
 
       for entry in chunk {
-        let name = entry.file_name().to_string_lossy();
-        let path = entry.path().to_path_buf();
-
-        for (pattern, description) in CLEANABLE_PATTERNS.iter() {
-          let pattern_matches = if pattern.contains('*') {
-            match_glob_pattern(pattern, &name)
-          } else {
-            &name == pattern
-          };
-
-          if pattern_matches {
-            let size = if entry.file_type().is_file() {
-              entry.metadata().map(|m| m.len()).unwrap_or(0)
-            } else {
-              0
-            };
-
-            local_items.push(CleanableItem::new(
-              path.clone(),
-              description.to_string(),
-              size,
-              description.to_string(),
-            ));
-            break;
-          }
+        let size = if entry.file_type().is_file() {
+          entry.metadata().map(|m| m.len()).unwrap_or(0)
+        } else {
+          0
+        };
+        if let Some(item) = match_entry(entry.path(), size, include_lockfiles) {
+          local_items.push(item);
         }
       }
 
@@ -84,17 +209,74 @@ fn scan_cleanable_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
   items
 }
 
-fn scan_gitignore_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
-  let gitignore_path = dir.join(".gitignore");
-  if !gitignore_path.exists() {
-    return Vec::new();
-  }
+/// Build the item the scanner reports for `path`, if it matches a cleanable
+/// pattern. `size` is the file size, or 0 for directories (measured later).
+pub fn match_entry(path: &Path, size: u64, include_lockfiles: bool) -> Option<CleanableItem> {
+  let name = path.file_name()?.to_string_lossy();
 
-  let patterns = match read_gitignore(&gitignore_path) {
-    Ok(p) => p,
-    Err(_) => return Vec::new(),
+  let (pattern, description) = CLEANABLE_PATTERNS.iter().find(|(pattern, _)| {
+    if pattern.contains('*') {
+      match_glob_pattern(pattern, &name)
+    } else {
+      &name == *pattern
+    }
+  })?;
+
+  let mut item = if PYTHON_ENV_PATTERNS.contains(pattern) {
+    // Matched by name only; keep it only if it really is a virtual environment
+    let venv = analyze_venv(path)?;
+    let mut item = CleanableItem::new(
+      path.to_path_buf(),
+      description.to_string(),
+      0,
+      venv.describe(),
+    );
+    item.selected = venv.broken;
+    item
+  } else {
+    CleanableItem::new(
+      path.to_path_buf(),
+      description.to_string(),
+      size,
+      description.to_string(),
+    )
   };
 
+  if let Some(manifests) = missing_manifests(path) {
+    item.orphaned = true;
+    item.info = format!(
+      "{} (orphaned: no {} next to it)",
+      item.info,
+      manifests.join(" or ")
+    );
+  } else if LOCKFILE_PATTERNS.contains(pattern) && !include_lockfiles {
+    // A lockfile of a live project is not an artifact
+    return None;
+  }
+
+  Some(item)
+}
+
+/// A lockfile whose manifest still sits next to it
+pub fn is_live_lockfile(path: &Path) -> bool {
+  path
+    .file_name()
+    .is_some_and(|name| LOCKFILE_PATTERNS.contains(&name.to_string_lossy().as_ref()))
+    && missing_manifests(path).is_none()
+}
+
+/// Whether the scanner walks into (and reports) an entry with this name
+pub fn is_scanned_name(name: &str) -> bool {
+  !name.starts_with('.') || PYTHON_ENV_PATTERNS.contains(&name)
+}
+
+fn scan_gitignore_items(
+  dir: &Path,
+  max_depth: usize,
+  include_lockfiles: bool,
+  exclusions: Arc<Exclusions>,
+) -> Vec<CleanableItem> {
+  let patterns = gitignore_patterns(dir);
   if patterns.is_empty() {
     return Vec::new();
   }
@@ -107,6 +289,10 @@ fn scan_gitignore_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
     .ignore(false)
     .git_ignore(false)
     .max_depth(Some(max_depth))
+    .filter_entry(move |e| {
+      let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+      e.file_name() != ".git" && !exclusions.is_excluded(e.path(), is_dir)
+    })
     .build();
 
   let thread_count = num_cpus::get().max(2);
@@ -125,33 +311,27 @@ fn scan_gitignore_items(dir: &Path, max_depth: usize) -> Vec<CleanableItem> {
       let mut local_items = Vec::new();
 
       for entry in chunk {
+        if is_tombstone(entry.file_name()) {
+          continue;
+        }
+        if !include_lockfiles && is_live_lockfile(entry.path()) {
+          continue;
+        }
+
         let path = entry.path().to_path_buf();
-        let rel_path = path.strip_prefix(&dir).unwrap_or(&path);
-        let rel_path_str = rel_path.to_string_lossy().to_string();
-
-        for pattern in &patterns_clone {
-          if matches_gitignore_pattern(pattern, &rel_path_str) {
-            let mut seen = seen_paths_clone.lock().unwrap();
-            if !seen.contains(&path) {
-              seen.insert(path.clone());
-
-              let size = if entry.path().is_file() {
-                match std::fs::metadata(entry.path()) {
-                  Ok(metadata) => metadata.len(),
-                  Err(_) => 0,
-                }
-              } else {
-                0
-              };
-
-              local_items.push(CleanableItem::new(
-                path.clone(),
-                format!("Gitignore pattern: {}", pattern),
-                size,
-                "Matches .gitignore pattern".to_string(),
-              ));
-            }
-            break;
+        let size = if entry.path().is_file() {
+          match std::fs::metadata(entry.path()) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+          }
+        } else {
+          0
+        };
+
+        if let Some(item) = match_gitignore_entry(&dir, &patterns_clone, &path, size) {
+          let mut seen = seen_paths_clone.lock().unwrap();
+          if seen.insert(path) {
+            local_items.push(item);
           }
         }
       }
@@ -198,7 +378,7 @@ pub fn calculate_directory_sizes(
       for i in chunk_start..chunk_end {
         let item = &items_chunk[i];
         let size = get_directory_size(&item.path);
-        if let Err(_) = sender.send((item.path.clone(), size)).await {
+        if sender.send((item.path.clone(), size)).await.is_err() {
           break;
         }
       }
@@ -206,7 +386,7 @@ pub fn calculate_directory_sizes(
   }
 }
 
-fn get_directory_size(path: &Path) -> u64 {
+pub fn get_directory_size(path: &Path) -> u64 {
   WalkDir::new(path)
     .into_iter()
     .filter_map(|e| e.ok())
@@ -228,6 +408,32 @@ fn match_glob_pattern(pattern: &str, name: &str) -> bool {
   }
 }
 
+/// Patterns of the `.gitignore` at the top of `dir`, for `--gitignore` scans
+pub fn gitignore_patterns(dir: &Path) -> Vec<String> {
+  read_gitignore(&dir.join(".gitignore")).unwrap_or_default()
+}
+
+/// Build the item a `--gitignore` scan of `dir` reports for `path`, if it
+/// matches one of `patterns`
+pub fn match_gitignore_entry(
+  dir: &Path,
+  patterns: &[String],
+  path: &Path,
+  size: u64,
+) -> Option<CleanableItem> {
+  let rel_path = path.strip_prefix(dir).unwrap_or(path).to_string_lossy();
+  let pattern = patterns
+    .iter()
+    .find(|pattern| matches_gitignore_pattern(pattern, &rel_path))?;
+
+  Some(CleanableItem::new(
+    path.to_path_buf(),
+    format!("Gitignore pattern: {}", pattern),
+    size,
+    "Matches .gitignore pattern".to_string(),
+  ))
+}
+
 fn matches_gitignore_pattern(pattern: &str, path: &str) -> bool {
   if pattern.ends_with('/') {
     let pattern = pattern.trim_end_matches('/');
@@ -283,3 +489,67 @@ fn split_into_chunks<T: Clone>(items: Vec<T>, chunk_count: usize) -> Vec<Vec<T>>
 fn split_into_chunks_ignore<T: Clone>(items: Vec<T>, chunk_count: usize) -> Vec<Vec<T>> {
   split_into_chunks(items, chunk_count)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn nested_roots_are_scanned_once() {
+    let tmp = tempfile::tempdir().unwrap();
+    let work = tmp.path().join("work");
+    let oss = tmp.path().join("oss");
+    fs::create_dir_all(work.join("app/node_modules")).unwrap();
+    fs::create_dir_all(oss.join("lib/target")).unwrap();
+    fs::write(work.join("app/package.json"), "{}").unwrap();
+    fs::write(oss.join("lib/Cargo.toml"), "[package]").unwrap();
+
+    let roots = vec![work.clone(), oss.clone(), work.join("app"), work.clone()];
+    let deduped = dedupe_roots(&roots);
+    assert_eq!(deduped.len(), 2);
+
+    let options = ScanOptions {
+      max_depth: 4,
+      ..Default::default()
+    };
+    let items = scan_roots(&roots, &options);
+    let work = work.canonicalize().unwrap();
+    let oss = oss.canonicalize().unwrap();
+
+    assert_eq!(items.len(), 2);
+    let root_of = |name: &str| {
+      items
+        .iter()
+        .find(|item| item.path.ends_with(name))
+        .and_then(|item| item.root.clone())
+    };
+    assert_eq!(root_of("node_modules"), Some(work));
+    assert_eq!(root_of("target"), Some(oss));
+  }
+
+  #[test]
+  fn outermost_items_drop_nested_ones() {
+    let item =
+      |path: &str| CleanableItem::new(PathBuf::from(path), String::new(), 1, String::new());
+    let items = vec![
+      item("/w/app/target/debug"),
+      item("/w/app/target"),
+      item("/w/app/target/debug/deps"),
+      item("/w/app/targets"),
+    ];
+
+    let kept: Vec<_> = outermost_items(&items)
+      .iter()
+      .map(|item| item.path.clone())
+      .collect();
+
+    assert_eq!(
+      kept,
+      vec![
+        PathBuf::from("/w/app/target"),
+        PathBuf::from("/w/app/targets")
+      ]
+    );
+  }
+}
@@ -0,0 +1,208 @@
+use crate::services::cleaner::remove_path;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Prefix of the hidden names items are renamed to before deletion
+pub const TOMBSTONE_PREFIX: &str = ".devtidy-tombstone-";
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Leftover tombstones removed by [`recover`]
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+  pub recovered: usize,
+  pub freed: u64,
+  pub remaining: usize,
+}
+
+/// Check whether a file name belongs to a tombstone created by DevTidy
+pub fn is_tombstone(name: &OsStr) -> bool {
+  name.to_string_lossy().starts_with(TOMBSTONE_PREFIX)
+}
+
+/// Directory holding one record per tombstone that still has to be deleted
+fn records_dir() -> Option<PathBuf> {
+  dirs::data_local_dir().map(|dir| dir.join("devtidy").join("tombstones"))
+}
+
+/// Atomically move `path` out of the way by renaming it to a hidden tombstone in
+/// the same directory, and record it so an interrupted deletion can be finished
+/// on the next start.
+pub fn bury(path: &Path) -> io::Result<PathBuf> {
+  bury_in(path, records_dir().as_deref())
+}
+
+/// Forget a tombstone once it has been deleted
+pub fn release(tombstone: &Path) {
+  if let Some(dir) = records_dir() {
+    release_in(tombstone, &dir);
+  }
+}
+
+/// Finish deleting tombstones left behind by an interrupted run
+pub fn recover() -> RecoveryReport {
+  match records_dir() {
+    Some(dir) => recover_in(&dir),
+    None => RecoveryReport::default(),
+  }
+}
+
+fn bury_in(path: &Path, records: Option<&Path>) -> io::Result<PathBuf> {
+  let parent = path
+    .parent()
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no parent"))?;
+  let name = path
+    .file_name()
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos())
+    .unwrap_or_default();
+  let id = format!(
+    "{}-{}-{}",
+    std::process::id(),
+    nanos,
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+  );
+  let tombstone = parent.join(format!(
+    "{}{}-{}",
+    TOMBSTONE_PREFIX,
+    id,
+    name.to_string_lossy()
+  ));
+
+  // Write the record first so a crash right after the rename is still recoverable
+  let record = match records {
+    Some(dir) => {
+      fs::create_dir_all(dir)?;
+      let record = dir.join(&id);
+      fs::write(&record, tombstone.to_string_lossy().as_bytes())?;
+      Some(record)
+    }
+    None => None,
+  };
+
+  if let Err(e) = fs::rename(path, &tombstone) {
+    if let Some(record) = record {
+      let _ = fs::remove_file(record);
+    }
+    return Err(e);
+  }
+
+  Ok(tombstone)
+}
+
+fn release_in(tombstone: &Path, records: &Path) {
+  let Some(id) = tombstone_id(tombstone) else {
+    return;
+  };
+  let _ = fs::remove_file(records.join(id));
+}
+
+fn recover_in(records: &Path) -> RecoveryReport {
+  let mut report = RecoveryReport::default();
+  let Ok(entries) = fs::read_dir(records) else {
+    return report;
+  };
+
+  for entry in entries.filter_map(Result::ok) {
+    let record = entry.path();
+    let Ok(contents) = fs::read_to_string(&record) else {
+      continue;
+    };
+    let tombstone = PathBuf::from(contents.trim());
+
+    // Never follow a record outside of a tombstone name
+    if !tombstone.file_name().is_some_and(is_tombstone) {
+      let _ = fs::remove_file(&record);
+      continue;
+    }
+
+    let outcome = remove_path(&tombstone);
+    report.freed += outcome.freed;
+    if outcome.is_complete() && tombstone.symlink_metadata().is_err() {
+      report.recovered += 1;
+      let _ = fs::remove_file(&record);
+    } else {
+      report.remaining += 1;
+    }
+  }
+
+  report
+}
+
+/// The unique id embedded in a tombstone name, which is also its record name
+fn tombstone_id(tombstone: &Path) -> Option<String> {
+  let name = tombstone.file_name()?.to_string_lossy().into_owned();
+  let rest = name.strip_prefix(TOMBSTONE_PREFIX)?;
+  let mut parts = rest.splitn(4, '-');
+  let pid = parts.next()?;
+  let nanos = parts.next()?;
+  let counter = parts.next()?;
+  Some(format!("{}-{}-{}", pid, nanos, counter))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bury_renames_in_place_and_records() {
+    let tmp = tempfile::tempdir().unwrap();
+    let records = tmp.path().join("records");
+    let project = tmp.path().join("project");
+    let target = project.join("node_modules");
+    fs::create_dir_all(target.join("left-pad")).unwrap();
+    fs::write(target.join("left-pad/index.js"), "module.exports = 1").unwrap();
+
+    let tombstone = bury_in(&target, Some(&records)).unwrap();
+
+    assert!(!target.exists());
+    assert!(tombstone.exists());
+    assert_eq!(tombstone.parent(), Some(project.as_path()));
+    assert!(is_tombstone(tombstone.file_name().unwrap()));
+    assert_eq!(fs::read_dir(&records).unwrap().count(), 1);
+
+    release_in(&tombstone, &records);
+    assert_eq!(fs::read_dir(&records).unwrap().count(), 0);
+  }
+
+  #[test]
+  fn recover_finishes_interrupted_deletions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let records = tmp.path().join("records");
+    let target = tmp.path().join("target");
+    fs::create_dir_all(target.join("debug")).unwrap();
+    fs::write(target.join("debug/app"), vec![0u8; 128]).unwrap();
+
+    let tombstone = bury_in(&target, Some(&records)).unwrap();
+    let report = recover_in(&records);
+
+    assert_eq!(report.recovered, 1);
+    assert_eq!(report.remaining, 0);
+    assert_eq!(report.freed, 128);
+    assert!(!tombstone.exists());
+    assert_eq!(fs::read_dir(&records).unwrap().count(), 0);
+  }
+
+  #[test]
+  fn recover_ignores_records_pointing_elsewhere() {
+    let tmp = tempfile::tempdir().unwrap();
+    let records = tmp.path().join("records");
+    let precious = tmp.path().join("src");
+    fs::create_dir_all(&records).unwrap();
+    fs::create_dir_all(&precious).unwrap();
+    fs::write(records.join("1-2-3"), precious.to_string_lossy().as_bytes()).unwrap();
+
+    let report = recover_in(&records);
+
+    assert_eq!(report.recovered, 0);
+    assert!(precious.exists());
+    assert_eq!(fs::read_dir(&records).unwrap().count(), 0);
+  }
+}
@@ -0,0 +1,793 @@
+use crate::core::constants::{
+  ToolchainManager, CLEANABLE_PATTERNS, TOOLCHAIN_MANAGERS, TOOLCHAIN_PIN_FILES,
+};
+use crate::core::models::CleanableItem;
+use crate::services::cleaner::{clean_headless, CleanOptions};
+use crate::services::scanner::get_directory_size;
+use anyhow::Result;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// Binaries whose resolved location tells which toolchain is currently active.
+/// rustup and pyenv put proxies and shims on the PATH instead, so they are
+/// asked with `ACTIVE_QUERIES`.
+const ACTIVE_BINARIES: &[&str] = &["node", "java"];
+
+/// Commands printing the active version of a manager, as `(manager, command)`
+const ACTIVE_QUERIES: &[(&str, &[&str])] = &[
+  ("rustup", &["rustup", "show", "active-toolchain"]),
+  ("pyenv", &["pyenv", "version-name"]),
+];
+
+/// An installed toolchain version
+struct Toolchain {
+  manager: &'static ToolchainManager,
+  /// Version directory name; `candidate/version` for SDKMAN
+  version: String,
+  path: PathBuf,
+  last_used: Option<SystemTime>,
+  /// Why this toolchain must be kept, if it must
+  protected: Option<String>,
+}
+
+impl Toolchain {
+  fn into_item(self) -> CleanableItem {
+    let mut info = format!(
+      "{}, {}",
+      self.manager.description,
+      last_used_hint(self.last_used)
+    );
+    if let Some(reason) = &self.protected {
+      info.push_str(&format!(" (protected: {})", reason));
+    }
+
+    let mut item = CleanableItem::new(
+      self.path,
+      format!("{} {}", self.manager.name, self.version),
+      0,
+      info,
+    );
+    item.protected = self.protected.is_some();
+    item
+  }
+}
+
+/// What is in use right now and must not be pruned
+#[derive(Default)]
+struct Active {
+  /// Resolved locations of `ACTIVE_BINARIES`
+  binaries: Vec<PathBuf>,
+  /// Versions the managers report as active, as `(manager, version)`
+  versions: Vec<(&'static str, String)>,
+}
+
+impl Active {
+  fn detect() -> Self {
+    let binaries = ACTIVE_BINARIES
+      .iter()
+      .filter_map(|binary| which::which(binary).ok())
+      .filter_map(|path| path.canonicalize().ok())
+      .collect();
+
+    let mut versions = Vec::new();
+    for (manager, command) in ACTIVE_QUERIES {
+      let Ok(output) = Command::new(command[0]).args(&command[1..]).output() else {
+        continue;
+      };
+      if output.status.success() {
+        let output = String::from_utf8_lossy(&output.stdout);
+        for version in parse_active_versions(&output) {
+          versions.push((*manager, version));
+        }
+      }
+    }
+
+    Self { binaries, versions }
+  }
+}
+
+/// Versions in the output of an `ACTIVE_QUERIES` command: the first word,
+/// which pyenv makes a `:`-separated list (`3.12.1:3.11.7`) and rustup follows
+/// with the reason (`stable-x86_64-unknown-linux-gnu (default)`)
+fn parse_active_versions(output: &str) -> Vec<String> {
+  output
+    .split_whitespace()
+    .next()
+    .map(|word| {
+      word
+        .split(':')
+        .filter(|version| !version.is_empty())
+        .map(str::to_string)
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// A toolchain version requested by a file inside a project
+struct Pin {
+  manager: &'static str,
+  version: String,
+  source: PathBuf,
+}
+
+/// Find installed rustup, nvm, pyenv and SDKMAN toolchains. Defaults, overrides,
+/// the active toolchains and versions pinned by projects under `roots` are
+/// protected.
+pub fn discover_toolchains(roots: &[PathBuf], max_depth: usize) -> Vec<CleanableItem> {
+  let active = Active::detect();
+
+  discover_in(
+    dirs::home_dir().as_deref(),
+    |var| std::env::var_os(var),
+    &roots
+      .iter()
+      .flat_map(|root| collect_pins(root, max_depth))
+      .collect::<Vec<_>>(),
+    &active,
+  )
+  .into_iter()
+  .map(Toolchain::into_item)
+  .collect()
+}
+
+/// Print installed toolchains with sizes and protection (for `dd toolchains --list`)
+pub fn print_toolchains(roots: &[PathBuf], max_depth: usize) -> Result<()> {
+  let items = sized(discover_toolchains(roots, max_depth));
+  if items.is_empty() {
+    println!("No toolchains installed with rustup, nvm, pyenv or SDKMAN.");
+    return Ok(());
+  }
+
+  for item in &items {
+    let marker = if item.protected { "*" } else { " " };
+    println!(
+      "{:>10} {} {} ({})",
+      item.display_size(),
+      marker,
+      item.item_type,
+      item.path.display()
+    );
+    println!("             {}", item.info);
+  }
+
+  let removable: u64 = items
+    .iter()
+    .filter(|item| !item.protected)
+    .map(|item| item.size)
+    .sum();
+  println!(
+    "\n* protected. Removable: {}",
+    human_bytes::human_bytes(removable as f64)
+  );
+  println!("Run 'dd toolchains --prune' to remove the unprotected toolchains.");
+  Ok(())
+}
+
+/// Remove every unprotected toolchain (for `dd toolchains --prune`)
+pub async fn prune_toolchains(
+  roots: &[PathBuf],
+  max_depth: usize,
+  jobs: usize,
+  yes: bool,
+) -> Result<()> {
+  let items: Vec<CleanableItem> = sized(discover_toolchains(roots, max_depth))
+    .into_iter()
+    .filter(|item| !item.protected)
+    .collect();
+  if items.is_empty() {
+    println!("Nothing to prune: every installed toolchain is protected.");
+    return Ok(());
+  }
+
+  let total: u64 = items.iter().map(|item| item.size).sum();
+  for item in &items {
+    println!("{:>10}  {}", item.display_size(), item.item_type);
+  }
+
+  if !yes {
+    print!(
+      "Remove {} toolchains ({})? [y/N] ",
+      items.len(),
+      human_bytes::human_bytes(total as f64)
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+      println!("Aborted.");
+      return Ok(());
+    }
+  }
+
+  let options = CleanOptions {
+    concurrency: jobs,
+    native: false,
+  };
+  let (freed, failed) = clean_headless(&items, options).await;
+
+  println!("\nFreed {}", human_bytes::human_bytes(freed as f64));
+  if failed > 0 {
+    anyhow::bail!("{} toolchains could not be removed", failed);
+  }
+  Ok(())
+}
+
+fn sized(mut items: Vec<CleanableItem>) -> Vec<CleanableItem> {
+  for item in &mut items {
+    item.size = get_directory_size(&item.path);
+  }
+  items.sort_by_key(|item| std::cmp::Reverse(item.size));
+  items
+}
+
+fn discover_in(
+  home: Option<&Path>,
+  env: impl Fn(&str) -> Option<OsString>,
+  pins: &[Pin],
+  active: &Active,
+) -> Vec<Toolchain> {
+  let mut toolchains = Vec::new();
+
+  for manager in TOOLCHAIN_MANAGERS.iter() {
+    let manager_home = match env(manager.env_var) {
+      Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+      _ => match home {
+        Some(home) => home.join(manager.home),
+        None => continue,
+      },
+    };
+
+    let installed = installed_versions(manager, &manager_home.join(manager.versions));
+    if installed.is_empty() {
+      continue;
+    }
+
+    let names: Vec<&str> = installed.iter().map(|(name, _)| name.as_str()).collect();
+    let defaults = default_versions(manager, &manager_home);
+    let overrides = override_versions(manager, &manager_home, &env);
+    let matches =
+      |version: &str, pin: &str| pin_matches(manager, &manager_home, &names, version, pin);
+
+    for (version, path) in &installed {
+      let is_default = defaults.iter().any(|pin| matches(version, pin));
+      let overridden_by = overrides.iter().find(|(pin, _)| matches(version, pin));
+      let is_active = active
+        .binaries
+        .iter()
+        .any(|binary| binary.starts_with(path))
+        || active
+          .versions
+          .iter()
+          .any(|(name, pin)| *name == manager.name && matches(version, pin));
+      let pinned_by = pins
+        .iter()
+        .filter(|pin| pin.manager == manager.name)
+        .find(|pin| pin_matches(manager, &manager_home, &names, version, &pin.version));
+
+      let protected = if is_default {
+        Some("default toolchain".to_string())
+      } else if let Some((_, reason)) = overridden_by {
+        Some(reason.clone())
+      } else if is_active {
+        Some("currently active".to_string())
+      } else {
+        pinned_by.map(|pin| format!("pinned by {}", pin.source.display()))
+      };
+
+      toolchains.push(Toolchain {
+        manager,
+        version: version.clone(),
+        path: path.clone(),
+        last_used: last_used(path),
+        protected,
+      });
+    }
+  }
+
+  toolchains
+}
+
+/// Installed version directories, as `(version, path)`
+fn installed_versions(manager: &ToolchainManager, dir: &Path) -> Vec<(String, PathBuf)> {
+  let mut versions = Vec::new();
+
+  for (name, path) in subdirectories(dir) {
+    if manager.grouped {
+      for (version, path) in subdirectories(&path) {
+        if version != "current" {
+          versions.push((format!("{}/{}", name, version), path));
+        }
+      }
+    } else {
+      versions.push((name, path));
+    }
+  }
+
+  versions.sort();
+  versions
+}
+
+/// Real (non-symlink) subdirectories of `dir`
+fn subdirectories(dir: &Path) -> Vec<(String, PathBuf)> {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+
+  entries
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+    .map(|entry| {
+      (
+        entry.file_name().to_string_lossy().to_string(),
+        entry.path(),
+      )
+    })
+    .collect()
+}
+
+/// The versions a manager uses when no project pins one
+fn default_versions(manager: &ToolchainManager, home: &Path) -> Vec<String> {
+  match manager.name {
+    "rustup" => fs::read_to_string(home.join("settings.toml"))
+      .ok()
+      .and_then(|settings| toml_string(&settings, &["default_toolchain"]))
+      .into_iter()
+      .collect(),
+    "nvm" => fs::read_to_string(home.join("alias/default"))
+      .map(|alias| first_line(&alias).into_iter().collect())
+      .unwrap_or_default(),
+    "pyenv" => fs::read_to_string(home.join("version"))
+      .map(|versions| meaningful_lines(&versions).collect())
+      .unwrap_or_default(),
+    "sdkman" => subdirectories(&home.join(manager.versions))
+      .into_iter()
+      .filter_map(|(candidate, path)| {
+        let target = fs::read_link(path.join("current")).ok()?;
+        let version = target.file_name()?.to_string_lossy().to_string();
+        Some(format!("{}/{}", candidate, version))
+      })
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// Versions selected by directory overrides and environment variables, as
+/// `(version, reason)`
+fn override_versions(
+  manager: &ToolchainManager,
+  home: &Path,
+  env: impl Fn(&str) -> Option<OsString>,
+) -> Vec<(String, String)> {
+  let from_env = |var: &str| -> Vec<(String, String)> {
+    env(var)
+      .map(|value| {
+        value
+          .to_string_lossy()
+          .split(':')
+          .map(str::trim)
+          .filter(|version| !version.is_empty())
+          .map(|version| (version.to_string(), format!("set by {}", var)))
+          .collect()
+      })
+      .unwrap_or_default()
+  };
+
+  match manager.name {
+    "rustup" => {
+      let mut versions = from_env("RUSTUP_TOOLCHAIN");
+      let settings = fs::read_to_string(home.join("settings.toml")).unwrap_or_default();
+      if let Ok(settings) = toml::from_str::<toml::Table>(&settings) {
+        if let Some(overrides) = settings.get("overrides").and_then(|o| o.as_table()) {
+          for (dir, toolchain) in overrides {
+            if let Some(toolchain) = toolchain.as_str() {
+              versions.push((toolchain.to_string(), format!("override for {}", dir)));
+            }
+          }
+        }
+      }
+      versions
+    }
+    "pyenv" => from_env("PYENV_VERSION"),
+    _ => Vec::new(),
+  }
+}
+
+/// Whether the installed `version` satisfies `pin` (`18` matches `v18.17.0`,
+/// `stable` matches `stable-x86_64-unknown-linux-gnu`, `1.70` matches
+/// `1.70.0-x86_64-unknown-linux-gnu`)
+fn pin_matches(
+  manager: &ToolchainManager,
+  home: &Path,
+  installed: &[&str],
+  version: &str,
+  pin: &str,
+) -> bool {
+  match manager.name {
+    "rustup" => version
+      .strip_prefix(pin)
+      .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '.'])),
+    "nvm" => {
+      let pin = resolve_nvm_alias(home, pin);
+      if matches!(pin.as_str(), "node" | "stable") {
+        return newest(installed) == Some(version);
+      }
+      let version = version.trim_start_matches('v');
+      let pin = pin.trim_start_matches('v');
+      version == pin || version.starts_with(&format!("{}.", pin))
+    }
+    "pyenv" => version == pin || version.starts_with(&format!("{}.", pin)),
+    _ => version == pin,
+  }
+}
+
+/// Follow nvm alias files (`lts/*` -> `lts/iron` -> `v20.11.0`)
+fn resolve_nvm_alias(home: &Path, pin: &str) -> String {
+  let mut pin = pin.to_string();
+  for _ in 0..8 {
+    let Some(next) = fs::read_to_string(home.join("alias").join(&pin))
+      .ok()
+      .and_then(|alias| first_line(&alias))
+    else {
+      break;
+    };
+    pin = next;
+  }
+  pin
+}
+
+fn newest<'a>(versions: &[&'a str]) -> Option<&'a str> {
+  versions.iter().copied().max_by_key(|version| {
+    version
+      .trim_start_matches('v')
+      .split('.')
+      .map(|part| part.parse::<u64>().unwrap_or(0))
+      .collect::<Vec<_>>()
+  })
+}
+
+/// Look for toolchain pin files in the ancestors of `root` and in the projects below it
+fn collect_pins(root: &Path, max_depth: usize) -> Vec<Pin> {
+  let mut files: Vec<PathBuf> = root
+    .ancestors()
+    .skip(1)
+    .flat_map(|dir| {
+      TOOLCHAIN_PIN_FILES
+        .iter()
+        .map(move |(name, _)| dir.join(name))
+    })
+    .filter(|path| path.is_file())
+    .collect();
+
+  let walker = WalkDir::new(root)
+    .max_depth(max_depth)
+    .into_iter()
+    .filter_entry(|e| {
+      if e.depth() == 0 || !e.file_type().is_dir() {
+        return true;
+      }
+      let name = e.file_name().to_string_lossy();
+      !name.starts_with('.') && !CLEANABLE_PATTERNS.contains_key(name.as_ref())
+    });
+  files.extend(
+    walker
+      .filter_map(Result::ok)
+      .filter(|entry| entry.file_type().is_file())
+      .map(|entry| entry.into_path()),
+  );
+
+  let mut pins = Vec::new();
+  for file in files {
+    let Some(name) = file
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+    else {
+      continue;
+    };
+    let Some((_, manager)) = TOOLCHAIN_PIN_FILES.iter().find(|(pin, _)| *pin == name) else {
+      continue;
+    };
+    let Ok(contents) = fs::read_to_string(&file) else {
+      continue;
+    };
+
+    for version in parse_pin_file(&name, &contents) {
+      pins.push(Pin {
+        manager,
+        version,
+        source: file.clone(),
+      });
+    }
+  }
+
+  pins
+}
+
+fn parse_pin_file(name: &str, contents: &str) -> Vec<String> {
+  match name {
+    "rust-toolchain.toml" | "rust-toolchain" => toml_string(contents, &["toolchain", "channel"])
+      .or_else(|| first_line(contents).filter(|line| !line.starts_with('[')))
+      .into_iter()
+      .collect(),
+    ".python-version" => meaningful_lines(contents).collect(),
+    ".sdkmanrc" => meaningful_lines(contents)
+      .filter_map(|line| {
+        let (candidate, version) = line.split_once('=')?;
+        Some(format!("{}/{}", candidate.trim(), version.trim()))
+      })
+      .collect(),
+    _ => first_line(contents).into_iter().collect(),
+  }
+}
+
+/// String value at `keys` (`["toolchain", "channel"]`) in a TOML document
+fn toml_string(contents: &str, keys: &[&str]) -> Option<String> {
+  let table: toml::Table = toml::from_str(contents).ok()?;
+  let (last, tables) = keys.split_last()?;
+  let table = tables
+    .iter()
+    .try_fold(&table, |table, key| table.get(*key)?.as_table())?;
+  table.get(*last)?.as_str().map(str::to_string)
+}
+
+fn meaningful_lines(contents: &str) -> impl Iterator<Item = String> + '_ {
+  contents
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(str::to_string)
+}
+
+fn first_line(contents: &str) -> Option<String> {
+  meaningful_lines(contents).next()
+}
+
+/// Most recent access of anything in the toolchain's `bin` directory. Access times
+/// are only a hint: many filesystems update them lazily or not at all.
+fn last_used(path: &Path) -> Option<SystemTime> {
+  let from_bin = fs::read_dir(path.join("bin")).ok().and_then(|entries| {
+    entries
+      .filter_map(Result::ok)
+      .filter_map(|entry| entry.metadata().ok()?.accessed().ok())
+      .max()
+  });
+
+  from_bin.or_else(|| fs::metadata(path).ok()?.modified().ok())
+}
+
+fn last_used_hint(time: Option<SystemTime>) -> String {
+  let Some(age) = time.and_then(|time| SystemTime::now().duration_since(time).ok()) else {
+    return "last use unknown".to_string();
+  };
+  format!("last used {}", format_age(age))
+}
+
+/// How long ago, roughly: "today", "3 days ago", "2 years ago"
+pub fn format_age(age: Duration) -> String {
+  let days = age.as_secs() / 86_400;
+  match days {
+    0 => "today".to_string(),
+    1 => "yesterday".to_string(),
+    2..=59 => format!("{} days ago", days),
+    60..=729 => format!("{} months ago", days / 30),
+    _ => format!("{} years ago", days / 365),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn find<'a>(toolchains: &'a [Toolchain], version: &str) -> &'a Toolchain {
+    toolchains
+      .iter()
+      .find(|toolchain| toolchain.version == version)
+      .unwrap()
+  }
+
+  #[test]
+  fn protects_defaults_and_pinned_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = tmp.path().join("home");
+    let rustup = home.join(".rustup");
+    for toolchain in [
+      "stable-x86_64-unknown-linux-gnu",
+      "1.70.0-x86_64-unknown-linux-gnu",
+      "nightly-2023-01-01-x86_64-unknown-linux-gnu",
+    ] {
+      fs::create_dir_all(rustup.join("toolchains").join(toolchain)).unwrap();
+    }
+    fs::write(
+      rustup.join("settings.toml"),
+      "default_toolchain = \"stable-x86_64-unknown-linux-gnu\"\n",
+    )
+    .unwrap();
+
+    let nvm = home.join(".nvm");
+    for version in ["v16.20.0", "v18.17.0", "v20.11.0"] {
+      fs::create_dir_all(nvm.join("versions/node").join(version)).unwrap();
+    }
+    fs::create_dir_all(nvm.join("alias/lts")).unwrap();
+    fs::write(nvm.join("alias/default"), "lts/*\n").unwrap();
+    fs::write(nvm.join("alias/lts/*"), "lts/iron\n").unwrap();
+    fs::write(nvm.join("alias/lts/iron"), "v20.11.0\n").unwrap();
+
+    let project = tmp.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join(".nvmrc"), "18\n").unwrap();
+    fs::write(
+      project.join("rust-toolchain.toml"),
+      "[toolchain]\nchannel = \"1.70\"\n",
+    )
+    .unwrap();
+
+    let pins = collect_pins(tmp.path(), 4);
+    let toolchains = discover_in(Some(&home), |_| None, &pins, &Active::default());
+
+    assert_eq!(toolchains.len(), 6);
+    assert_eq!(
+      find(&toolchains, "stable-x86_64-unknown-linux-gnu")
+        .protected
+        .as_deref(),
+      Some("default toolchain")
+    );
+    assert!(find(&toolchains, "1.70.0-x86_64-unknown-linux-gnu")
+      .protected
+      .as_deref()
+      .unwrap()
+      .contains("rust-toolchain.toml"));
+    assert!(
+      find(&toolchains, "nightly-2023-01-01-x86_64-unknown-linux-gnu")
+        .protected
+        .is_none()
+    );
+    assert!(find(&toolchains, "v16.20.0").protected.is_none());
+    assert!(find(&toolchains, "v18.17.0")
+      .protected
+      .as_deref()
+      .unwrap()
+      .contains(".nvmrc"));
+    assert_eq!(
+      find(&toolchains, "v20.11.0").protected.as_deref(),
+      Some("default toolchain")
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn sdkman_current_and_env_overrides() {
+    let tmp = tempfile::tempdir().unwrap();
+    let sdkman = tmp.path().join("custom-sdkman");
+    let java = sdkman.join("candidates/java");
+    fs::create_dir_all(java.join("11.0.20-tem")).unwrap();
+    fs::create_dir_all(java.join("17.0.8-tem")).unwrap();
+    std::os::unix::fs::symlink(java.join("17.0.8-tem"), java.join("current")).unwrap();
+
+    let env = |var: &str| (var == "SDKMAN_DIR").then(|| sdkman.clone().into_os_string());
+    let toolchains = discover_in(None, env, &[], &Active::default());
+
+    assert_eq!(toolchains.len(), 2);
+    assert!(find(&toolchains, "java/11.0.20-tem").protected.is_none());
+    assert!(find(&toolchains, "java/17.0.8-tem").protected.is_some());
+  }
+
+  #[test]
+  fn active_binaries_are_protected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let pyenv = tmp.path().join(".pyenv/versions");
+    fs::create_dir_all(pyenv.join("3.10.12/bin")).unwrap();
+    fs::create_dir_all(pyenv.join("3.12.1/bin")).unwrap();
+
+    let active = Active {
+      binaries: vec![pyenv.join("3.12.1/bin/python3.12")],
+      ..Default::default()
+    };
+    let toolchains = discover_in(Some(tmp.path()), |_| None, &[], &active);
+
+    assert!(find(&toolchains, "3.10.12").protected.is_none());
+    assert_eq!(
+      find(&toolchains, "3.12.1").protected.as_deref(),
+      Some("currently active")
+    );
+  }
+
+  #[test]
+  fn protects_versions_behind_proxies_and_shims() {
+    let tmp = tempfile::tempdir().unwrap();
+    let home = tmp.path();
+    // On the PATH are rustup's proxy and pyenv's shim, not the toolchains
+    fs::create_dir_all(home.join(".cargo/bin")).unwrap();
+    fs::write(home.join(".cargo/bin/rustc"), "").unwrap();
+    fs::create_dir_all(home.join(".pyenv/shims")).unwrap();
+    fs::write(home.join(".pyenv/shims/python"), "").unwrap();
+    let rustup = home.join(".rustup");
+    for toolchain in [
+      "stable-x86_64-unknown-linux-gnu",
+      "beta-x86_64-unknown-linux-gnu",
+      "nightly-x86_64-unknown-linux-gnu",
+      "1.70.0-x86_64-unknown-linux-gnu",
+    ] {
+      fs::create_dir_all(rustup.join("toolchains").join(toolchain)).unwrap();
+    }
+    fs::write(
+      rustup.join("settings.toml"),
+      "default_toolchain = \"stable-x86_64-unknown-linux-gnu\"\n\n[overrides]\n\"/work/legacy\" = \"1.70.0-x86_64-unknown-linux-gnu\"\n",
+    )
+    .unwrap();
+    for version in ["3.10.12", "3.11.7", "3.12.1"] {
+      fs::create_dir_all(home.join(".pyenv/versions").join(version)).unwrap();
+    }
+
+    let active = Active {
+      binaries: vec![
+        home.join(".cargo/bin/rustc"),
+        home.join(".pyenv/shims/python"),
+      ],
+      versions: parse_active_versions("3.12.1\n")
+        .into_iter()
+        .map(|version| ("pyenv", version))
+        .chain([("rustup", "beta-x86_64-unknown-linux-gnu".to_string())])
+        .collect(),
+    };
+    let env = |var: &str| match var {
+      "RUSTUP_TOOLCHAIN" => Some(OsString::from("nightly")),
+      "PYENV_VERSION" => Some(OsString::from("3.11")),
+      _ => None,
+    };
+    let toolchains = discover_in(Some(home), env, &[], &active);
+
+    let reason = |version: &str| find(&toolchains, version).protected.clone();
+    assert_eq!(
+      reason("1.70.0-x86_64-unknown-linux-gnu").as_deref(),
+      Some("override for /work/legacy")
+    );
+    assert_eq!(
+      reason("nightly-x86_64-unknown-linux-gnu").as_deref(),
+      Some("set by RUSTUP_TOOLCHAIN")
+    );
+    assert_eq!(
+      reason("beta-x86_64-unknown-linux-gnu").as_deref(),
+      Some("currently active")
+    );
+    assert_eq!(reason("3.11.7").as_deref(), Some("set by PYENV_VERSION"));
+    assert_eq!(reason("3.12.1").as_deref(), Some("currently active"));
+    assert_eq!(reason("3.10.12"), None);
+  }
+
+  #[test]
+  fn parses_active_versions() {
+    assert_eq!(
+      parse_active_versions("stable-x86_64-unknown-linux-gnu (default)\n"),
+      vec!["stable-x86_64-unknown-linux-gnu"]
+    );
+    assert_eq!(
+      parse_active_versions("3.12.1:3.11.7\n"),
+      vec!["3.12.1", "3.11.7"]
+    );
+    assert!(parse_active_versions("").is_empty());
+  }
+
+  #[test]
+  fn parses_pin_files() {
+    assert_eq!(parse_pin_file("rust-toolchain", "1.75.0\n"), vec!["1.75.0"]);
+    assert_eq!(
+      parse_pin_file(
+        "rust-toolchain.toml",
+        "[toolchain]\n# pinned\nchannel = \"1.70\" # msrv\ncomponents = [\"clippy\"]\n"
+      ),
+      vec!["1.70"]
+    );
+    assert_eq!(
+      parse_pin_file(".python-version", "3.11.4\n# comment\n3.10\n"),
+      vec!["3.11.4", "3.10"]
+    );
+    assert_eq!(
+      parse_pin_file(".sdkmanrc", "java=17.0.8-tem\n# x\ngradle = 8.5\n"),
+      vec!["java/17.0.8-tem", "gradle/8.5"]
+    );
+  }
+}
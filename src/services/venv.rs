@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `pyvenv.cfg` and the site-packages directory say about a virtual environment
+#[derive(Debug)]
+pub struct VenvInfo {
+  pub version: Option<String>,
+  /// Base interpreter the environment was created from
+  pub interpreter: Option<PathBuf>,
+  /// The base interpreter is gone, so the environment can no longer run
+  pub broken: bool,
+  pub packages: usize,
+}
+
+impl VenvInfo {
+  pub fn describe(&self) -> String {
+    if self.broken {
+      let interpreter = self
+        .interpreter
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "its interpreter".to_string());
+      return format!(
+        "Broken Python virtual environment: {} no longer exists",
+        interpreter
+      );
+    }
+
+    let packages = match self.packages {
+      1 => "1 package".to_string(),
+      n => format!("{} packages", n),
+    };
+    match &self.version {
+      Some(version) => format!("Python {} virtual environment, {}", version, packages),
+      None => format!("Python virtual environment, {}", packages),
+    }
+  }
+}
+
+/// Validate a `venv`/`.venv`/`env` candidate. Returns `None` when the directory
+/// has no `pyvenv.cfg` and is therefore not a virtual environment.
+pub fn analyze_venv(path: &Path) -> Option<VenvInfo> {
+  let config = fs::read_to_string(path.join("pyvenv.cfg")).ok()?;
+
+  let mut home = None;
+  let mut executable = None;
+  let mut version = None;
+  for line in config.lines() {
+    let Some((key, value)) = line.split_once('=') else {
+      continue;
+    };
+    let value = value.trim();
+    match key.trim() {
+      "home" => home = Some(PathBuf::from(value)),
+      "executable" | "base-executable" => executable = Some(PathBuf::from(value)),
+      "version" | "version_info" => version = Some(value.to_string()),
+      _ => {}
+    }
+  }
+
+  let interpreter = executable.or(home);
+  let base_missing = interpreter.as_ref().is_some_and(|path| !path.exists());
+
+  Some(VenvInfo {
+    version,
+    interpreter,
+    broken: base_missing || has_dangling_interpreter(path),
+    packages: count_packages(path),
+  })
+}
+
+/// `bin/python` is usually a symlink into the base installation; a dangling
+/// link means the interpreter was removed or upgraded away
+fn has_dangling_interpreter(path: &Path) -> bool {
+  ["bin/python", "bin/python3", "Scripts/python.exe"]
+    .iter()
+    .map(|name| path.join(name))
+    .any(|link| link.symlink_metadata().is_ok() && fs::metadata(&link).is_err())
+}
+
+/// Installed distributions (`*.dist-info`, `*.egg-info`) in site-packages
+fn count_packages(path: &Path) -> usize {
+  let mut site_packages = vec![path.join("Lib").join("site-packages")];
+  for lib in ["lib", "lib64"] {
+    if let Ok(entries) = fs::read_dir(path.join(lib)) {
+      site_packages.extend(
+        entries
+          .filter_map(Result::ok)
+          .filter(|entry| entry.file_name().to_string_lossy().starts_with("python"))
+          .map(|entry| entry.path().join("site-packages")),
+      );
+    }
+  }
+
+  site_packages.sort();
+  site_packages.dedup_by(|a, b| fs::canonicalize(&*a).ok() == fs::canonicalize(&*b).ok());
+
+  site_packages
+    .iter()
+    .filter_map(|dir| fs::read_dir(dir).ok())
+    .flat_map(|entries| entries.filter_map(Result::ok))
+    .filter(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+      name.ends_with(".dist-info") || name.ends_with(".egg-info")
+    })
+    .count()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::services::scanner::{scan_directory, ScanOptions};
+
+  fn make_venv(path: &Path, home: &Path, packages: &[&str]) {
+    let site = path.join("lib/python3.11/site-packages");
+    fs::create_dir_all(&site).unwrap();
+    fs::write(
+      path.join("pyvenv.cfg"),
+      format!(
+        "home = {}\ninclude-system-site-packages = false\nversion = 3.11.4\n",
+        home.display()
+      ),
+    )
+    .unwrap();
+    for package in packages {
+      fs::create_dir_all(site.join(format!("{}.dist-info", package))).unwrap();
+      fs::create_dir_all(site.join(package)).unwrap();
+    }
+  }
+
+  #[test]
+  fn reads_version_and_packages() {
+    let tmp = tempfile::tempdir().unwrap();
+    let venv = tmp.path().join(".venv");
+    make_venv(&venv, tmp.path(), &["requests-2.31.0", "idna-3.4"]);
+
+    let info = analyze_venv(&venv).unwrap();
+
+    assert!(!info.broken);
+    assert_eq!(info.version.as_deref(), Some("3.11.4"));
+    assert_eq!(info.packages, 2);
+    assert_eq!(
+      info.describe(),
+      "Python 3.11.4 virtual environment, 2 packages"
+    );
+  }
+
+  #[test]
+  fn missing_interpreter_is_broken() {
+    let tmp = tempfile::tempdir().unwrap();
+    let venv = tmp.path().join("venv");
+    make_venv(&venv, &tmp.path().join("gone/bin"), &[]);
+
+    let info = analyze_venv(&venv).unwrap();
+
+    assert!(info.broken);
+    assert!(info.describe().contains("gone/bin"));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn dangling_interpreter_link_is_broken() {
+    let tmp = tempfile::tempdir().unwrap();
+    let venv = tmp.path().join("venv");
+    make_venv(&venv, tmp.path(), &[]);
+    fs::create_dir_all(venv.join("bin")).unwrap();
+    std::os::unix::fs::symlink(tmp.path().join("python3.9"), venv.join("bin/python")).unwrap();
+
+    assert!(analyze_venv(&venv).unwrap().broken);
+  }
+
+  #[test]
+  fn scanner_validates_and_preselects() {
+    let tmp = tempfile::tempdir().unwrap();
+    let healthy = tmp.path().join("app/.venv");
+    let broken = tmp.path().join("old/venv");
+    let not_a_venv = tmp.path().join("config/env");
+    make_venv(&healthy, tmp.path(), &["flask-3.0.0"]);
+    make_venv(&broken, &tmp.path().join("missing"), &[]);
+    fs::create_dir_all(&not_a_venv).unwrap();
+
+    let options = ScanOptions {
+      max_depth: 3,
+      ..Default::default()
+    };
+    let items = scan_directory(tmp.path(), &options);
+    let find = |path: &Path| items.iter().find(|item| item.path == path);
+
+    assert!(!find(&healthy).unwrap().selected);
+    assert!(find(&healthy).unwrap().info.contains("1 package"));
+    assert!(find(&broken).unwrap().selected);
+    assert!(find(&not_a_venv).is_none());
+  }
+}
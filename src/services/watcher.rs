@@ -0,0 +1,335 @@
+use crate::core::constants::WATCH_REMEASURE_SECS;
+use crate::core::models::CleanableItem;
+use crate::services::exclude::Exclusions;
+use crate::services::scanner::{
+  get_directory_size, gitignore_patterns, is_live_lockfile, is_scanned_name, match_entry,
+  match_gitignore_entry, ScanOptions,
+};
+use crate::services::tombstone::is_tombstone;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// What a batch of file system events means for the item list
+#[derive(Debug)]
+pub enum WatchChange {
+  /// An artifact appeared or changed size
+  Updated(CleanableItem),
+  /// The path no longer exists
+  Removed(PathBuf),
+}
+
+/// Watch `roots` recursively (inotify on Linux) and forward every changed path.
+/// The watcher stops when the returned handle is dropped.
+pub fn watch_roots(
+  roots: &[PathBuf],
+  sender: mpsc::UnboundedSender<PathBuf>,
+) -> notify::Result<RecommendedWatcher> {
+  let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+    let Ok(event) = result else {
+      return;
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+      return;
+    }
+    for path in event.paths {
+      let _ = sender.send(path);
+    }
+  })?;
+
+  for root in roots {
+    watcher.watch(root, RecursiveMode::Recursive)?;
+  }
+  Ok(watcher)
+}
+
+/// How a root decides which paths are artifacts, built once per watcher
+enum Matcher {
+  /// The regular `CLEANABLE_PATTERNS` scan
+  Patterns,
+  /// `--gitignore`: the patterns of the root's `.gitignore`
+  Gitignore(Vec<String>),
+}
+
+struct WatchedRoot {
+  path: PathBuf,
+  exclusions: Exclusions,
+  matcher: Matcher,
+}
+
+/// Turns changed paths into updates of the artifacts containing them.
+///
+/// Measuring a directory walks all of it, so an artifact that keeps changing
+/// (a `target` during `cargo build`) is re-measured at most once per
+/// `WATCH_REMEASURE_SECS`; changes in between are held back until it is due.
+pub struct ChangeResolver {
+  roots: Vec<WatchedRoot>,
+  options: ScanOptions,
+  last_measured: HashMap<PathBuf, Instant>,
+  /// Artifacts that changed while their last measurement was too recent
+  pending: HashMap<PathBuf, CleanableItem>,
+}
+
+impl ChangeResolver {
+  pub fn new(roots: &[PathBuf], options: &ScanOptions) -> Self {
+    let roots = roots
+      .iter()
+      .map(|root| WatchedRoot {
+        path: root.clone(),
+        exclusions: Exclusions::new(root, options),
+        matcher: if options.use_gitignore {
+          Matcher::Gitignore(gitignore_patterns(root))
+        } else {
+          Matcher::Patterns
+        },
+      })
+      .collect();
+
+    Self {
+      roots,
+      options: options.clone(),
+      last_measured: HashMap::new(),
+      pending: HashMap::new(),
+    }
+  }
+
+  /// Updates for a batch of changed paths, plus the held-back artifacts that
+  /// are due by now. Every artifact appears at most once.
+  pub fn resolve(&mut self, paths: impl IntoIterator<Item = PathBuf>) -> Vec<WatchChange> {
+    let now = Instant::now();
+    let mut changes = Vec::new();
+    let mut due: HashMap<PathBuf, CleanableItem> = HashMap::new();
+
+    for path in paths {
+      match self.artifact_of(&path) {
+        Some(item) if item.path.is_dir() => {
+          if self.is_due(&item.path, now) {
+            self.pending.remove(&item.path);
+            due.insert(item.path.clone(), item);
+          } else {
+            self.pending.insert(item.path.clone(), item);
+          }
+        }
+        Some(item) => changes.push(WatchChange::Updated(item)),
+        None if fs::symlink_metadata(&path).is_err() => {
+          self
+            .pending
+            .retain(|pending, _| !pending.starts_with(&path));
+          changes.push(WatchChange::Removed(path));
+        }
+        None => {}
+      }
+    }
+
+    let ready: Vec<PathBuf> = self
+      .pending
+      .keys()
+      .filter(|path| self.is_due(path, now))
+      .cloned()
+      .collect();
+    for path in ready {
+      if let Some(item) = self.pending.remove(&path) {
+        due.insert(path, item);
+      }
+    }
+
+    for (path, mut item) in due {
+      item.size = get_directory_size(&path);
+      self.last_measured.insert(path, Instant::now());
+      changes.push(WatchChange::Updated(item));
+    }
+    changes
+  }
+
+  /// When the earliest held-back artifact may be measured again
+  pub fn next_due(&self) -> Option<Instant> {
+    self
+      .pending
+      .keys()
+      .filter_map(|path| self.last_measured.get(path))
+      .map(|measured| *measured + remeasure_interval())
+      .min()
+  }
+
+  fn is_due(&self, path: &Path, now: Instant) -> bool {
+    self
+      .last_measured
+      .get(path)
+      .is_none_or(|measured| now.duration_since(*measured) >= remeasure_interval())
+  }
+
+  /// The outermost existing ancestor of `path` under one of the roots that the
+  /// scanner would report, not yet measured if it is a directory. A path
+  /// deleted inside an artifact resolves to that artifact, so it shrinks.
+  fn artifact_of(&self, path: &Path) -> Option<CleanableItem> {
+    let root = self
+      .roots
+      .iter()
+      .find(|root| path.starts_with(&root.path))?;
+    let relative = path.strip_prefix(&root.path).ok()?;
+
+    let mut current = root.path.clone();
+    for (depth, component) in relative.components().enumerate() {
+      if depth >= self.options.max_depth {
+        return None;
+      }
+
+      let name = component.as_os_str();
+      if is_tombstone(name) || name == ".git" {
+        return None;
+      }
+      if matches!(root.matcher, Matcher::Patterns) && !is_scanned_name(&name.to_string_lossy()) {
+        return None;
+      }
+      current.push(name);
+
+      let metadata = fs::symlink_metadata(&current).ok()?;
+      if root.exclusions.is_excluded(&current, metadata.is_dir()) {
+        return None;
+      }
+      let size = if metadata.is_file() {
+        metadata.len()
+      } else {
+        0
+      };
+      let item = match &root.matcher {
+        Matcher::Patterns => match_entry(&current, size, self.options.include_lockfiles),
+        Matcher::Gitignore(patterns) => {
+          if !self.options.include_lockfiles && is_live_lockfile(&current) {
+            return None;
+          }
+          match_gitignore_entry(&root.path, patterns, &current, size)
+        }
+      };
+      if let Some(mut item) = item {
+        item.root = Some(root.path.clone());
+        return Some(item);
+      }
+    }
+
+    None
+  }
+}
+
+fn remeasure_interval() -> Duration {
+  Duration::from_secs(WATCH_REMEASURE_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options() -> ScanOptions {
+    ScanOptions {
+      max_depth: 6,
+      ..Default::default()
+    }
+  }
+
+  fn updated(changes: Vec<WatchChange>) -> CleanableItem {
+    match <[WatchChange; 1]>::try_from(changes) {
+      Ok([WatchChange::Updated(item)]) => item,
+      other => panic!("unexpected changes: {:?}", other),
+    }
+  }
+
+  fn cargo_project(root: &Path) -> PathBuf {
+    let target = root.join("app/target");
+    fs::create_dir_all(target.join("debug/deps")).unwrap();
+    fs::write(root.join("app/Cargo.toml"), "[package]").unwrap();
+    fs::write(target.join("debug/deps/libfoo.rlib"), vec![0u8; 300]).unwrap();
+    target
+  }
+
+  #[test]
+  fn changes_inside_artifacts_update_the_artifact() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    let target = cargo_project(&root);
+    let mut resolver = ChangeResolver::new(std::slice::from_ref(&root), &options());
+
+    let item = updated(resolver.resolve([
+      target.join("debug/deps/libfoo.rlib"),
+      target.join("debug/deps"),
+    ]));
+
+    assert_eq!(item.path, target);
+    assert_eq!(item.size, 300);
+    assert_eq!(item.root, Some(root));
+  }
+
+  #[test]
+  fn deletions_inside_artifacts_shrink_them_once_due() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    let target = cargo_project(&root);
+    let rlib = target.join("debug/deps/libfoo.rlib");
+    let mut resolver = ChangeResolver::new(std::slice::from_ref(&root), &options());
+    assert_eq!(updated(resolver.resolve([rlib.clone()])).size, 300);
+
+    fs::remove_file(&rlib).unwrap();
+
+    // Measured too recently: held back until the interval has passed
+    assert!(resolver.resolve([rlib.clone()]).is_empty());
+    assert!(resolver.next_due().is_some());
+
+    resolver.last_measured.clear();
+    let item = updated(resolver.resolve([]));
+    assert_eq!(item.path, target);
+    assert_eq!(item.size, 0);
+    assert!(resolver.next_due().is_none());
+  }
+
+  #[test]
+  fn gitignore_mode_uses_the_root_gitignore() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    fs::create_dir_all(root.join("app/generated")).unwrap();
+    fs::write(root.join("app/generated/api.ts"), vec![0u8; 50]).unwrap();
+    fs::write(root.join(".gitignore"), "generated\n").unwrap();
+    let options = ScanOptions {
+      use_gitignore: true,
+      ..options()
+    };
+    let mut resolver = ChangeResolver::new(std::slice::from_ref(&root), &options);
+
+    let item = updated(resolver.resolve([root.join("app/generated/api.ts")]));
+
+    assert_eq!(item.path, root.join("app/generated"));
+    assert_eq!(item.size, 50);
+  }
+
+  #[test]
+  fn ignores_sources_and_reports_removals() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    fs::create_dir_all(root.join("app/src")).unwrap();
+    fs::write(root.join("app/src/main.rs"), "fn main() {}").unwrap();
+    let mut resolver = ChangeResolver::new(std::slice::from_ref(&root), &options());
+
+    assert!(resolver.resolve([root.join("app/src/main.rs")]).is_empty());
+    assert!(matches!(
+      resolver.resolve([root.join("app/node_modules")]).as_slice(),
+      [WatchChange::Removed(_)]
+    ));
+  }
+
+  #[tokio::test]
+  async fn forwards_file_system_events() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().canonicalize().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let _watcher = watch_roots(std::slice::from_ref(&root), tx).unwrap();
+
+    fs::create_dir_all(root.join("node_modules")).unwrap();
+
+    let path = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+    assert!(path.starts_with(&root));
+  }
+}
@@ -1,4 +1,4 @@
-use crate::core::models::{App, AppState, CleanableItem};
+use crate::core::models::{App, AppState, CleanableItem, ScanScope};
 use ratatui::prelude::*;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
@@ -34,7 +34,7 @@ pub fn draw(f: &mut Frame, app: &App) {
     .margin(1)
     .constraints(
       [
-        Constraint::Length(3),
+        Constraint::Length(if app.disks.is_empty() { 3 } else { 4 }),
         Constraint::Min(5),
         Constraint::Length(3),
       ]
@@ -50,6 +50,7 @@ pub fn draw(f: &mut Frame, app: &App) {
     AppState::Cleaning => draw_cleaning_view(f, app, chunks[1]),
     AppState::Complete => draw_complete_view(f, app, chunks[1]),
     AppState::Help => draw_help_view(f, app, chunks[1]),
+    AppState::Plan => draw_plan_view(f, app, chunks[1]),
   }
 
   draw_footer(f, app, chunks[2]);
@@ -62,6 +63,7 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
     AppState::Cleaning => "DevTidy - Cleaning",
     AppState::Complete => "DevTidy - Complete",
     AppState::Help => "DevTidy - Help",
+    AppState::Plan => "DevTidy - Review Free-Space Plan",
   };
 
   let header_color = match app.state {
@@ -70,9 +72,40 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
     AppState::Cleaning => colors::SECONDARY,
     AppState::Complete => colors::SUCCESS,
     AppState::Help => colors::INFO,
+    AppState::Plan => colors::SECONDARY,
   };
 
-  let header = Paragraph::new(title)
+  let mut spans = vec![Span::raw(title)];
+  if matches!(app.state, AppState::Scanning | AppState::Selecting) {
+    spans.push(Span::raw("   "));
+    for scope in ScanScope::ALL {
+      let style = if scope == app.scope {
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+      } else {
+        Style::default().fg(colors::TEXT_DIM)
+      };
+      spans.push(Span::styled(format!(" {} ", scope.title()), style));
+    }
+  }
+  if app.watching {
+    match &app.watch_error {
+      Some(error) => spans.push(Span::styled(
+        format!("   watch failed: {}", error),
+        Style::default().fg(colors::ERROR),
+      )),
+      None => spans.push(Span::styled(
+        "   ● watching",
+        Style::default().add_modifier(Modifier::BOLD),
+      )),
+    }
+  }
+
+  let mut lines = vec![Line::from(spans)];
+  if !app.disks.is_empty() {
+    lines.push(disk_line(app));
+  }
+
+  let header = Paragraph::new(lines)
     .style(Style::default().fg(colors::TEXT).bg(header_color))
     .alignment(Alignment::Center)
     .block(Block::default().borders(Borders::ALL));
@@ -80,6 +113,43 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
   f.render_widget(header, area);
 }
 
+/// Free space per filesystem, and what it will be once the selection is cleaned
+fn disk_line(app: &App) -> Line<'static> {
+  let mut spans = Vec::new();
+  for (i, disk) in app.disks.iter().enumerate() {
+    if i > 0 {
+      spans.push(Span::raw("  |  "));
+    }
+
+    let free = format!(
+      "{}: {} free of {}",
+      disk.mount_point.display(),
+      human_bytes::human_bytes(disk.available as f64),
+      human_bytes::human_bytes(disk.total as f64)
+    );
+    if disk.is_low() {
+      spans.push(Span::styled(
+        format!(" {} ", free),
+        Style::default()
+          .fg(colors::BACKGROUND)
+          .bg(colors::WARNING)
+          .add_modifier(Modifier::BOLD),
+      ));
+    } else {
+      spans.push(Span::raw(free));
+    }
+
+    let projected = disk.projected_available(&app.mounts, &app.items);
+    if projected > disk.available {
+      spans.push(Span::raw(format!(
+        " → {} after cleaning",
+        human_bytes::human_bytes(projected as f64)
+      )));
+    }
+  }
+  Line::from(spans)
+}
+
 fn draw_scanning_view(f: &mut Frame, app: &App, area: Rect) {
   let chunks = Layout::default()
     .direction(Direction::Vertical)
@@ -108,7 +178,16 @@ fn draw_scanning_view(f: &mut Frame, app: &App, area: Rect) {
     .alignment(Alignment::Left);
   f.render_widget(spinner, chunks[0]);
 
-  let current_dir = Paragraph::new(format!("Directory: {}", app.current_dir.display()))
+  let location = match app.scope {
+    ScanScope::Projects if app.roots.len() > 1 => format!("Roots: {}", app.display_roots()),
+    ScanScope::Projects => format!("Directory: {}", app.display_roots()),
+    ScanScope::Global => "Looking for global developer caches in your home directory".to_string(),
+    ScanScope::Toolchains => format!(
+      "Looking for installed toolchains (pins read from {})",
+      app.display_roots()
+    ),
+  };
+  let current_dir = Paragraph::new(location)
     .style(Style::default().fg(colors::TEXT))
     .alignment(Alignment::Left);
   f.render_widget(current_dir, chunks[1]);
@@ -154,7 +233,9 @@ fn draw_selecting_view(f: &mut Frame, app: &App, area: Rect) {
     .split(area);
 
   fn generate_style(item: &CleanableItem, line: i8) -> Style {
-    if item.selected {
+    if item.protected || item.stale {
+      Style::default().fg(colors::TEXT_DIM)
+    } else if item.selected {
       Style::default().fg(colors::SUCCESS)
     } else {
       Style::default().fg(if line == 1 {
@@ -169,13 +250,58 @@ fn draw_selecting_view(f: &mut Frame, app: &App, area: Rect) {
     .items
     .iter()
     .map(|item| {
-      let prefix = if item.selected { "✓ " } else { "" };
+      let prefix = if item.selected {
+        "✓ "
+      } else if item.protected {
+        "[protected] "
+      } else {
+        ""
+      };
       let first_line = format!("{}{}", prefix, item.path.display());
-      let second_line = format!("└── {} - {}", item.display_info(), item.display_size());
+      let stale = if item.stale { " (refreshing...)" } else { "" };
+      let second_line = format!(
+        "└── {} - {}{}",
+        item.display_info(),
+        item.display_size(),
+        stale
+      );
+
+      let mut first_spans = vec![Span::styled(first_line, generate_style(item, 1))];
+      if let Some(root) = item.root.as_ref().filter(|_| app.roots.len() > 1) {
+        first_spans.push(Span::styled(
+          format!("  [{}]", root.display()),
+          Style::default().fg(colors::INFO),
+        ));
+      }
+      if item.orphaned {
+        first_spans.push(Span::raw(" "));
+        first_spans.push(Span::styled(
+          " orphaned ",
+          Style::default()
+            .fg(colors::BACKGROUND)
+            .bg(colors::WARNING)
+            .add_modifier(Modifier::BOLD),
+        ));
+      }
+
+      let mut second_spans = vec![Span::styled(second_line, generate_style(item, 2))];
+      if let Some(verdict) = app.verdicts.get(&item.path) {
+        let color = if verdict.preselects() {
+          colors::SUCCESS
+        } else if verdict.safe_to_delete {
+          colors::INFO
+        } else {
+          colors::WARNING
+        };
+        second_spans.push(Span::styled(
+          format!("  AI: {}", verdict.summary()),
+          Style::default().fg(color),
+        ));
+      }
 
       let text = Text::from(vec![
-        Line::from(Span::styled(first_line, generate_style(item, 1))),
-        Line::from(Span::styled(second_line, generate_style(item, 2))),
+        Line::from(first_spans),
+        Line::from(second_spans),
         Line::from(Span::raw("")),
       ]);
 
@@ -183,7 +309,16 @@ fn draw_selecting_view(f: &mut Frame, app: &App, area: Rect) {
     })
     .collect();
 
-  let list_area = chunks[0];
+  let list_area = if app.show_explanation {
+    let columns = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+      .split(chunks[0]);
+    draw_explanation_panel(f, app, columns[1]);
+    columns[0]
+  } else {
+    chunks[0]
+  };
 
   let current_selection_index = app.list_state.selected();
   let highlight_style = if current_selection_index
@@ -225,7 +360,30 @@ fn draw_selecting_view(f: &mut Frame, app: &App, area: Rect) {
     let selected_count = app.selected_count();
     let selected_size = app.selected_size();
 
-    let status_text = if selected_count > 0 {
+    let status_text = if let Some(input) = &app.budget_input {
+      match &app.budget_error {
+        Some(error) => format!("Free how much? {}_ | {}", input, error),
+        None => format!(
+          "Free how much? {}_ (e.g. 20G; Enter: plan, Esc: cancel)",
+          input
+        ),
+      }
+    } else if let Some(review) = &app.ai_review {
+      format!(
+        "Asking {} about each item... {}/{} | {} selected",
+        review.model.as_deref().unwrap_or("the AI"),
+        review.done,
+        review.total,
+        selected_count
+      )
+    } else if let Some(error) = &app.ai_error {
+      format!("AI review: {}", error)
+    } else if app.scanning {
+      format!(
+        "Showing previous results, refreshing in the background... | {} selected",
+        selected_count
+      )
+    } else if selected_count > 0 {
       format!(
         "{} | Total: {} ({})",
         app.get_selected_info(),
@@ -257,13 +415,115 @@ fn draw_selecting_view(f: &mut Frame, app: &App, area: Rect) {
   }
 }
 
+/// The AI explanation of the highlighted item, streaming in as it arrives
+fn draw_explanation_panel(f: &mut Frame, app: &App, area: Rect) {
+  let item = app.list_state.selected().and_then(|i| app.items.get(i));
+  let title = match item.and_then(|item| item.path.file_name()) {
+    Some(name) => format!("AI: {}", name.to_string_lossy()),
+    None => "AI".to_string(),
+  };
+
+  let lines = match item.and_then(|item| app.explanations.get(&item.path)) {
+    None => vec![Line::from(Span::styled(
+      "Press e to ask the AI about this item",
+      Style::default().fg(colors::TEXT_DIM),
+    ))],
+    Some(explanation) => {
+      let mut lines =
+        Text::styled(explanation.text.trim(), Style::default().fg(colors::TEXT)).lines;
+      if let Some(error) = &explanation.error {
+        lines.push(Line::from(Span::styled(
+          format!("Error: {} (press e to retry)", error),
+          Style::default().fg(colors::ERROR),
+        )));
+      } else if !explanation.done {
+        let frame = SPINNER_FRAMES
+          [(app.scan_start_time.elapsed().as_millis() / 100) as usize % SPINNER_FRAMES.len()];
+        lines.push(Line::from(Span::styled(
+          format!("{} thinking...", frame),
+          Style::default().fg(colors::INFO),
+        )));
+      }
+      lines
+    }
+  };
+
+  let panel = Paragraph::new(lines)
+    .block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(colors::INFO)),
+    )
+    .wrap(Wrap { trim: false });
+  f.render_widget(panel, area);
+}
+
+fn draw_plan_view(f: &mut Frame, app: &App, area: Rect) {
+  let Some(plan) = &app.budget else {
+    return;
+  };
+
+  let summary_color = if plan.reached() {
+    colors::SUCCESS
+  } else {
+    colors::WARNING
+  };
+  let mut lines = vec![
+    Line::from(Span::styled(
+      plan.summary(),
+      Style::default()
+        .fg(summary_color)
+        .add_modifier(Modifier::BOLD),
+    )),
+    Line::from(""),
+  ];
+  for choice in &plan.choices {
+    lines.push(Line::from(vec![
+      Span::styled(
+        format!("{:>10}  ", human_bytes::human_bytes(choice.size as f64)),
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::styled(
+        choice.path.display().to_string(),
+        Style::default().fg(colors::TEXT),
+      ),
+    ]));
+    lines.push(Line::from(Span::styled(
+      format!("{:>10}  └── {}", "", choice.reason),
+      Style::default().fg(colors::TEXT_DIM),
+    )));
+  }
+
+  let plan_view = Paragraph::new(lines)
+    .block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title("Cheapest items to free the requested space")
+        .border_style(Style::default().fg(colors::SECONDARY)),
+    )
+    .wrap(Wrap { trim: false })
+    .scroll((app.plan_scroll as u16, 0));
+  f.render_widget(plan_view, area);
+}
+
 fn draw_cleaning_view(f: &mut Frame, app: &App, area: Rect) {
   let chunks = Layout::default()
     .direction(Direction::Vertical)
-    .constraints([Constraint::Length(1), Constraint::Length(3)])
+    .constraints([
+      Constraint::Length(1),
+      Constraint::Length(3),
+      Constraint::Length(4),
+      Constraint::Min(0),
+    ])
     .split(area);
 
-  let status = Paragraph::new("Cleaning selected items...")
+  let status_text = if app.clean_cancelled {
+    "Cancelling after the current files..."
+  } else {
+    "Cleaning selected items..."
+  };
+  let status = Paragraph::new(status_text)
     .style(Style::default().fg(colors::WARNING))
     .alignment(Alignment::Center);
   f.render_widget(status, chunks[0]);
@@ -276,18 +536,41 @@ fn draw_cleaning_view(f: &mut Frame, app: &App, area: Rect) {
         .border_style(Style::default().fg(colors::PRIMARY)),
     )
     .gauge_style(Style::default().fg(colors::SUCCESS).bg(Color::Black))
+    .label(format!(
+      "{} / {}",
+      human_bytes::human_bytes(app.clean_removed_bytes as f64),
+      human_bytes::human_bytes(app.clean_total_bytes as f64)
+    ))
     .ratio(app.progress as f64);
   f.render_widget(gauge, chunks[1]);
 
-  if let Some(item) = &app.processing_item {
-    let status = Paragraph::new(format!("Processing: {}", item))
+  let eta = match app.clean_eta() {
+    Some(eta) => format!("{}s", eta.as_secs()),
+    None => "estimating...".to_string(),
+  };
+  let stats = Paragraph::new(format!(
+    "Files removed: {}\nItems finished: {}/{} ({} parallel)\nElapsed: {}s | ETA: {}",
+    app.clean_removed_files,
+    app.clean_results.len(),
+    app.clean_items_total,
+    app.clean_jobs,
+    app.clean_start_time.elapsed().as_secs(),
+    eta
+  ))
+  .style(Style::default().fg(colors::TEXT))
+  .alignment(Alignment::Left);
+  f.render_widget(stats, chunks[2]);
+
+  if !app.clean_active_items.is_empty() {
+    let active = app
+      .clean_active_items
+      .iter()
+      .map(|path| Line::from(format!("Processing: {}", path)))
+      .collect::<Vec<_>>();
+    let status = Paragraph::new(active)
       .style(Style::default().fg(colors::TEXT_DIM))
       .alignment(Alignment::Left);
-    let status_area = chunks[1].inner(Margin {
-      vertical: 2,
-      horizontal: 0,
-    });
-    f.render_widget(status, status_area);
+    f.render_widget(status, chunks[3]);
   }
 }
 
@@ -311,26 +594,53 @@ fn draw_complete_view(f: &mut Frame, app: &App, area: Rect) {
     .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
     .split(chunks[1]);
 
-  let cleaned_info = Paragraph::new(format!(
+  let mut results_text = format!(
     "\nCleaned: {}\n\nOriginal items: {}\nRemaining items: {}",
     human_bytes::human_bytes(app.cleaned_size as f64),
     app.scanned_items,
     app.items.len()
-  ))
-  .style(Style::default().fg(colors::TEXT))
-  .alignment(Alignment::Left)
-  .block(
-    Block::default()
-      .borders(Borders::ALL)
-      .title("Results")
-      .border_style(Style::default().fg(colors::SUCCESS)),
   );
+  if app.partially_cleaned > 0 {
+    results_text.push_str(&format!("\nPartially removed: {}", app.partially_cleaned));
+  }
+  if app.natively_cleaned > 0 {
+    results_text.push_str(&format!(
+      "\nCleaned by native tools: {}",
+      app.natively_cleaned
+    ));
+  }
+  if app.deferred_cleans > 0 {
+    results_text.push_str(&format!(
+      "\nDeferred to next start: {}",
+      app.deferred_cleans
+    ));
+  }
+  if app.failed_to_clean > 0 {
+    results_text.push_str(&format!("\nFailed: {}", app.failed_to_clean));
+  }
+
+  let cleaned_info = Paragraph::new(results_text)
+    .style(Style::default().fg(colors::TEXT))
+    .alignment(Alignment::Left)
+    .block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title("Results")
+        .border_style(Style::default().fg(colors::SUCCESS)),
+    );
   f.render_widget(cleaned_info, stats_chunks[0]);
 
-  let info_text = "\nAll selected items have been cleaned.\n\nPress any key to return to the list view\nor 'q' to quit the application.";
+  let info_text = match &app.last_clean_error {
+    Some(error) => format!(
+      "\nSome items could not be fully removed:\n{}\n\nPress any key to return to the list view\nor 'q' to quit the application.",
+      error
+    ),
+    None => "\nAll selected items have been cleaned.\n\nPress any key to return to the list view\nor 'q' to quit the application.".to_string(),
+  };
   let info = Paragraph::new(info_text)
     .style(Style::default().fg(colors::TEXT))
     .alignment(Alignment::Left)
+    .wrap(Wrap { trim: false })
     .block(
       Block::default()
         .borders(Borders::ALL)
@@ -380,10 +690,30 @@ fn draw_help_view(f: &mut Frame, app: &App, area: Rect) {
       Span::styled("  c     ", Style::default().fg(colors::PRIMARY)),
       Span::raw("Clean selected items"),
     ]),
+    Line::from(vec![
+      Span::styled("  x/Esc ", Style::default().fg(colors::PRIMARY)),
+      Span::raw("Cancel a running clean (stops between files)"),
+    ]),
+    Line::from(vec![
+      Span::styled("  f     ", Style::default().fg(colors::PRIMARY)),
+      Span::raw("Free a given amount of space: select the cheapest items and review why"),
+    ]),
+    Line::from(vec![
+      Span::styled("  a     ", Style::default().fg(colors::PRIMARY)),
+      Span::raw("Ask the AI whether each item is safe to delete; selects the confident ones"),
+    ]),
+    Line::from(vec![
+      Span::styled("  e     ", Style::default().fg(colors::PRIMARY)),
+      Span::raw("Ask the AI about the highlighted item; press again to hide the answer"),
+    ]),
     Line::from(vec![
       Span::styled("  r     ", Style::default().fg(colors::PRIMARY)),
       Span::raw("Rescan directory for items"),
     ]),
+    Line::from(vec![
+      Span::styled("  Tab   ", Style::default().fg(colors::PRIMARY)),
+      Span::raw("Switch between project artifacts, global caches and toolchains"),
+    ]),
     Line::from(vec![
       Span::styled("  j/↓   ", Style::default().fg(colors::PRIMARY)),
       Span::raw("Move down"),
@@ -479,6 +809,32 @@ fn draw_help_view(f: &mut Frame, app: &App, area: Rect) {
       ),
       Span::raw(" - Set maximum scan depth (default: 6)"),
     ]),
+    Line::from(vec![
+      Span::styled(
+        "  dd -j, --jobs <JOBS>",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(" - Number of items removed in parallel (default: 4)"),
+    ]),
+    Line::from(vec![
+      Span::styled("  dd --native", Style::default().fg(colors::PRIMARY)),
+      Span::raw(" - Use cargo clean, gradle clean, ... when the tool is installed"),
+    ]),
+    Line::from(vec![
+      Span::styled(
+        "  dd --exclude <GLOB>",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(" - Skip matching paths; see also .devtidyignore and protected paths"),
+    ]),
+    Line::from(vec![
+      Span::styled("  dd --free <SIZE>", Style::default().fg(colors::PRIMARY)),
+      Span::raw(" - Preselect the cheapest items that free SIZE (e.g. 20G)"),
+    ]),
+    Line::from(vec![
+      Span::styled("  dd --lockfiles", Style::default().fg(colors::PRIMARY)),
+      Span::raw(" - Also list lockfiles of projects that still have a manifest"),
+    ]),
     Line::from(vec![
       Span::styled("  dd -v, --version", Style::default().fg(colors::PRIMARY)),
       Span::raw(" - Show version information"),
@@ -487,6 +843,28 @@ fn draw_help_view(f: &mut Frame, app: &App, area: Rect) {
       Span::styled("  dd -i, --install", Style::default().fg(colors::PRIMARY)),
       Span::raw(" - Install devtidy globally"),
     ]),
+    Line::from(vec![
+      Span::styled("  dd global [--list]", Style::default().fg(colors::PRIMARY)),
+      Span::raw(" - Find and clean global developer caches (~/.cargo, ~/.npm, ...)"),
+    ]),
+    Line::from(vec![
+      Span::styled(
+        "  dd toolchains [--list|--prune]",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(" - Prune old rustup, nvm, pyenv and SDKMAN versions"),
+    ]),
+    Line::from(vec![
+      Span::styled(
+        "  dd git [--prune] [--gc]",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(" - Stale worktrees and .git sizes"),
+    ]),
+    Line::from(vec![
+      Span::styled("  dd watch", Style::default().fg(colors::PRIMARY)),
+      Span::raw(" - Keep the list open and follow artifact sizes live"),
+    ]),
     Line::from(""),
     Line::from(Span::styled(
       "AI Commands:",
@@ -502,8 +880,20 @@ fn draw_help_view(f: &mut Frame, app: &App, area: Rect) {
       Span::raw(" - Explain what a folder is used for using AI"),
     ]),
     Line::from(vec![
-      Span::styled("  dd ai-suggest", Style::default().fg(colors::PRIMARY)),
-      Span::raw(" - Get AI suggestions for cleaning the current project"),
+      Span::styled(
+        "  dd ai-suggest [PATH] [--clean]",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(
+        " - Get AI verdicts on the scan, one prompt per project; --clean removes the safe ones",
+      ),
+    ]),
+    Line::from(vec![
+      Span::styled(
+        "  dd ai-report [PATH] [-o FILE]",
+        Style::default().fg(colors::PRIMARY),
+      ),
+      Span::raw(" - Write a per-project cleanup report with an AI plan"),
     ]),
     Line::from(vec![
       Span::styled("  dd ai-chat", Style::default().fg(colors::PRIMARY)),
@@ -521,11 +911,7 @@ fn draw_help_view(f: &mut Frame, app: &App, area: Rect) {
 
   let visible_height = inner_area.height as usize;
   let total_lines = help_lines.len();
-  let max_scroll = if total_lines > visible_height {
-    total_lines - visible_height
-  } else {
-    0
-  };
+  let max_scroll = total_lines.saturating_sub(visible_height);
 
   let scroll = app.help_scroll.min(max_scroll);
 
@@ -599,11 +985,12 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
   let footer_text = match app.state {
     AppState::Scanning => "",
     AppState::Selecting => {
-      "↑/↓: navigate | Space: select | c: clean | r: rescan | q: quit | h: help"
+      "↑/↓: navigate | Space: select | c: clean | f: free space | a: AI review | e: explain | r: rescan | Tab: switch tab | q: quit | h: help"
     }
-    AppState::Cleaning => "q: quit",
+    AppState::Cleaning => "Esc/x: cancel | q: cancel and quit",
     AppState::Complete => "any key: return | q: quit",
     AppState::Help => "↑/↓/Mouse: scroll | PageUp/Down: fast scroll | h/Esc: back",
+    AppState::Plan => "↑/↓: scroll | c/Enter: clean | Esc: adjust selection | q: quit",
   };
 
   let footer = Paragraph::new(footer_text)
@@ -619,6 +1006,11 @@ fn draw_footer(f: &mut Frame, app: &App, area: Rect) {
     let status_text = match app.state {
       AppState::Scanning => "Scanning for items...".to_string(),
       AppState::Cleaning => "Cleaning selected items...".to_string(),
+      AppState::Plan => app
+        .budget
+        .as_ref()
+        .map(|plan| plan.summary())
+        .unwrap_or_default(),
       AppState::Complete => format!(
         "Total space cleaned: {}",
         human_bytes::human_bytes(app.cleaned_size as f64)